@@ -0,0 +1,102 @@
+use std::{
+    process::Command,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+    time::Duration,
+};
+
+/// How often the accent color is read again, so a change in the OS settings shows up
+/// without a restart.
+const REFRESH_INTERVAL: Duration = Duration::from_secs(60);
+
+static ACCENT: Mutex<Option<[u8; 3]>> = Mutex::new(None);
+static WATCHING: AtomicBool = AtomicBool::new(false);
+
+fn output(program: &str, args: &[&str]) -> Option<String> {
+    let out = Command::new(program).args(args).output().ok()?;
+    out.status
+        .success()
+        .then(|| String::from_utf8_lossy(&out.stdout).into_owned())
+}
+
+/// The accent stored as an `0xAABBGGRR` DWORD in the registry.
+#[cfg(target_os = "windows")]
+fn read() -> Option<[u8; 3]> {
+    let out = output(
+        "reg",
+        &[
+            "query",
+            r"HKCU\Software\Microsoft\Windows\DWM",
+            "/v",
+            "AccentColor",
+        ],
+    )?;
+    let hex = out.split_whitespace().find_map(|w| w.strip_prefix("0x"))?;
+    let [r, g, b, _] = u32::from_str_radix(hex, 16).ok()?.to_le_bytes();
+    Some([r, g, b])
+}
+
+/// macOS only offers a fixed set of accents, stored as a number. Blue, the default,
+/// isn't stored at all.
+#[cfg(target_os = "macos")]
+fn read() -> Option<[u8; 3]> {
+    let value = output("defaults", &["read", "-g", "AppleAccentColor"]);
+    Some(match value.as_deref().map(str::trim) {
+        Some("-1") => [142, 142, 147],
+        Some("0") => [255, 82, 89],
+        Some("1") => [247, 130, 27],
+        Some("2") => [255, 199, 38],
+        Some("3") => [98, 186, 70],
+        Some("5") => [165, 80, 167],
+        Some("6") => [247, 79, 158],
+        _ => [0, 122, 255],
+    })
+}
+
+/// GNOME names its accent, KDE stores it as `AccentColor=r,g,b` in kdeglobals.
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+fn read() -> Option<[u8; 3]> {
+    let gnome = output(
+        "gsettings",
+        &["get", "org.gnome.desktop.interface", "accent-color"],
+    );
+    let named = match gnome.as_deref().map(|s| s.trim().trim_matches('\'')) {
+        Some("blue") => Some([53, 132, 228]),
+        Some("teal") => Some([33, 144, 164]),
+        Some("green") => Some([58, 148, 74]),
+        Some("yellow") => Some([200, 136, 0]),
+        Some("orange") => Some([237, 91, 0]),
+        Some("red") => Some([230, 45, 66]),
+        Some("pink") => Some([213, 97, 153]),
+        Some("purple") => Some([145, 65, 172]),
+        Some("slate") => Some([111, 131, 150]),
+        _ => None,
+    };
+    named.or_else(|| {
+        let path = directories_next::BaseDirs::new()?
+            .config_dir()
+            .join("kdeglobals");
+        let contents = std::fs::read_to_string(path).ok()?;
+        let value = contents
+            .lines()
+            .find_map(|line| line.trim().strip_prefix("AccentColor="))?;
+        let mut parts = value.split(',').map(|n| n.trim().parse::<u8>().ok());
+        Some([parts.next()??, parts.next()??, parts.next()??])
+    })
+}
+
+/// The operating system's accent color, if it has one. The first call starts keeping
+/// an eye on it in the background, so it may take a moment to show up and changes
+/// are picked up within a minute.
+pub fn system_accent() -> Option<[u8; 3]> {
+    if !WATCHING.swap(true, Ordering::Relaxed) {
+        std::thread::spawn(|| loop {
+            let accent = read();
+            *ACCENT.lock().unwrap_or_else(|e| e.into_inner()) = accent;
+            std::thread::sleep(REFRESH_INTERVAL);
+        });
+    }
+    *ACCENT.lock().unwrap_or_else(|e| e.into_inner())
+}
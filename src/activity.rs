@@ -0,0 +1,46 @@
+use std::collections::VecDeque;
+
+use chrono::{DateTime, Utc};
+
+/// How many entries are kept.
+const CAPACITY: usize = 50;
+
+#[derive(Clone, serde::Deserialize, serde::Serialize)]
+pub struct Entry {
+    pub at: DateTime<Utc>,
+    pub text: String,
+}
+
+/// The most recent things that happened: timer events and the results of syncing,
+/// reports and other background work. Unlike notifications these stay around, so
+/// they can be looked at after being away.
+#[derive(Default, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct ActivityLog {
+    entries: VecDeque<Entry>,
+}
+
+impl ActivityLog {
+    pub fn push(&mut self, text: impl Into<String>) {
+        if self.entries.len() == CAPACITY {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(Entry {
+            at: Utc::now(),
+            text: text.into(),
+        });
+    }
+
+    /// Newest first.
+    pub fn entries(&self) -> impl Iterator<Item = &Entry> {
+        self.entries.iter().rev()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
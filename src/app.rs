@@ -1,37 +1,417 @@
-use std::{fmt::Display, time::Duration};
+use std::{
+    io,
+    path::PathBuf,
+    sync::mpsc,
+    time::{Duration, Instant},
+};
 
-use egui::{Button, Color32};
+use chrono::{Datelike, Timelike};
+use egui::Color32;
 use notify_rust::Notification;
 
+use self::confetti::Confetti;
+use crate::{
+    accent,
+    activity::ActivityLog,
+    app_usage::AppSampler,
+    blocker,
+    clock::format_time,
+    event_log,
+    events::{EventBus, TimerEvent},
+    gcal::{self, CalendarConfig, CalendarSync},
+    history::{History, SessionFilter},
+    lan::{LanBrowser, LanHost},
+    media_key::MediaKey,
+    monitors,
+    notion::{self, NotionConfig, NotionUploader},
+    ntfy::{self, NtfyConfig, NtfyPusher},
+    obsidian, paths,
+    remote_sync::{self, RemoteConfig},
+    report::DailyReport,
+    retry::Worker,
+    room::{Follower, RoomHost, SharedTimer},
+    screen_lock,
+    settings::{NotifTemplates, OverlayScreen, Settings},
+    sync::{self, SyncDir},
+    tasks::TaskList,
+    theme::{self, ThemeFile},
+    timer::{PomodoroMode, Timer},
+    tray::Tray,
+    update_check::{self, Release},
+};
+
+mod about_view;
+mod activity_view;
+mod confetti;
+mod keys;
+mod palette;
+mod settings_view;
+mod stats_view;
+mod tasks_view;
+mod timer_view;
+mod wizard_view;
+mod zen_view;
+
+/// How often the sync folder is checked for changes from other devices.
+const SYNC_INTERVAL: Duration = Duration::from_secs(60);
+/// How often the state is saved while running, so a crash loses little.
+const AUTOSAVE_INTERVAL: Duration = Duration::from_secs(30);
+/// How often the encrypted sync file on the server is reconciled.
+const REMOTE_SYNC_INTERVAL: Duration = Duration::from_secs(5 * 60);
+/// The shortest stopped work session that's kept as abandoned.
+const MIN_ABANDONED: Duration = Duration::from_secs(60);
+/// How many rhythms are offered under the duration sliders.
+const RECENT_DURATIONS: usize = 4;
+/// How long the warning shows before the screen is locked for a long break.
+const LOCK_WARNING: Duration = Duration::from_secs(10);
+
+/// App state fields holding tokens, passphrases or private URLs, which are left out
+/// when the state has to be saved outside the data directory.
+const SECRET_FIELDS: [&str; 4] = ["remote", "notion", "calendar", "ntfy"];
+
+#[derive(Clone, Copy, PartialEq, serde::Deserialize, serde::Serialize)]
+enum Tab {
+    Timer,
+    Tasks,
+    Stats,
+    Activity,
+}
+
+impl Tab {
+    const ALL: [Tab; 4] = [Tab::Timer, Tab::Tasks, Tab::Stats, Tab::Activity];
+
+    fn name(self) -> &'static str {
+        match self {
+            Tab::Timer => "Timer",
+            Tab::Tasks => "Tasks",
+            Tab::Stats => "Stats",
+            Tab::Activity => "Log",
+        }
+    }
+}
+
 /// We derive Deserialize/Serialize so we can persist app state on shutdown.
 #[derive(serde::Deserialize, serde::Serialize)]
 #[serde(default)] // if we add new fields, give them default values when deserializing old state
 pub struct EpomoApp {
     // this how you opt-out of serialization of a member
-    interval_period: i64,
-    long_break_period: i64,
-    short_break_period: i64,
-    session_count: usize,
-    show_notifs: bool,
+    settings: Settings,
+    // With the SQLite store these are still read from old state once, to move them over.
+    #[cfg_attr(feature = "sqlite", serde(skip_serializing))]
+    history: History,
+    #[cfg_attr(feature = "sqlite", serde(skip_serializing))]
+    tasks: TaskList,
+    /// The pomodoro timer comes first, followed by any extra countdowns.
+    timers: Vec<Timer>,
+    /// Tag for the work sessions recorded from now on.
+    tag: Option<String>,
+    /// Work, short break and long break minutes of the last few distinct rhythms run,
+    /// latest first.
+    recent_durations: Vec<[i64; 3]>,
+    device_id: String,
+    sync_dir: Option<PathBuf>,
+    settings_synced_at: Option<chrono::DateTime<chrono::Utc>>,
+    synced_settings: Option<Settings>,
+    remote: RemoteConfig,
+    notion: NotionConfig,
+    calendar: CalendarConfig,
+    ntfy: NtfyConfig,
+    tab: Tab,
+    /// Inner size of the window in points, restored on the next launch.
+    window_size: Option<[f32; 2]>,
+    /// Outer position of the window and the size of the monitor it was on.
+    window_pos: Option<([f32; 2], [f32; 2])>,
+    /// The last day a report was written for.
+    last_report: Option<chrono::NaiveDate>,
+    /// The last day GitHub was asked for a newer release, and what it said.
+    update_checked_on: Option<chrono::NaiveDate>,
+    latest_release: Option<Release>,
+    activity: ActivityLog,
 
+    #[cfg(feature = "sqlite")]
+    #[serde(skip)]
+    store: Option<crate::store::SqliteStore>,
+    #[serde(skip)]
+    events: EventBus,
+    /// The app's own subscription to `events`.
+    #[serde(skip)]
+    ui_events: mpsc::Receiver<TimerEvent>,
+    #[serde(skip)]
+    window_restored: bool,
+    #[serde(skip)]
+    settings_open: bool,
+    /// Which sessions the Stats tab counts.
+    #[serde(skip)]
+    stats_filter: SessionFilter,
+    /// The first-run wizard's current step, while it's showing.
+    #[serde(skip)]
+    wizard_step: Option<usize>,
+    #[serde(skip)]
+    wizard_autostart: bool,
+    /// Whether the OS starts epomo on login, read once the settings ask for it.
+    #[serde(skip)]
+    autostart_enabled: Option<bool>,
+    #[serde(skip)]
+    palette_open: bool,
+    #[serde(skip)]
+    palette_query: String,
+    #[serde(skip)]
+    palette_selected: usize,
+    #[serde(skip)]
+    media_key: Option<MediaKey>,
+    #[serde(skip)]
+    media_key_error: Option<String>,
+    /// The accent color selections are drawn in, if not egui's own.
+    #[serde(skip)]
+    accent_applied: Option<[u8; 3]>,
+    #[serde(skip)]
+    theme_file: ThemeFile,
+    #[serde(skip)]
+    theme_error: Option<String>,
+    #[serde(skip)]
+    tray: Option<Tray>,
+    #[serde(skip)]
+    tray_error: Option<String>,
+    /// What the tray icon shows now: minutes left and mode.
+    #[serde(skip)]
+    tray_shown: Option<(Option<i64>, PomodoroMode)>,
+    #[serde(skip)]
+    new_tag: String,
+    #[serde(skip)]
+    new_task_title: String,
+    #[serde(skip)]
+    new_task_estimate: u32,
+    #[serde(skip)]
+    new_timer_label: String,
+    #[serde(skip)]
+    new_timer_minutes: i64,
+    #[serde(skip)]
+    work_until: bool,
+    #[serde(skip)]
+    until_hour: u32,
+    #[serde(skip)]
+    until_minute: u32,
+    #[serde(skip)]
+    zen: bool,
+    #[serde(skip)]
+    confetti: Option<Confetti>,
+    /// Running through each work session while app tracking is on.
+    #[serde(skip)]
+    app_sampler: Option<AppSampler>,
+    /// Start of the work session waiting for a rating.
     #[serde(skip)]
-    current_mode: PomodoroMode,
+    energy_due: Option<chrono::DateTime<chrono::Utc>>,
+    /// Start of the session just stopped early, and why, while the user is asked.
     #[serde(skip)]
-    ends_at: Option<chrono::DateTime<chrono::Utc>>,
+    abandon_reason: Option<(chrono::DateTime<chrono::Utc>, String)>,
+    #[serde(skip)]
+    dimmed: bool,
+    /// Where the monitors are in the window, in pixels, while the overlay spans them all.
+    #[serde(skip)]
+    overlay_monitors: Vec<egui::Rect>,
+    #[serde(skip)]
+    dim_dismissed: bool,
+    /// When the screen gets locked for the long break, unless called off.
+    #[serde(skip)]
+    lock_at: Option<Instant>,
+    /// Whether the hosts file blocks the sites now, as far as the last change that
+    /// went through goes.
+    #[serde(skip)]
+    sites_blocked: bool,
+    /// Whether the sites are being blocked or unblocked, and the answer once that's done.
+    #[serde(skip)]
+    block_result: Option<(bool, mpsc::Receiver<io::Result<()>>)>,
+    #[serde(skip)]
+    block_error: Option<String>,
+    #[cfg(target_os = "linux")]
+    #[serde(skip)]
+    idle_inhibitor: Option<crate::idle_inhibit::IdleInhibitor>,
+    #[serde(skip)]
+    file_error: Option<String>,
+    /// Why the last save didn't fully work, shown in a banner until one does.
+    #[serde(skip)]
+    save_error: Option<String>,
+    /// Sessions read from another app's export, waiting to be confirmed.
+    #[serde(skip)]
+    import_preview: Option<Vec<crate::history::Session>>,
+    #[serde(skip)]
+    update_result: Option<mpsc::Receiver<io::Result<Release>>>,
+    #[serde(skip)]
+    last_sync: Option<Instant>,
+    /// The settings sent to the sync folder, and the answer once the sync is done.
+    #[serde(skip)]
+    sync_result: Option<(Settings, mpsc::Receiver<io::Result<sync::Outcome>>)>,
+    #[serde(skip)]
+    sync_error: Option<String>,
+    #[serde(skip)]
+    remote_result: Option<mpsc::Receiver<io::Result<remote_sync::Outcome>>>,
+    #[serde(skip)]
+    last_remote_sync: Option<Instant>,
+    #[serde(skip)]
+    remote_status: Option<String>,
+    #[serde(skip)]
+    notion_uploader: Option<NotionUploader>,
+    #[serde(skip)]
+    notion_error: Option<String>,
+    #[serde(skip)]
+    ntfy_pusher: Option<NtfyPusher>,
+    #[serde(skip)]
+    ntfy_error: Option<String>,
+    #[serde(skip)]
+    calendar_sync: Option<CalendarSync>,
+    #[serde(skip)]
+    calendar_sign_in: Option<mpsc::Receiver<io::Result<String>>>,
+    /// Key of the calendar event booked for the running work phase.
+    #[serde(skip)]
+    calendar_booked: Option<chrono::DateTime<chrono::Utc>>,
+    #[serde(skip)]
+    calendar_error: Option<String>,
+    #[serde(skip)]
+    lan_host: Option<LanHost>,
+    #[serde(skip)]
+    lan_browser: Option<LanBrowser>,
+    #[serde(skip)]
+    follower: Option<Follower>,
+    #[serde(skip)]
+    room_host: Option<RoomHost>,
+    #[serde(skip)]
+    room_code: String,
+    #[serde(skip)]
+    share_error: Option<String>,
 }
 
 impl Default for EpomoApp {
     fn default() -> Self {
+        let mut events = EventBus::default();
+        let ui_events = events.subscribe();
         Self {
-            interval_period: 25,
-            long_break_period: 15,
-            short_break_period: 5,
-            session_count: 0,
-            show_notifs: true,
-            ends_at: None,
-            current_mode: PomodoroMode::Work, // Begin with work
+            settings: Settings::default(),
+            history: History::default(),
+            tasks: TaskList::default(),
+            timers: vec![Timer::pomodoro()],
+            tag: None,
+            recent_durations: Vec::new(),
+            device_id: format!("{:016x}", fastrand::u64(..)),
+            sync_dir: None,
+            settings_synced_at: None,
+            synced_settings: None,
+            remote: RemoteConfig::default(),
+            notion: NotionConfig::default(),
+            calendar: CalendarConfig::default(),
+            ntfy: NtfyConfig::default(),
+            tab: Tab::Timer,
+            window_size: None,
+            window_pos: None,
+            last_report: None,
+            update_checked_on: None,
+            latest_release: None,
+            activity: ActivityLog::default(),
+            #[cfg(feature = "sqlite")]
+            store: None,
+            events,
+            ui_events,
+            window_restored: false,
+            settings_open: false,
+            stats_filter: SessionFilter::All,
+            wizard_step: None,
+            wizard_autostart: false,
+            autostart_enabled: None,
+            palette_open: false,
+            palette_query: String::new(),
+            palette_selected: 0,
+            media_key: None,
+            media_key_error: None,
+            accent_applied: None,
+            theme_file: ThemeFile::default(),
+            theme_error: None,
+            tray: None,
+            tray_error: None,
+            tray_shown: None,
+            new_tag: String::new(),
+            new_task_title: String::new(),
+            new_task_estimate: 1,
+            new_timer_label: String::new(),
+            new_timer_minutes: 10,
+            work_until: false,
+            until_hour: 17,
+            until_minute: 0,
+            zen: false,
+            confetti: None,
+            app_sampler: None,
+            energy_due: None,
+            abandon_reason: None,
+            dimmed: false,
+            overlay_monitors: Vec::new(),
+            dim_dismissed: false,
+            lock_at: None,
+            sites_blocked: false,
+            block_result: None,
+            block_error: None,
+            #[cfg(target_os = "linux")]
+            idle_inhibitor: None,
+            file_error: None,
+            save_error: None,
+            import_preview: None,
+            update_result: None,
+            last_sync: None,
+            sync_result: None,
+            sync_error: None,
+            remote_result: None,
+            last_remote_sync: None,
+            remote_status: None,
+            notion_uploader: None,
+            notion_error: None,
+            ntfy_pusher: None,
+            ntfy_error: None,
+            calendar_sync: None,
+            calendar_sign_in: None,
+            calendar_booked: None,
+            calendar_error: None,
+            lan_host: None,
+            lan_browser: None,
+            follower: None,
+            room_host: None,
+            room_code: String::new(),
+            share_error: None,
+        }
+    }
+}
+
+/// Moves what state saved before settings profiles existed kept at the top level into the
+/// settings, like version 1 backups. Returns whether there was anything to move.
+fn migrate_state(state: &mut serde_json::Value) -> bool {
+    let Some(top) = state.as_object_mut() else {
+        return false;
+    };
+    if top.contains_key("settings") {
+        return false;
+    }
+    let mut settings = serde_json::json!({});
+    let mut profile = serde_json::json!({ "name": "Default" });
+    let mut moved = false;
+    for key in ["interval_period", "long_break_period", "short_break_period"] {
+        if let Some(minutes) = top.remove(key) {
+            profile[key] = minutes;
+            moved = true;
         }
     }
+    if moved {
+        settings["profiles"] = serde_json::json!([profile]);
+    }
+    if let Some(show) = top.remove("show_notifs") {
+        settings["show_notifs"] = show;
+        moved = true;
+    }
+    if moved {
+        top.insert("settings".to_owned(), settings);
+    }
+    moved
+}
+
+fn from_state(state: serde_json::Value) -> Option<EpomoApp> {
+    serde_json::from_value(state)
+        .map_err(|e| tracing::warn!("could not read the saved state: {e}"))
+        .ok()
 }
 
 impl EpomoApp {
@@ -39,180 +419,1398 @@ impl EpomoApp {
     pub fn new(cc: &eframe::CreationContext<'_>) -> Self {
         // This is also where you can customize the look and feel of egui using
         // `cc.egui_ctx.set_visuals` and `cc.egui_ctx.set_fonts`.
+        let mut style = (*cc.egui_ctx.style()).clone();
+        focus_ring(&mut style);
+        cc.egui_ctx.set_style(style);
 
-        // Load previous app state (if any).
-        // Note that you must enable the `persistence` feature for this to work.
-        if let Some(storage) = cc.storage {
-            return eframe::get_value(storage, eframe::APP_KEY).unwrap_or_default();
+        // Load previous app state (if any). Versions before the state was saved by epomo
+        // itself left it in eframe's storage.
+        let stored: Option<Self> = match paths::load_state(&SECRET_FIELDS) {
+            Some(mut state) => {
+                migrate_state(&mut state);
+                from_state(state)
+            }
+            None => cc
+                .storage
+                .filter(|_| paths::custom_data_dir().is_none())
+                .and_then(|storage| {
+                    // Only go through JSON for state old enough to need it, as enums
+                    // don't survive the trip from RON.
+                    let mut state = eframe::get_value(storage, eframe::APP_KEY)?;
+                    if migrate_state(&mut state) {
+                        from_state(state)
+                    } else {
+                        eframe::get_value(storage, eframe::APP_KEY)
+                    }
+                }),
+        };
+        // Nothing saved yet means this is the first launch.
+        let first_run = stored.is_none();
+        let mut app = stored.unwrap_or_default();
+        if first_run {
+            app.wizard_step = Some(0);
         }
+        app.settings.ensure_profile();
+        for timer in &mut app.timers {
+            timer.restore(&mut app.settings);
+        }
+        if !app.timers.first().map_or(false, |t| t.cycles) {
+            app.timers.insert(0, Timer::pomodoro());
+        }
+        app.settings.apply_schedule(chrono::Local::now().weekday());
 
-        Default::default()
+        if let Some(dir) = paths::data_dir() {
+            event_log::spawn(dir.join("events.jsonl"), app.events.subscribe());
+            #[cfg(feature = "sqlite")]
+            app.open_store(&dir.join("epomo.sqlite"));
+        }
+        app
     }
-}
 
-#[derive(Copy, Clone, PartialEq)]
-enum PomodoroMode {
-    LongBreak,
-    ShortBreak,
-    Work,
-}
+    /// Switches history, tasks and settings over to the database, moving in whatever the
+    /// app state held if the database is new.
+    #[cfg(feature = "sqlite")]
+    fn open_store(&mut self, path: &std::path::Path) {
+        let res = std::fs::create_dir_all(path.parent().unwrap_or(path))
+            .map_err(|e| e.to_string())
+            .and_then(|()| crate::store::SqliteStore::open(path).map_err(|e| e.to_string()));
+        let mut store = match res {
+            Ok(store) => store,
+            Err(e) => {
+                self.file_error = Some(format!("Could not open the database: {e}"));
+                return;
+            }
+        };
+        let res = match store.is_empty() {
+            Ok(true) => store.save(&self.settings, &self.history, &self.tasks),
+            Ok(false) => store.load().map(|(settings, history, tasks)| {
+                self.settings = settings;
+                self.settings.ensure_profile();
+                self.history = history;
+                self.tasks = tasks;
+            }),
+            Err(e) => Err(e),
+        };
+        match res {
+            Ok(()) => self.store = Some(store),
+            Err(e) => self.file_error = Some(format!("Could not read the database: {e}")),
+        }
+    }
 
-impl PomodoroMode {
-    fn get_notif_str(&self) -> &'static str {
-        match *self {
-            PomodoroMode::LongBreak => "Time for a long break!",
-            PomodoroMode::ShortBreak => "Time for a short break!",
-            PomodoroMode::Work => "Time to work!",
+    /// Exchanges history and settings with the sync folder, if one is set. The folder
+    /// may be on a slow or sleeping drive, so this happens on a worker thread.
+    fn start_sync(&mut self) {
+        let Some(root) = self.sync_dir.clone() else {
+            return;
+        };
+        let dir = SyncDir::new(root, self.device_id.clone());
+        let rx = dir.spawn(
+            self.history.clone(),
+            self.settings.clone(),
+            self.synced_settings.clone(),
+            self.settings_synced_at,
+        );
+        self.sync_result = Some((self.settings.clone(), rx));
+        self.last_sync = Some(Instant::now());
+    }
+
+    fn poll_sync(&mut self) {
+        let Some((sent, res)) = self
+            .sync_result
+            .as_ref()
+            .and_then(|(sent, rx)| Some((sent.clone(), rx.try_recv().ok()?)))
+        else {
+            return;
+        };
+        self.sync_result = None;
+        match res {
+            Ok(outcome) => {
+                // Sessions recorded while the sync was in flight are kept by merging.
+                self.history.merge(outcome.history.sessions);
+                self.history.merge_abandoned(outcome.history.abandoned);
+                // Edits made in the meantime weren't sent, so they're left to go out
+                // with the next sync.
+                let mut synced = sent;
+                if let Some((saved_at, settings)) = outcome.settings {
+                    synced = synced.merged(self.synced_settings.as_ref(), settings.clone());
+                    self.settings = self
+                        .settings
+                        .merged(self.synced_settings.as_ref(), settings);
+                    self.settings.ensure_profile();
+                    self.settings_synced_at = Some(saved_at);
+                }
+                self.synced_settings = Some(synced);
+                self.sync_error = None;
+            }
+            Err(e) => {
+                tracing::warn!("syncing with the sync folder failed: {e}");
+                self.activity
+                    .push(format!("Syncing with the sync folder failed: {e}"));
+                self.sync_error = Some(e.to_string());
+            }
         }
     }
-}
 
-impl Display for PomodoroMode {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match *self {
-            PomodoroMode::LongBreak => write!(f, "Long break"),
-            PomodoroMode::ShortBreak => write!(f, "Short break"),
-            PomodoroMode::Work => write!(f, "Work"),
+    fn start_remote_sync(&mut self) {
+        // Local edits since the last sync count as made now, so they win over older
+        // edits from other devices.
+        let changed_at = if self.synced_settings.as_ref() == Some(&self.settings) {
+            self.settings_synced_at
+                .unwrap_or(chrono::DateTime::<chrono::Utc>::MIN_UTC)
+        } else {
+            chrono::Utc::now()
+        };
+        self.remote_result = Some(remote_sync::spawn(
+            self.remote.clone(),
+            self.history.clone(),
+            (changed_at, self.settings.clone()),
+        ));
+        self.last_remote_sync = Some(Instant::now());
+    }
+
+    /// Keeps the Notion, ntfy and calendar workers running while they're set up, and
+    /// shows what went wrong with them.
+    fn run_workers(&mut self) {
+        let failures = [
+            (
+                "Notion upload",
+                keep_worker(
+                    &mut self.notion_uploader,
+                    &self.notion,
+                    self.notion.is_configured(),
+                    notion::uploader,
+                    &mut self.notion_error,
+                ),
+            ),
+            (
+                "ntfy push",
+                keep_worker(
+                    &mut self.ntfy_pusher,
+                    &self.ntfy,
+                    self.ntfy.is_configured(),
+                    ntfy::pusher,
+                    &mut self.ntfy_error,
+                ),
+            ),
+            (
+                "Calendar update",
+                keep_worker(
+                    &mut self.calendar_sync,
+                    &self.calendar,
+                    self.calendar.is_signed_in(),
+                    gcal::updater,
+                    &mut self.calendar_error,
+                ),
+            ),
+        ];
+        for (what, errors) in failures {
+            for e in errors {
+                tracing::warn!("{what} failed: {e}");
+                self.activity.push(format!("{what} failed: {e}"));
+            }
         }
     }
-}
 
-impl From<PomodoroMode> for egui::Color32 {
-    fn from(val: PomodoroMode) -> Self {
-        match val {
-            PomodoroMode::LongBreak => Color32::from_rgb(240, 140, 58),
-            PomodoroMode::ShortBreak => Color32::from_rgb(240, 231, 58),
-            PomodoroMode::Work => Color32::from_rgb(58, 191, 240),
+    /// Finishes signing in to Google once the browser comes back.
+    fn finish_calendar_sign_in(&mut self) {
+        if let Some(res) = self
+            .calendar_sign_in
+            .as_ref()
+            .and_then(|rx| rx.try_recv().ok())
+        {
+            self.calendar_sign_in = None;
+            match res {
+                Ok(token) => {
+                    self.calendar.refresh_token = Some(token);
+                    self.calendar_error = None;
+                    self.activity
+                        .push("Signed in to Google Calendar".to_owned());
+                }
+                Err(e) => {
+                    tracing::warn!("could not sign in to Google: {e}");
+                    self.calendar_error = Some(format!("Could not sign in: {e}"));
+                }
+            }
         }
     }
-}
 
-fn format_duration(duration: chrono::Duration, mode: PomodoroMode) -> String {
-    format!(
-        "{:02}:{:02}:{:02} {}",
-        duration.num_hours(),
-        duration.num_minutes(),
-        duration.num_seconds() % 60,
-        mode
-    )
-}
+    /// Shortens the event booked for a work phase that was cut short.
+    fn end_calendar_booking(&mut self, at: chrono::DateTime<chrono::Utc>) {
+        if let (Some(key), Some(sync)) = (self.calendar_booked.take(), &self.calendar_sync) {
+            sync.push(gcal::Job::EndAt { key, end: at });
+        }
+    }
 
-fn get_mode(cur_mode: PomodoroMode, session_count: usize) -> PomodoroMode {
-    match cur_mode {
-        PomodoroMode::Work => {
-            if session_count % 4 == 0 {
-                PomodoroMode::LongBreak
-            } else {
-                PomodoroMode::ShortBreak
+    /// What a focus block is called in the calendar.
+    fn calendar_summary(&self) -> String {
+        match &self.tag {
+            Some(tag) => format!("🍅 Focus #{tag}"),
+            None => "🍅 Focus".to_owned(),
+        }
+    }
+
+    fn poll_remote_sync(&mut self) {
+        let Some(res) = self
+            .remote_result
+            .as_ref()
+            .and_then(|rx| rx.try_recv().ok())
+        else {
+            return;
+        };
+        self.remote_result = None;
+        match res {
+            Ok(outcome) => {
+                // Sessions recorded while the sync was in flight are kept by merging.
+                self.history.merge(outcome.history.sessions);
+                self.history.merge_abandoned(outcome.history.abandoned);
+                self.history.resolve_overlaps();
+                if let Some((saved_at, settings)) = outcome.settings {
+                    self.settings = self
+                        .settings
+                        .merged(self.synced_settings.as_ref(), settings);
+                    self.settings.ensure_profile();
+                    self.settings_synced_at = Some(saved_at);
+                    self.synced_settings = Some(self.settings.clone());
+                }
+                let status = match outcome.dropped_overlaps {
+                    0 => "Synced".to_owned(),
+                    n => format!("Synced, dropped {n} overlapping sessions"),
+                };
+                self.activity.push(format!("Encrypted sync: {status}"));
+                self.remote_status = Some(status);
+            }
+            Err(e) => {
+                tracing::warn!("encrypted sync failed: {e}");
+                self.activity.push(format!("Encrypted sync failed: {e}"));
+                self.remote_status = Some(format!("Sync failed: {e}"));
             }
         }
-        _ => PomodoroMode::Work,
     }
-}
 
-impl eframe::App for EpomoApp {
-    /// Called by the frame work to save state before shutdown.
-    fn save(&mut self, storage: &mut dyn eframe::Storage) {
-        eframe::set_value(storage, eframe::APP_KEY, self);
+    /// Writes today's report once the report hour has passed, or yesterday's if it was
+    /// missed.
+    fn write_due_report(&mut self) {
+        let Some(dir) = &self.settings.report_dir else {
+            return;
+        };
+        let now = chrono::Local::now();
+        let today = now.date_naive();
+        let due = if now.hour() >= self.settings.report_hour {
+            Some(today)
+        } else {
+            today.pred_opt()
+        };
+        let Some(day) = due.filter(|day| self.last_report.map_or(true, |last| last < *day)) else {
+            return;
+        };
+        let res = DailyReport::new(day, &self.history, &self.tasks, self.settings.clock_format)
+            .write(dir, self.settings.report_format);
+        match res {
+            Ok(path) => self
+                .activity
+                .push(format!("Wrote the daily report to {}", path.display())),
+            Err(e) => {
+                // Not retried until the next report is due, so a broken folder doesn't
+                // turn into an error every frame.
+                tracing::warn!("could not write the daily report: {e}");
+                self.activity
+                    .push(format!("Could not write the daily report: {e}"));
+                self.file_error = Some(format!("Could not write the daily report: {e}"));
+            }
+        }
+        self.last_report = Some(day);
     }
 
-    /// Called each time the UI needs repainting, which may be many times per second.
-    /// Put your widgets into a `SidePanel`, `TopPanel`, `CentralPanel`, `Window` or `Area`.
-    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+    /// Asks GitHub for the latest release at most once a day, if the user opted in.
+    fn check_for_update(&mut self) {
+        if let Some(res) = self
+            .update_result
+            .as_ref()
+            .and_then(|rx| rx.try_recv().ok())
+        {
+            match res {
+                Ok(release) => {
+                    if release.is_newer() {
+                        self.activity
+                            .push(format!("Version {} is available", release.version));
+                    }
+                    self.latest_release = Some(release);
+                }
+                Err(e) => tracing::warn!("could not check for updates: {e}"),
+            }
+            self.update_result = None;
+        }
+        let today = chrono::Local::now().date_naive();
+        if !self.settings.check_updates
+            || self.update_result.is_some()
+            || self.update_checked_on == Some(today)
+        {
+            return;
+        }
+        let (tx, rx) = mpsc::channel();
+        std::thread::spawn(move || {
+            let _ = tx.send(update_check::latest());
+        });
+        self.update_result = Some(rx);
+        // Failures aren't retried until tomorrow either.
+        self.update_checked_on = Some(today);
+    }
+
+    /// Restores the saved window size and position on the first frame and remembers them
+    /// afterwards.
+    fn track_window(&mut self, frame: &mut eframe::Frame) {
+        let info = frame.info().window_info;
+        if !self.window_restored {
+            if let Some([width, height]) = self.window_size {
+                frame.set_window_size(egui::vec2(width, height));
+            }
+            if let Some(([x, y], monitor)) = self.window_pos {
+                let mut pos = egui::pos2(x, y);
+                // eframe only tells us the size of the monitor we're on. If that differs
+                // from the one the window was saved on, the screen setup changed and the
+                // old spot may be off screen, so pull the window back onto this monitor.
+                if let Some(current) = info.monitor_size {
+                    if [current.x, current.y] != monitor {
+                        let size = self
+                            .window_size
+                            .map_or(info.size, |[w, h]| egui::vec2(w, h));
+                        pos.x = pos.x.clamp(0.0, (current.x - size.x).max(0.0));
+                        pos.y = pos.y.clamp(0.0, (current.y - size.y).max(0.0));
+                    }
+                }
+                frame.set_window_pos(pos);
+            }
+            self.window_restored = true;
+        } else if !self.dimmed && !self.zen {
+            // Don't remember the fullscreen size of the break overlay or zen mode.
+            self.window_size = Some([info.size.x, info.size.y]);
+            if let (Some(pos), Some(monitor)) = (info.position, info.monitor_size) {
+                self.window_pos = Some(([pos.x, pos.y], [monitor.x, monitor.y]));
+            }
+        }
+    }
+
+    /// Applies the zoom setting, which Ctrl+scroll (or pinching) also adjusts.
+    fn apply_zoom(&mut self, ctx: &egui::Context, frame: &eframe::Frame) {
+        let zoom = ctx.input(|i| i.zoom_delta());
+        if zoom != 1.0 {
+            self.settings.ui_scale = (self.settings.ui_scale * zoom).clamp(0.5, 3.0);
+        }
+        let native = frame.info().native_pixels_per_point.unwrap_or(1.0);
+        let pixels_per_point = native * self.settings.ui_scale;
+        if (ctx.pixels_per_point() - pixels_per_point).abs() > f32::EPSILON {
+            ctx.set_pixels_per_point(pixels_per_point);
+        }
+    }
+
+    /// Highlights selections in the OS accent color while the setting is on, and puts
+    /// egui's own color back once it's off.
+    fn apply_accent(&mut self, ctx: &egui::Context) {
+        let accent = self
+            .settings
+            .follow_accent
+            .then(accent::system_accent)
+            .flatten();
+        if accent == self.accent_applied {
+            return;
+        }
+        let mut style = (*ctx.style()).clone();
+        let default = if style.visuals.dark_mode {
+            egui::Visuals::dark()
+        } else {
+            egui::Visuals::light()
+        };
+        style.visuals.selection.bg_fill = match accent {
+            Some([r, g, b]) => Color32::from_rgb(r, g, b),
+            None => self
+                .theme_file
+                .theme()
+                .and_then(|theme| theme.accent())
+                .unwrap_or(default.selection.bg_fill),
+        };
+        ctx.set_style(style);
+        self.accent_applied = accent;
+    }
+
+    /// Switches to the look in the theme file whenever it's saved, and back to egui's
+    /// own when it's removed. A file that can't be read leaves the current look alone.
+    fn apply_theme(&mut self, ctx: &egui::Context) {
+        let (mut style, fonts) = match self.theme_file.poll(ctx) {
+            None => return,
+            Some(theme::Change::Loaded(theme, fonts)) => {
+                self.theme_error = None;
+                (theme.style(), fonts)
+            }
+            Some(theme::Change::Removed) => {
+                self.theme_error = None;
+                (egui::Style::default(), egui::FontDefinitions::default())
+            }
+            Some(theme::Change::Failed(e)) => {
+                tracing::warn!("{e}");
+                self.activity.push(e.clone());
+                self.theme_error = Some(e);
+                return;
+            }
+        };
+        focus_ring(&mut style);
+        ctx.set_style(style);
+        ctx.set_fonts(fonts);
+        // Put the accent color and motion settings back on top of the new style.
+        self.accent_applied = None;
+    }
+
+    /// Turns egui's animations off while reduced motion is on, and back on after.
+    fn apply_motion(&self, ctx: &egui::Context) {
+        let animation_time = if self.settings.reduce_motion {
+            0.0
+        } else {
+            egui::Style::default().animation_time
+        };
+        if (ctx.style().animation_time - animation_time).abs() > f32::EPSILON {
+            let mut style = (*ctx.style()).clone();
+            style.animation_time = animation_time;
+            ctx.set_style(style);
+        }
+    }
+
+    /// Advances the timers and everything that follows them directly: shared timers, site
+    /// blocking and keeping the screen on.
+    fn tick(&mut self, ctx: &egui::Context) {
         let Self {
-            interval_period,
-            ends_at,
-            long_break_period,
-            short_break_period,
-            current_mode,
-            session_count,
-            show_notifs,
+            settings,
+            timers,
+            sites_blocked,
+            block_result,
+            block_error,
+            activity,
+            lan_host,
+            lan_browser,
+            follower,
+            room_host,
+            share_error,
+            ..
         } = self;
+        let (pomodoro, countdowns) = timers
+            .split_first_mut()
+            .expect("the pomodoro timer always exists");
 
-        egui::CentralPanel::default().show(ctx, |ui| {
-            ui.heading("Pomodoro");
-            ui.vertical(|ui| {
-                ui.label("Interval time in minutes");
-                ui.add_enabled(
-                    ends_at.is_none() || *current_mode != PomodoroMode::Work,
-                    egui::Slider::new(interval_period, 1..=120).suffix("m"),
-                );
-            });
-            ui.vertical(|ui| {
-                ui.label("Short break time in minutes");
-                ui.add_enabled(
-                    *current_mode != PomodoroMode::ShortBreak,
-                    egui::Slider::new(short_break_period, 1..=30).suffix("m"),
-                );
-            });
-            ui.vertical(|ui| {
-                ui.label("Long break time in minutes");
-                ui.add_enabled(
-                    *current_mode != PomodoroMode::LongBreak,
-                    egui::Slider::new(long_break_period, 1..=120).suffix("m"),
-                );
+        // When following someone else's timer, the host drives all transitions.
+        match follower.as_ref().map(|client| client.poll()) {
+            Some(Ok(Some(shared))) => {
+                pomodoro.follow(shared.mode, shared.ends_at, shared.session_count);
+            }
+            Some(Err(_)) => {
+                *follower = None;
+                *share_error = Some("The host ended the shared timer".to_owned());
+            }
+            _ => {}
+        }
+
+        // Core loop
+        if follower.is_none() && pomodoro.tick(settings) {
+            ctx.request_repaint();
+        }
+        for countdown in countdowns.iter_mut() {
+            countdown.tick(settings);
+        }
+
+        if let Some(host) = lan_host {
+            host.broadcast(&SharedTimer {
+                mode: pomodoro.mode,
+                ends_at: pomodoro.ends_at,
+                session_count: pomodoro.session_count,
             });
-            ui.add(egui::Checkbox::new(show_notifs, "Show notifications"));
-            ui.horizontal(|ui| {
-                if ui
-                    .add_enabled(ends_at.is_none(), Button::new("Start"))
-                    .clicked()
-                {
-                    *ends_at =
-                        Some(chrono::Utc::now() + chrono::Duration::minutes(*interval_period));
-                };
-                if ui
-                    .add_enabled(ends_at.is_some(), Button::new("Stop"))
-                    .clicked()
-                {
-                    *ends_at = None;
-                    *session_count = 0;
-                };
+        }
+        if let Some(host) = room_host {
+            let shared = SharedTimer {
+                mode: pomodoro.mode,
+                ends_at: pomodoro.ends_at,
+                session_count: pomodoro.session_count,
+            };
+            if let Err(e) = host.broadcast(&shared) {
+                *room_host = None;
+                *share_error = Some(format!("Lost the relay: {e}"));
+            }
+        }
+        if let Some(browser) = lan_browser {
+            browser.poll();
+        }
+
+        // Site blocking follows the work phases. Editing the hosts file may pop up an
+        // elevation prompt, so it happens on a worker thread.
+        let want_block =
+            settings.block_sites && pomodoro.is_running() && pomodoro.mode == PomodoroMode::Work;
+        // A change that failed isn't tried again until the next one is due, so a
+        // declined prompt doesn't come straight back.
+        if want_block == *sites_blocked {
+            *block_error = None;
+        } else if block_result.is_none() && block_error.is_none() {
+            let domains = if want_block {
+                blocker::parse_domains(&settings.blocked_sites)
+            } else {
+                Vec::new()
+            };
+            let (tx, rx) = mpsc::channel();
+            std::thread::spawn(move || {
+                let _ = tx.send(blocker::set_blocked(&domains));
             });
-            // Countdown
-            if ends_at.is_some() {
-                // Core loop
-                let now = chrono::Utc::now();
-                let time_left = ends_at.unwrap() - now;
-                if time_left < chrono::Duration::seconds(0) {
-                    if *current_mode == PomodoroMode::Work {
-                        *session_count += 1;
-                    }
-                    *current_mode = get_mode(*current_mode, *session_count);
-                    match current_mode {
-                        PomodoroMode::LongBreak => {
-                            *ends_at = Some(
-                                chrono::Utc::now() + chrono::Duration::minutes(*long_break_period),
-                            )
+            *block_result = Some((want_block, rx));
+        }
+        let done = block_result
+            .as_ref()
+            .and_then(|(block, rx)| Some((*block, rx.try_recv().ok()?)));
+        if let Some((block, res)) = done {
+            match res {
+                Ok(()) => *sites_blocked = block,
+                Err(e) => {
+                    tracing::warn!("could not update the hosts file: {e}");
+                    activity.push(format!("Could not update the blocked sites: {e}"));
+                    *block_error = Some(e.to_string());
+                }
+            }
+            *block_result = None;
+        }
+
+        #[cfg(target_os = "linux")]
+        {
+            let want_inhibit = settings.inhibit_idle
+                && pomodoro.is_running()
+                && pomodoro.mode == PomodoroMode::Work;
+            if want_inhibit != self.idle_inhibitor.is_some() {
+                self.idle_inhibitor = want_inhibit.then(crate::idle_inhibit::IdleInhibitor::start);
+            }
+        }
+    }
+
+    /// Shows or hides the tray icon as the setting changes, and redraws it whenever the
+    /// minute left or the mode changes.
+    fn update_tray(&mut self) {
+        if !self.settings.tray_icon {
+            self.tray = None;
+            self.tray_error = None;
+            self.tray_shown = None;
+            return;
+        }
+        if self.tray.is_none() && self.tray_error.is_none() {
+            match Tray::new() {
+                Ok(tray) => self.tray = Some(tray),
+                Err(e) => {
+                    tracing::warn!("could not add the tray icon: {e}");
+                    self.tray_error = Some(e);
+                }
+            }
+        }
+        let Some(tray) = &mut self.tray else {
+            return;
+        };
+        let pomodoro = &self.timers[0];
+        // Rounded up, so it reads 1 until the very end like the countdown does.
+        let minutes = pomodoro
+            .time_left()
+            .map(|left| (left.num_seconds() + 59) / 60);
+        let shown = Some((minutes, pomodoro.mode));
+        if self.tray_shown == shown {
+            return;
+        }
+        let tooltip = match minutes {
+            Some(minutes) => format!("epomo: {}, {minutes} min left", pomodoro.mode),
+            None => "epomo".to_owned(),
+        };
+        tray.show(minutes, self.settings.color(pomodoro.mode), &tooltip);
+        self.tray_shown = shown;
+    }
+
+    /// Hands the timers' queued events to every subscriber, then reacts to them here:
+    /// records history, notifies and celebrates.
+    fn handle_events(&mut self, ctx: &egui::Context) {
+        for timer in &mut self.timers {
+            for event in timer.take_events() {
+                self.events.publish(event);
+            }
+        }
+        let events: Vec<TimerEvent> = self.ui_events.try_iter().collect();
+        // After a catch-up, one summary beats a notification per phase that went by.
+        let quiet = events
+            .iter()
+            .any(|e| matches!(e, TimerEvent::CaughtUp { .. }));
+        // Work isn't starting after all, so don't announce it.
+        let held = events
+            .iter()
+            .any(|e| matches!(e, TimerEvent::HeldForTheDay { .. }));
+        for event in events {
+            self.activity.push(event.to_string());
+            match event {
+                TimerEvent::PhaseStarted { mode, ends_at } => {
+                    tracing::info!("{mode} started, ends at {ends_at}");
+                    // Not after a catch-up: nobody's there to step away.
+                    self.lock_at = (mode == PomodoroMode::LongBreak
+                        && self.settings.lock_on_long_break
+                        && !quiet)
+                        .then(|| Instant::now() + LOCK_WARNING);
+                    if let Some(sync) = &self.calendar_sync {
+                        if mode == PomodoroMode::Work && self.calendar.prebook {
+                            let start = self.timers[0]
+                                .phase_started_at
+                                .unwrap_or_else(chrono::Utc::now);
+                            sync.push(gcal::Job::Add {
+                                key: ends_at,
+                                start: start.min(ends_at),
+                                end: ends_at,
+                                summary: self.calendar_summary(),
+                            });
+                            self.calendar_booked = Some(ends_at);
                         }
-                        PomodoroMode::ShortBreak => {
-                            *ends_at = Some(
-                                chrono::Utc::now() + chrono::Duration::minutes(*short_break_period),
-                            )
+                    }
+                    if mode == PomodoroMode::Work && self.timers[0].target.is_none() {
+                        self.remember_durations();
+                    }
+                    self.app_sampler = (mode == PomodoroMode::Work && self.settings.track_apps)
+                        .then(|| AppSampler::start(self.settings.tag_rules.clone()));
+                }
+                TimerEvent::PhaseEnded {
+                    mode,
+                    next,
+                    started_at,
+                    ended_at,
+                } => {
+                    // What the work session is recorded under, also used for its Notion row.
+                    let mut session_tag = None;
+                    if mode == PomodoroMode::Work {
+                        let sampler = self.app_sampler.take();
+                        let app = sampler.as_ref().and_then(AppSampler::dominant);
+                        let tag = self.tag.clone().or_else(|| {
+                            let sampler = sampler.as_ref().filter(|_| self.settings.auto_tag);
+                            sampler.and_then(AppSampler::suggested_tag)
+                        });
+                        session_tag = tag.clone();
+                        let profile = Some(self.settings.profile().name.clone());
+                        self.history.record(started_at, ended_at, app, tag, profile);
+                        self.energy_due =
+                            (self.settings.ask_energy && !quiet).then_some(started_at);
+                        self.log_to_obsidian();
+                        self.tasks.credit_session();
+                        if !quiet {
+                            self.celebrate(ctx, next);
                         }
-                        PomodoroMode::Work => {
-                            *ends_at = Some(
-                                chrono::Utc::now() + chrono::Duration::minutes(*interval_period),
-                            )
+                    }
+                    if let Some(sync) = &self.calendar_sync {
+                        match self.calendar_booked.take() {
+                            // Pauses may have moved the end since it was booked.
+                            Some(key) => sync.push(gcal::Job::EndAt { key, end: ended_at }),
+                            None if mode == PomodoroMode::Work => sync.push(gcal::Job::Add {
+                                key: started_at,
+                                start: started_at,
+                                end: ended_at,
+                                summary: self.calendar_summary(),
+                            }),
+                            None => {}
                         }
                     }
-                    if *show_notifs {
-                        Notification::new()
-                            .summary("epomo")
-                            .body(current_mode.get_notif_str())
-                            .show()
-                            .unwrap();
+                    if let Some(uploader) = &self.notion_uploader {
+                        let task = self.tasks.tasks.iter().find(|t| !t.done);
+                        uploader.push(notion::Row {
+                            mode,
+                            started_at,
+                            ended_at,
+                            tag: session_tag,
+                            notes: task.map(|t| t.title.clone()),
+                        });
+                    }
+                    self.dim_dismissed = false;
+                    let announce = !(quiet || (held && next == PomodoroMode::Work));
+                    if self.settings.show_notifs && announce {
+                        let template = self.settings.notif_templates.for_mode(next);
+                        notify_with_sound(
+                            &self.notif_text(template, next, None),
+                            &self.settings.profile().sound,
+                        );
+                    }
+                    if let Some(pusher) = self.ntfy_pusher.as_ref().filter(|_| announce) {
+                        let template = self.settings.notif_templates.for_mode(next);
+                        pusher.push(ntfy::Message {
+                            next,
+                            body: self.notif_text(template, next, None),
+                        });
                     }
-                    ctx.request_repaint();
                 }
-                ui.label(
-                    egui::RichText::new(format_duration(time_left, *current_mode))
-                        .heading()
-                        .color(Into::<Color32>::into(*current_mode)),
-                );
-                ui.label(format!("Completed session count {}", *session_count));
+                TimerEvent::CaughtUp {
+                    phases,
+                    sessions,
+                    mode,
+                } if self.settings.show_notifs => {
+                    notify(&format!(
+                        "{phases} phases went by while epomo wasn't updating, \
+                         {sessions} of them work sessions. Now: {mode}"
+                    ));
+                }
+                TimerEvent::Skipped { at, .. } => {
+                    self.dim_dismissed = false;
+                    self.app_sampler = None;
+                    self.end_calendar_booking(at);
+                }
+                TimerEvent::Stopped {
+                    mode,
+                    started_at,
+                    at,
+                } => {
+                    // Stopping right after starting by mistake isn't worth keeping.
+                    let started_at = started_at.filter(|&start| {
+                        mode == PomodoroMode::Work
+                            && (at - start).to_std().map_or(false, |d| d >= MIN_ABANDONED)
+                    });
+                    if let Some(started_at) = started_at {
+                        let profile = Some(self.settings.profile().name.clone());
+                        self.history
+                            .abandon(started_at, at, self.tag.clone(), profile);
+                        self.abandon_reason = Some((started_at, String::new()));
+                    }
+                    self.app_sampler = None;
+                    self.end_calendar_booking(at);
+                }
+                TimerEvent::DailyLimitReached { .. } if self.settings.show_notifs && !quiet => {
+                    let hold = if self.settings.hold_at_daily_limit {
+                        "Work won't start again after this break."
+                    } else {
+                        "Consider calling it a day."
+                    };
+                    notify(&format!("That's today's limit reached. {hold}"));
+                }
+                TimerEvent::HeldForTheDay { .. } if self.settings.show_notifs => {
+                    notify("Break's over, and so is work for today. Start again if you must.");
+                }
+                TimerEvent::CountdownFinished { label, .. } if self.settings.show_notifs => {
+                    let template = &self.settings.notif_templates.countdown;
+                    notify(&self.notif_text(template, self.timers[0].mode, Some(label.as_str())));
+                }
+                _ => {}
             }
-            ctx.request_repaint_after(Duration::from_secs(1));
+        }
+        if !self.settings.track_apps {
+            self.app_sampler = None;
+        }
+        if let Some(sampler) = &self.app_sampler {
+            sampler.set_paused(!self.timers[0].is_running());
+        }
+    }
+
+    /// Moves the profile's current durations to the front of the recent ones.
+    fn remember_durations(&mut self) {
+        let profile = self.settings.profile();
+        let durations = [
+            profile.interval_period,
+            profile.short_break_period,
+            profile.long_break_period,
+        ];
+        self.recent_durations.retain(|d| *d != durations);
+        self.recent_durations.insert(0, durations);
+        self.recent_durations.truncate(RECENT_DURATIONS);
+    }
+
+    /// Adds the session just recorded to today's note in the Obsidian vault, if one is
+    /// set.
+    fn log_to_obsidian(&mut self) {
+        let (Some(vault), Some(session)) =
+            (&self.settings.obsidian_vault, self.history.sessions.last())
+        else {
+            return;
+        };
+        let task = self.tasks.tasks.iter().find(|t| !t.done);
+        let res = obsidian::append_session(
+            vault,
+            &self.settings.obsidian_note,
+            session,
+            task.map(|t| t.title.as_str()),
+            self.settings.clock_format,
+        );
+        if let Err(e) = res {
+            tracing::warn!("could not add the session to the Obsidian note: {e}");
+            self.activity.push(format!(
+                "Could not add the session to the Obsidian note: {e}"
+            ));
+            self.file_error = Some(format!(
+                "Could not add the session to the Obsidian note: {e}"
+            ));
+        }
+    }
+
+    /// Fills in a notification template. `mode` is the phase the notification is about.
+    fn notif_text(&self, template: &str, mode: PomodoroMode, label: Option<&str>) -> String {
+        let duration = format!("{} min", self.settings.profile().period(mode));
+        let sessions_today = self
+            .history
+            .count_on(chrono::Local::now().date_naive())
+            .to_string();
+        let task = self
+            .tasks
+            .tasks
+            .iter()
+            .find(|t| !t.done)
+            .map_or("", |t| t.title.as_str());
+        NotifTemplates::fill(
+            template,
+            &[
+                ("mode", &mode.to_string()),
+                ("duration", &duration),
+                ("sessions_today", &sessions_today),
+                ("task", task),
+                ("label", label.unwrap_or_default()),
+            ],
+        )
+    }
+
+    /// Throws confetti for a finished work session, more of it when a long break is up.
+    fn celebrate(&mut self, ctx: &egui::Context, next: PomodoroMode) {
+        if self.settings.reduce_motion {
+            return;
+        }
+        let profile = self.settings.profile();
+        let count = match next {
+            PomodoroMode::LongBreak => 150,
+            _ => 50,
+        };
+        self.confetti = Some(Confetti::burst(
+            ctx.screen_rect(),
+            count,
+            &[
+                profile.work_color,
+                profile.short_break_color,
+                profile.long_break_color,
+            ]
+            .map(|[r, g, b]| Color32::from_rgb(r, g, b)),
+        ));
+    }
+
+    /// Wakes the UI up when a countdown's display ticks over to the next second, or less
+    /// often while the window is in the background. With nothing running we only need to
+    /// come back while waiting on background work.
+    fn schedule_repaint(&self, ctx: &egui::Context) {
+        let waiting = self.follower.is_some()
+            || self.lan_browser.is_some()
+            || self.remote_result.is_some()
+            || self.block_result.is_some();
+        let background = self.settings.background_repaint_secs > 0
+            && !self.zen
+            && !self.dimmed
+            && !ctx.input(|i| i.raw.has_focus);
+        match next_second(&self.timers) {
+            Some(_) if background => {
+                // Nobody is watching the seconds, but phase ends must still be on time
+                // for the notifications.
+                let until_end = self
+                    .timers
+                    .iter()
+                    .filter_map(|t| t.time_left()?.to_std().ok())
+                    .min()
+                    .unwrap_or_default();
+                let every = Duration::from_secs(self.settings.background_repaint_secs);
+                ctx.request_repaint_after(every.min(until_end + Duration::from_millis(1)));
+            }
+            Some(delay) => ctx.request_repaint_after(delay),
+            None if waiting => ctx.request_repaint_after(Duration::from_secs(1)),
+            None => {}
+        }
+    }
+
+    /// Saves the app state, and the database if there is one. eframe's own storage only
+    /// logs it when writing fails, so the state is saved here instead, where a failure
+    /// can be shown. Anything that couldn't be saved is kept in `save_error` for the
+    /// banner until a later save works.
+    fn persist(&mut self) {
+        let mut problems = Vec::new();
+        #[cfg(feature = "sqlite")]
+        if let Some(store) = &mut self.store {
+            if let Err(e) = store.save(&self.settings, &self.history, &self.tasks) {
+                problems.push(format!("Could not save to the database: {e}"));
+            }
+        }
+        match paths::save_state(self, &SECRET_FIELDS) {
+            Ok(paths::Saved::DataDir) => {}
+            Ok(paths::Saved::Fallback { dir, error }) => problems.push(format!(
+                "Could not save to the data directory ({error}), so it's in {} for now",
+                dir.display()
+            )),
+            Err(e) => problems.push(format!(
+                "Could not save ({e}). Changes since the last save will be lost on exit"
+            )),
+        }
+        let problem = (!problems.is_empty()).then(|| problems.join(". "));
+        if problem.is_some() && problem != self.save_error {
+            let problem = problem.as_deref().unwrap_or_default();
+            tracing::warn!("{problem}");
+            self.activity.push(problem);
+        }
+        self.save_error = problem;
+    }
+
+    /// Counts down to locking the screen for a long break, with a way to call it off.
+    fn show_lock_warning(&mut self, ctx: &egui::Context) {
+        let Some(lock_at) = self.lock_at else {
+            return;
+        };
+        let pomodoro = &self.timers[0];
+        if pomodoro.mode != PomodoroMode::LongBreak || !pomodoro.is_running() {
+            self.lock_at = None;
+            return;
+        }
+        let left = lock_at.saturating_duration_since(Instant::now());
+        if left.is_zero() {
+            self.lock_at = None;
+            if let Err(e) = screen_lock::lock() {
+                tracing::warn!("could not lock the screen: {e}");
+                self.activity
+                    .push(format!("Could not lock the screen: {e}"));
+            }
+            return;
+        }
+        egui::Window::new("Long break")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .show(ctx, |ui| {
+                ui.heading(format!(
+                    "Locking the screen in {} s",
+                    left.as_secs_f32().ceil()
+                ));
+                ui.label("Time to step away.");
+                if ui.button("Don't lock").clicked() {
+                    self.lock_at = None;
+                }
+            });
+        ctx.request_repaint_after(Duration::from_millis(250));
+    }
+
+    /// Dims the screen during breaks. Returns whether the overlay took over the window.
+    fn show_break_overlay(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) -> bool {
+        let Self {
+            settings,
+            timers,
+            window_pos,
+            window_size,
+            zen,
+            dimmed,
+            overlay_monitors,
+            dim_dismissed,
+            ..
+        } = self;
+        let pomodoro = &timers[0];
+
+        if *dimmed && ctx.input(|i| i.key_pressed(egui::Key::Escape)) {
+            *dim_dismissed = true;
+        }
+        let dim = settings.dim_breaks
+            && !*dim_dismissed
+            && pomodoro.is_running()
+            && pomodoro.mode != PomodoroMode::Work;
+        if dim != *dimmed {
+            if dim {
+                let native = frame.info().native_pixels_per_point.unwrap_or(1.0);
+                let monitors = match settings.dim_screen {
+                    OverlayScreen::Current => Vec::new(),
+                    OverlayScreen::Primary | OverlayScreen::All => monitors::all(),
+                };
+                let primary = monitors.iter().find(|m| m.primary);
+                match (settings.dim_screen, monitors::desktop(&monitors), primary) {
+                    (OverlayScreen::All, Some(desktop), _) => {
+                        frame.set_decorations(false);
+                        let desktop_in_points = monitors::in_points(desktop, native);
+                        frame.set_window_pos(desktop_in_points.min);
+                        frame.set_window_size(desktop_in_points.size());
+                        *overlay_monitors = monitors
+                            .iter()
+                            .map(|m| m.rect.translate(-desktop.min.to_vec2()))
+                            .collect();
+                    }
+                    (OverlayScreen::Primary, _, Some(primary)) => {
+                        frame.set_window_pos(monitors::in_points(primary.rect, native).min);
+                        frame.set_fullscreen(true);
+                    }
+                    // The monitors couldn't be listed, so this one will have to do.
+                    _ => frame.set_fullscreen(true),
+                }
+            } else {
+                frame.set_fullscreen(*zen);
+                if !overlay_monitors.is_empty() {
+                    overlay_monitors.clear();
+                    frame.set_decorations(true);
+                    if let Some([width, height]) = window_size {
+                        frame.set_window_size(egui::vec2(*width, *height));
+                    }
+                }
+                if let Some((pos, _)) = window_pos {
+                    frame.set_window_pos(egui::Pos2::from(*pos));
+                }
+            }
+            // eframe can only make a window click-through when it opens, so
+            // instead the overlay stops staying on top and lets notifications
+            // and other windows come up over it.
+            frame.set_always_on_top(dim && !settings.dim_click_through);
+            *dimmed = dim;
+        }
+
+        if let (true, Some(time_left)) = (dim, pomodoro.time_left()) {
+            let break_length = pomodoro.length(settings);
+            let elapsed = (break_length - time_left).num_milliseconds() as f32 / 1000.0;
+            let fade = if settings.reduce_motion {
+                1.0
+            } else {
+                (elapsed / DIM_FADE_SECS).clamp(0.0, 1.0)
+            };
+            paint_break_overlay(
+                ctx,
+                overlay_monitors,
+                settings.dim_opacity * fade,
+                &format_duration(time_left, pomodoro.mode, settings.hide_seconds),
+                settings.color(pomodoro.mode),
+            );
+            if fade < 1.0 {
+                ctx.request_repaint_after(Duration::from_millis(100));
+            } else {
+                self.schedule_repaint(ctx);
+            }
+            return true;
+        }
+        false
+    }
+}
+
+/// Keeps `worker` running with `config` while `wanted`, starting it over whenever
+/// `config` changes. The latest problem is kept in `error` for showing, and all of them
+/// are returned.
+fn keep_worker<C: Clone + PartialEq + Send + 'static, T: Send + 'static>(
+    worker: &mut Option<Worker<C, T>>,
+    config: &C,
+    wanted: bool,
+    start: fn(C) -> Worker<C, T>,
+    error: &mut Option<String>,
+) -> Vec<String> {
+    if !wanted {
+        *worker = None;
+        return Vec::new();
+    }
+    if worker.as_ref().map(Worker::config) != Some(config) {
+        *worker = Some(start(config.clone()));
+        *error = None;
+    }
+    let errors = worker.as_ref().map(Worker::take_errors).unwrap_or_default();
+    if let Some(e) = errors.last() {
+        *error = Some(e.clone());
+    }
+    errors
+}
+
+/// Keyboard focus looks like a press by default, which is easy to miss. Give it a clear
+/// ring instead.
+fn focus_ring(style: &mut egui::Style) {
+    style.visuals.widgets.active.bg_stroke =
+        egui::Stroke::new(2.0, style.visuals.selection.stroke.color);
+}
+
+fn notify(body: &str) {
+    notify_with_sound(body, "");
+}
+
+/// Like [`notify`], playing the named system sound unless `sound` is empty.
+fn notify_with_sound(body: &str, sound: &str) {
+    let mut notification = Notification::new();
+    notification.summary("epomo").body(body);
+    if !sound.trim().is_empty() {
+        notification.sound_name(sound.trim());
+    }
+    // A missing notification daemon shouldn't take the timer down with it.
+    if let Err(e) = notification.show() {
+        tracing::warn!("could not show a notification: {e}");
+    }
+}
+
+/// Time until the soonest running timer's countdown shows a different second.
+fn next_second(timers: &[Timer]) -> Option<Duration> {
+    timers
+        .iter()
+        .filter(|t| t.is_running())
+        .filter_map(|t| t.grace_left().or_else(|| t.time_left()))
+        .map(|left| {
+            // The display truncates to whole seconds, so it changes when the remaining
+            // milliseconds run out. The extra millisecond makes sure we land past it.
+            Duration::from_millis(left.num_milliseconds().rem_euclid(1000) as u64 + 1)
+        })
+        .min()
+}
+
+/// The countdown as shown big, in whole minutes with `hide_seconds`. Minutes are
+/// rounded up, so it reads 00:01 until the very end.
+fn format_countdown(duration: chrono::Duration, hide_seconds: bool) -> String {
+    if !hide_seconds {
+        return format_time(duration);
+    }
+    let minutes = (duration.num_seconds() + 59) / 60;
+    format!("{:02}:{:02}", minutes / 60, minutes % 60)
+}
+
+fn format_duration(duration: chrono::Duration, mode: PomodoroMode, hide_seconds: bool) -> String {
+    format!("{} {}", format_countdown(duration, hide_seconds), mode)
+}
+
+/// Fades towards `target` over a second, so mode colors blend instead of snapping at
+/// each transition. Snaps anyway with reduced motion, which turns egui's own
+/// animations off.
+fn animated_color(ctx: &egui::Context, id: &str, target: Color32) -> Color32 {
+    if ctx.style().animation_time <= 0.0 {
+        return target;
+    }
+    let id = egui::Id::new(id);
+    let [r, g, b, _] = target.to_array();
+    let channel = |i: usize, value: u8| {
+        ctx.animate_value_with_time(id.with(i), value as f32, 1.0)
+            .round() as u8
+    };
+    Color32::from_rgb(channel(0, r), channel(1, g), channel(2, b))
+}
+
+/// Mixes `t` of `b` into `a`. Unlike drawing a translucent color on top, the result
+/// stays opaque, which matters because the window itself is transparent.
+fn blend(a: Color32, b: Color32, t: f32) -> Color32 {
+    let mix = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * t).round() as u8;
+    Color32::from_rgb(mix(a.r(), b.r()), mix(a.g(), b.g()), mix(a.b(), b.b()))
+}
+
+/// How long the break overlay takes to fade in to its full opacity.
+const DIM_FADE_SECS: f32 = 30.0;
+
+/// Darkens the whole window and shows the remaining break time on top of it, in the
+/// middle of each of `monitors` (in pixels) or else of the window.
+fn paint_break_overlay(
+    ctx: &egui::Context,
+    monitors: &[egui::Rect],
+    opacity: f32,
+    label: &str,
+    color: Color32,
+) {
+    let painter = ctx.layer_painter(egui::LayerId::new(
+        egui::Order::Foreground,
+        egui::Id::new("break_overlay"),
+    ));
+    let screen = ctx.screen_rect();
+    painter.rect_filled(
+        screen,
+        0.0,
+        Color32::from_black_alpha((opacity * 255.0) as u8),
+    );
+    let centers: Vec<_> = if monitors.is_empty() {
+        vec![screen.center()]
+    } else {
+        monitors
+            .iter()
+            .map(|&m| monitors::in_points(m, ctx.pixels_per_point()).center())
+            .collect()
+    };
+    for center in centers {
+        painter.text(
+            center,
+            egui::Align2::CENTER_CENTER,
+            label,
+            egui::FontId::proportional(48.0),
+            color,
+        );
+        painter.text(
+            center + egui::vec2(0.0, 48.0),
+            egui::Align2::CENTER_CENTER,
+            "Press Esc to return",
+            egui::FontId::proportional(14.0),
+            Color32::GRAY,
+        );
+    }
+}
+
+impl eframe::App for EpomoApp {
+    /// Called by the frame work to save state before shutdown, and every
+    /// [`AUTOSAVE_INTERVAL`] while running.
+    fn save(&mut self, _storage: &mut dyn eframe::Storage) {
+        self.persist();
+    }
+
+    fn auto_save_interval(&self) -> Duration {
+        AUTOSAVE_INTERVAL
+    }
+
+    // With a custom data directory, eframe's own storage is left alone.
+    fn persist_native_window(&self) -> bool {
+        paths::custom_data_dir().is_none()
+    }
+
+    fn persist_egui_memory(&self) -> bool {
+        paths::custom_data_dir().is_none()
+    }
+
+    fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
+        // Unlike in `tick`, this waits for the hosts file, elevation prompt and all: the
+        // window is going away anyway, and a worker thread wouldn't outlive the process
+        // to finish the job, leaving the sites blocked. A change still underway may
+        // block them too.
+        if self.sites_blocked || self.block_result.is_some() {
+            let _ = blocker::set_blocked(&[]);
+        }
+    }
+
+    /// The window is transparent so the break overlay can dim what's behind it.
+    fn clear_color(&self, _visuals: &egui::Visuals) -> [f32; 4] {
+        egui::Rgba::TRANSPARENT.to_array()
+    }
+
+    /// Called each time the UI needs repainting, which may be many times per second.
+    /// Put your widgets into a `SidePanel`, `TopPanel`, `CentralPanel`, `Window` or `Area`.
+    fn update(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
+        self.poll_sync();
+        if self.sync_result.is_none()
+            && self
+                .last_sync
+                .map_or(true, |t| t.elapsed() >= SYNC_INTERVAL)
+        {
+            self.start_sync();
+        }
+        if self.remote.is_configured()
+            && self.remote_result.is_none()
+            && self
+                .last_remote_sync
+                .map_or(true, |t| t.elapsed() >= REMOTE_SYNC_INTERVAL)
+        {
+            self.start_remote_sync();
+        }
+        self.poll_remote_sync();
+        self.finish_calendar_sign_in();
+        self.run_workers();
+        self.write_due_report();
+        self.check_for_update();
+
+        self.track_window(frame);
+        self.apply_zoom(ctx, frame);
+        self.apply_theme(ctx);
+        self.apply_accent(ctx);
+        self.apply_motion(ctx);
+        self.tick(ctx);
+        self.handle_access_keys(ctx, frame);
+        self.handle_media_key(ctx, frame);
+        self.update_tray();
+        self.handle_events(ctx);
+        self.show_lock_warning(ctx);
+        if self.show_break_overlay(ctx, frame) || self.show_zen(ctx, frame) {
+            return;
+        }
+
+        if let Some(problem) = &self.save_error {
+            let mut retry = false;
+            egui::TopBottomPanel::top("save_error").show(ctx, |ui| {
+                ui.horizontal_wrapped(|ui| {
+                    ui.colored_label(Color32::RED, problem.as_str());
+                    retry = ui.small_button("Try again").clicked();
+                });
+            });
+            if retry {
+                self.persist();
+            }
+        }
+        egui::TopBottomPanel::top("tabs").show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                for (i, tab) in Tab::ALL.into_iter().enumerate() {
+                    ui.selectable_value(&mut self.tab, tab, tab.name())
+                        .on_hover_text(format!("Alt+{}", i + 1));
+                }
+                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                    if let Some(release) = self
+                        .latest_release
+                        .as_ref()
+                        .filter(|r| self.settings.check_updates && r.is_newer())
+                    {
+                        ui.hyperlink_to(format!("v{} available", release.version), &release.url);
+                    }
+                    if ui.small_button("⛶").on_hover_text("Zen mode (F)").clicked() {
+                        self.zen = true;
+                        frame.set_fullscreen(true);
+                    }
+                    if ui
+                        .selectable_label(self.settings_open, "⚙")
+                        .on_hover_text("Settings (Alt+O)")
+                        .clicked()
+                    {
+                        self.settings_open = !self.settings_open;
+                    }
+                    if self.settings.profiles.len() > 1 {
+                        let settings = &mut self.settings;
+                        egui::ComboBox::from_id_source("header_profile")
+                            .selected_text(settings.profile().name.as_str())
+                            .show_ui(ui, |ui| {
+                                for (i, profile) in settings.profiles.iter().enumerate() {
+                                    ui.selectable_value(
+                                        &mut settings.active_profile,
+                                        i,
+                                        &profile.name,
+                                    );
+                                }
+                            })
+                            .response
+                            .on_hover_text("Profile");
+                    }
+                });
+            });
+        });
+        let mut panel = egui::Frame::central_panel(&ctx.style());
+        if self.settings.tint_panel {
+            let pomodoro = &self.timers[0];
+            let color = animated_color(ctx, "panel_tint", self.settings.color(pomodoro.mode));
+            panel = panel.fill(blend(panel.fill, color, 0.2));
+        }
+        egui::CentralPanel::default().frame(panel).show(ctx, |ui| {
+            egui::ScrollArea::vertical().show(ui, |ui| match self.tab {
+                Tab::Timer => self.timer_view(ui),
+                Tab::Tasks => self.tasks_view(ui),
+                Tab::Stats => self.stats_view(ui),
+                Tab::Activity => self.activity_view(ui),
+            });
+        });
+        let mut settings_open = self.settings_open;
+        egui::Window::new("Settings")
+            .open(&mut settings_open)
+            .collapsible(false)
+            .vscroll(true)
+            .show(ctx, |ui| {
+                self.settings_view(ui);
+                ui.collapsing("About", |ui| self.about_view(ui));
+            });
+        self.settings_open = settings_open;
+        self.show_wizard(ctx);
+        self.show_palette(ctx, frame);
+        // Pick up anything the views just did, like starting or skipping a phase.
+        self.handle_events(ctx);
+        if let Some(confetti) = &mut self.confetti {
+            if !confetti.paint(ctx) {
+                self.confetti = None;
+            }
+        }
+        self.schedule_repaint(ctx);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn state_from_before_profiles_keeps_its_durations() {
+        let mut state = serde_json::json!({
+            "interval_period": 50,
+            "long_break_period": 20,
+            "short_break_period": 10,
+            "session_count": 3,
+            "show_notifs": false,
         });
+        assert!(migrate_state(&mut state));
+        let app = from_state(state).unwrap();
+        let profile = &app.settings.profiles[0];
+        assert_eq!(profile.interval_period, 50);
+        assert_eq!(profile.long_break_period, 20);
+        assert_eq!(profile.short_break_period, 10);
+        assert!(!app.settings.show_notifs);
+
+        let mut current = serde_json::to_value(EpomoApp::default()).unwrap();
+        assert!(!migrate_state(&mut current));
     }
 }
@@ -0,0 +1,139 @@
+use std::{io, path::Path};
+
+use super::EpomoApp;
+use crate::paths;
+
+/// Total size of the files directly in `dir`, and how many there are.
+fn dir_size(dir: &Path) -> io::Result<(u64, usize)> {
+    let mut total = (0, 0);
+    for entry in std::fs::read_dir(dir)? {
+        let metadata = entry?.metadata()?;
+        if metadata.is_file() {
+            total.0 += metadata.len();
+            total.1 += 1;
+        }
+    }
+    Ok(total)
+}
+
+fn format_bytes(bytes: u64) -> String {
+    match bytes {
+        0..=1023 => format!("{bytes} B"),
+        1024..=1_048_575 => format!("{:.1} KB", bytes as f64 / 1024.0),
+        _ => format!("{:.1} MB", bytes as f64 / 1_048_576.0),
+    }
+}
+
+impl EpomoApp {
+    /// What version this is, where it keeps its files and how each integration is
+    /// doing, for bug reports.
+    fn diagnostics(&self) -> Vec<(&'static str, String)> {
+        let build = if cfg!(debug_assertions) {
+            "debug"
+        } else {
+            "release"
+        };
+        let storage = if cfg!(feature = "sqlite") {
+            "SQLite"
+        } else {
+            "app state"
+        };
+        let mut rows = vec![
+            ("Version", env!("CARGO_PKG_VERSION").to_owned()),
+            (
+                "Build",
+                format!(
+                    "{}-{}, {build}",
+                    std::env::consts::OS,
+                    std::env::consts::ARCH
+                ),
+            ),
+            ("Storage", storage.to_owned()),
+        ];
+        match paths::data_dir() {
+            Some(dir) => {
+                let size = match dir_size(&dir) {
+                    Ok((bytes, files)) => format!("{} in {files} files", format_bytes(bytes)),
+                    Err(e) => format!("unknown: {e}"),
+                };
+                rows.push(("Data directory", dir.display().to_string()));
+                rows.push(("Data size", size));
+            }
+            None => rows.push(("Data directory", "none".to_owned())),
+        }
+        rows.push((
+            "History",
+            format!(
+                "{} sessions, {} stopped early, {} tasks",
+                self.history.sessions.len(),
+                self.history.abandoned.len(),
+                self.tasks.tasks.len()
+            ),
+        ));
+
+        let sharing =
+            self.lan_host.is_some() || self.room_host.is_some() || self.follower.is_some();
+        let integrations = [
+            ("Tray icon", self.settings.tray_icon, &self.tray_error),
+            ("Media key", self.settings.media_key, &self.media_key_error),
+            (
+                "Site blocking",
+                self.settings.block_sites,
+                &self.block_error,
+            ),
+            ("Sync folder", self.sync_dir.is_some(), &self.sync_error),
+            (
+                "Server sync",
+                self.remote.is_configured(),
+                &self.remote_status,
+            ),
+            ("Notion", self.notion.is_configured(), &self.notion_error),
+            ("ntfy", self.ntfy.is_configured(), &self.ntfy_error),
+            (
+                "Google Calendar",
+                self.calendar.is_signed_in(),
+                &self.calendar_error,
+            ),
+            ("Obsidian", self.settings.obsidian_vault.is_some(), &None),
+            ("Shared timer", sharing, &self.share_error),
+            ("Update check", self.settings.check_updates, &None),
+            ("App tracking", self.settings.track_apps, &None),
+        ];
+        for (name, on, last) in integrations {
+            let status = match (on, last) {
+                (false, _) => "off".to_owned(),
+                (true, None) => "on".to_owned(),
+                (true, Some(last)) => format!("on, last: {last}"),
+            };
+            rows.push((name, status));
+        }
+        if let Some(e) = &self.theme_error {
+            rows.push(("Theme file", e.clone()));
+        }
+        if let Some(e) = &self.save_error {
+            rows.push(("Last save error", e.clone()));
+        }
+        if let Some(e) = &self.file_error {
+            rows.push(("Last file error", e.clone()));
+        }
+        rows
+    }
+
+    pub(super) fn about_view(&mut self, ui: &mut egui::Ui) {
+        let rows = self.diagnostics();
+        egui::Grid::new("diagnostics").striped(true).show(ui, |ui| {
+            for (name, value) in &rows {
+                ui.label(*name);
+                ui.label(value.as_str());
+                ui.end_row();
+            }
+        });
+        if ui.button("Copy diagnostics").clicked() {
+            let text: String = rows
+                .iter()
+                .map(|(name, value)| format!("{name}: {value}\n"))
+                .collect();
+            ui.output_mut(|o| o.copied_text = text);
+        }
+    }
+}
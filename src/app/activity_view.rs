@@ -0,0 +1,33 @@
+use super::EpomoApp;
+use crate::clock::format_clock;
+
+impl EpomoApp {
+    pub(super) fn activity_view(&mut self, ui: &mut egui::Ui) {
+        let Self {
+            settings, activity, ..
+        } = self;
+
+        if activity.is_empty() {
+            ui.weak("Nothing has happened yet.");
+            return;
+        }
+        if ui.small_button("Clear").clicked() {
+            activity.clear();
+            return;
+        }
+        let today = chrono::Local::now().date_naive();
+        egui::Grid::new("activity").striped(true).show(ui, |ui| {
+            for entry in activity.entries() {
+                let at = entry.at.with_timezone(&chrono::Local);
+                let time = format_clock(&at, settings.clock_format);
+                if at.date_naive() == today {
+                    ui.weak(time);
+                } else {
+                    ui.weak(format!("{} {time}", at.format("%b %-d")));
+                }
+                ui.label(entry.text.as_str());
+                ui.end_row();
+            }
+        });
+    }
+}
@@ -0,0 +1,73 @@
+use std::time::Instant;
+
+use egui::{Color32, Pos2, Rect, Vec2};
+
+/// How long a burst lasts, in seconds.
+const LIFETIME: f32 = 2.0;
+/// Downward acceleration in points per second squared.
+const GRAVITY: f32 = 600.0;
+
+struct Particle {
+    pos: Pos2,
+    vel: Vec2,
+    color: Color32,
+    size: f32,
+}
+
+/// A short burst of confetti falling over the window.
+pub struct Confetti {
+    particles: Vec<Particle>,
+    started_at: Instant,
+    last_frame: Instant,
+}
+
+impl Confetti {
+    /// Throws `count` pieces up from the bottom of `rect`.
+    pub fn burst(rect: Rect, count: usize, colors: &[Color32]) -> Self {
+        let particles = (0..count)
+            .map(|_| Particle {
+                pos: egui::pos2(rect.left() + fastrand::f32() * rect.width(), rect.bottom()),
+                vel: egui::vec2(
+                    (fastrand::f32() - 0.5) * 200.0,
+                    -(300.0 + fastrand::f32() * 400.0),
+                ),
+                color: colors[fastrand::usize(..colors.len())],
+                size: 2.0 + fastrand::f32() * 3.0,
+            })
+            .collect();
+        let now = Instant::now();
+        Self {
+            particles,
+            started_at: now,
+            last_frame: now,
+        }
+    }
+
+    /// Moves and draws the particles. Returns false once the burst is over.
+    pub fn paint(&mut self, ctx: &egui::Context) -> bool {
+        let age = self.started_at.elapsed().as_secs_f32();
+        if age >= LIFETIME {
+            return false;
+        }
+        let dt = self.last_frame.elapsed().as_secs_f32();
+        self.last_frame = Instant::now();
+
+        let painter = ctx.layer_painter(egui::LayerId::new(
+            egui::Order::Foreground,
+            egui::Id::new("confetti"),
+        ));
+        // Fade out over the last half second.
+        let alpha = ((LIFETIME - age) / 0.5).min(1.0);
+        for p in &mut self.particles {
+            p.vel.y += GRAVITY * dt;
+            p.pos += p.vel * dt;
+            painter.rect_filled(
+                Rect::from_center_size(p.pos, Vec2::splat(p.size)),
+                0.0,
+                p.color.linear_multiply(alpha),
+            );
+        }
+        ctx.request_repaint();
+        true
+    }
+}
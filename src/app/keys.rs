@@ -0,0 +1,79 @@
+use egui::{Key, Modifiers};
+
+use super::{palette::Command, EpomoApp, Tab};
+use crate::media_key::MediaKey;
+
+impl EpomoApp {
+    /// Alt+letter shortcuts for the main controls, so the app can be run without a
+    /// mouse. Tab and Shift+Tab move between controls, Space or Enter presses them.
+    pub(super) fn handle_access_keys(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
+        let pomodoro = &self.timers[0];
+        let (paused, running) = (pomodoro.is_paused(), pomodoro.is_running());
+        let mut commands = Vec::new();
+        for (key, tab) in [Key::Num1, Key::Num2, Key::Num3, Key::Num4]
+            .into_iter()
+            .zip(Tab::ALL)
+        {
+            commands.push((key, Some(Command::ShowTab(tab))));
+        }
+        commands.extend([
+            (Key::O, Some(Command::ToggleSettings)),
+            (
+                Key::S,
+                if paused {
+                    Some(Command::Resume)
+                } else {
+                    (!running).then_some(Command::Start)
+                },
+            ),
+            (
+                Key::P,
+                if paused {
+                    Some(Command::Resume)
+                } else {
+                    running.then_some(Command::Pause)
+                },
+            ),
+            (Key::N, (running || paused).then_some(Command::Skip)),
+            (Key::X, (running || paused).then_some(Command::Stop)),
+        ]);
+        for (key, command) in commands {
+            if ctx.input_mut(|i| i.consume_key(Modifiers::ALT, key)) {
+                if let Some(command) = command {
+                    self.run_command(command, frame);
+                }
+            }
+        }
+    }
+
+    /// Registers or drops the media key as the setting changes, and toggles the timer
+    /// when it's pressed: starts it, pauses it or resumes it.
+    pub(super) fn handle_media_key(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
+        if !self.settings.media_key {
+            self.media_key = None;
+            self.media_key_error = None;
+            return;
+        }
+        if self.media_key.is_none() && self.media_key_error.is_none() {
+            match MediaKey::register(ctx) {
+                Ok(key) => self.media_key = Some(key),
+                Err(e) => {
+                    tracing::warn!("could not register the media key: {e}");
+                    self.media_key_error = Some(e);
+                }
+            }
+        }
+        if !self.media_key.as_ref().map_or(false, MediaKey::take_press) {
+            return;
+        }
+        let pomodoro = &self.timers[0];
+        let command = if pomodoro.is_paused() {
+            Command::Resume
+        } else if pomodoro.is_running() {
+            Command::Pause
+        } else {
+            Command::Start
+        };
+        self.run_command(command, frame);
+    }
+}
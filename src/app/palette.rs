@@ -0,0 +1,191 @@
+use std::cmp::Reverse;
+
+use egui::{Key, Modifiers};
+
+use super::{timer_view::start_pomodoro, EpomoApp, Tab};
+
+/// Something the user can do from the keyboard, through an access key or the palette.
+#[derive(Clone, Copy, PartialEq)]
+pub(super) enum Command {
+    Start,
+    Pause,
+    Resume,
+    Skip,
+    Stop,
+    Zen,
+    ShowTab(Tab),
+    ToggleSettings,
+    SwitchProfile(usize),
+    /// Tags the sessions from now on with the tag at this place in
+    /// [`History::sessions_by_tag`](crate::history::History::sessions_by_tag).
+    SetTag(usize),
+    ClearTag,
+}
+
+/// Scores how well `query` matches `text` as a subsequence, or `None` if it doesn't.
+/// Runs of consecutive letters and matches at the start of words count for more.
+fn fuzzy_score(query: &str, text: &str) -> Option<i32> {
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+    let mut score = 0;
+    let mut from = 0;
+    for q in query.to_lowercase().chars().filter(|c| !c.is_whitespace()) {
+        let i = from + text[from..].iter().position(|&c| c == q)?;
+        score += 1;
+        if from > 0 && i == from {
+            score += 2;
+        }
+        if i == 0 || text[i - 1] == ' ' {
+            score += 3;
+        }
+        from = i + 1;
+    }
+    Some(score)
+}
+
+impl EpomoApp {
+    /// The commands that make sense right now, with their names.
+    fn commands(&self) -> Vec<(String, Command)> {
+        let pomodoro = &self.timers[0];
+        let mut commands = Vec::new();
+        if self.follower.is_none() {
+            if pomodoro.is_paused() {
+                commands.push(("Resume".to_owned(), Command::Resume));
+            } else if pomodoro.is_running() {
+                commands.push(("Pause".to_owned(), Command::Pause));
+            } else {
+                commands.push(("Start".to_owned(), Command::Start));
+            }
+            if pomodoro.is_running() || pomodoro.is_paused() {
+                commands.push(("Skip".to_owned(), Command::Skip));
+                commands.push(("Stop".to_owned(), Command::Stop));
+            }
+        }
+        commands.push(("Zen mode".to_owned(), Command::Zen));
+        for tab in Tab::ALL {
+            commands.push((format!("Open {}", tab.name()), Command::ShowTab(tab)));
+        }
+        commands.push(("Settings".to_owned(), Command::ToggleSettings));
+        for (i, profile) in self.settings.profiles.iter().enumerate() {
+            if i != self.settings.active_profile {
+                commands.push((
+                    format!("Switch to profile {}", profile.name),
+                    Command::SwitchProfile(i),
+                ));
+            }
+        }
+        for (i, (tag, _)) in self.history.sessions_by_tag().into_iter().enumerate() {
+            if self.tag.as_ref() != Some(&tag) {
+                commands.push((format!("Tag #{tag}"), Command::SetTag(i)));
+            }
+        }
+        if self.tag.is_some() {
+            commands.push(("Clear tag".to_owned(), Command::ClearTag));
+        }
+        commands
+    }
+
+    pub(super) fn run_command(&mut self, command: Command, frame: &mut eframe::Frame) {
+        let until = self.until_time();
+        let Self {
+            settings,
+            timers,
+            follower,
+            ..
+        } = self;
+        let pomodoro = &mut timers[0];
+        // A shared timer is run by its host.
+        let controls = follower.is_none();
+        match command {
+            Command::Start if controls => start_pomodoro(pomodoro, settings, until),
+            Command::Pause if controls => pomodoro.pause(),
+            Command::Resume if controls => pomodoro.resume(),
+            Command::Skip if controls => pomodoro.skip(settings),
+            Command::Stop if controls => pomodoro.stop(),
+            Command::Start | Command::Pause | Command::Resume | Command::Skip | Command::Stop => {}
+            Command::Zen => {
+                self.zen = true;
+                frame.set_fullscreen(true);
+            }
+            Command::ShowTab(tab) => self.tab = tab,
+            Command::ToggleSettings => self.settings_open = !self.settings_open,
+            Command::SwitchProfile(i) => settings.active_profile = i,
+            Command::SetTag(i) => {
+                if let Some((tag, _)) = self.history.sessions_by_tag().into_iter().nth(i) {
+                    self.tag = Some(tag);
+                }
+            }
+            Command::ClearTag => self.tag = None,
+        }
+    }
+
+    /// Opens on Ctrl+K (Cmd+K on a Mac): a search box over every command. Arrow keys pick
+    /// one, Enter runs it and Esc closes the palette.
+    pub(super) fn show_palette(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
+        if ctx.input_mut(|i| i.consume_key(Modifiers::COMMAND, Key::K)) {
+            self.palette_open = !self.palette_open;
+            self.palette_query.clear();
+            self.palette_selected = 0;
+        }
+        if !self.palette_open {
+            return;
+        }
+        let [up, down, enter, escape] = [Key::ArrowUp, Key::ArrowDown, Key::Enter, Key::Escape]
+            .map(|key| ctx.input_mut(|i| i.consume_key(Modifiers::NONE, key)));
+
+        let commands = self.commands();
+        let Self {
+            palette_query,
+            palette_selected,
+            ..
+        } = self;
+        let mut chosen = None;
+        egui::Window::new("Command palette")
+            .title_bar(false)
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_TOP, egui::vec2(0.0, 40.0))
+            .show(ctx, |ui| {
+                let edit = ui.add(
+                    egui::TextEdit::singleline(palette_query)
+                        .hint_text("Type a command")
+                        .desired_width(f32::INFINITY),
+                );
+                edit.request_focus();
+                if edit.changed() {
+                    *palette_selected = 0;
+                }
+                let mut matches: Vec<(i32, &String, Command)> = commands
+                    .iter()
+                    .filter_map(|(name, command)| {
+                        Some((fuzzy_score(palette_query, name)?, name, *command))
+                    })
+                    .collect();
+                matches.sort_by_key(|(score, ..)| Reverse(*score));
+                if matches.is_empty() {
+                    ui.weak("No matching command");
+                    return;
+                }
+                if down {
+                    *palette_selected = (*palette_selected + 1) % matches.len();
+                }
+                if up {
+                    *palette_selected =
+                        palette_selected.checked_sub(1).unwrap_or(matches.len() - 1);
+                }
+                *palette_selected = (*palette_selected).min(matches.len() - 1);
+                for (i, (_, name, command)) in matches.iter().enumerate() {
+                    let selected = i == *palette_selected;
+                    if ui.selectable_label(selected, name.as_str()).clicked() || (selected && enter)
+                    {
+                        chosen = Some(*command);
+                    }
+                }
+            });
+        if escape || chosen.is_some() {
+            self.palette_open = false;
+        }
+        if let Some(command) = chosen {
+            self.run_command(command, frame);
+        }
+    }
+}
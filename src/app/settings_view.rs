@@ -0,0 +1,901 @@
+use std::ops::RangeInclusive;
+
+use egui::{Button, Color32};
+
+use super::{EpomoApp, LOCK_WARNING};
+use crate::{
+    accent, autostart,
+    backup::Backup,
+    clock::ClockFormat,
+    gcal, history_import,
+    lan::{LanBrowser, LanHost},
+    room::{self, Follower, Hello, RoomHost},
+    settings::{LongBreakSkip, OverlayScreen, ReportFormat, Settings},
+    theme::ThemeFile,
+    timer::{self, PomodoroMode},
+};
+
+/// How synced settings are combined, shown with both kinds of sync.
+const SETTINGS_MERGE_NOTE: &str = "Settings are merged one by one. When the same setting \
+     was changed on two devices, the latest change wins.";
+
+/// A slider for a length in minutes with −5/+5 buttons beside it. Scrolling the mouse
+/// wheel over the slider moves it a minute at a time.
+fn minutes_slider(ui: &mut egui::Ui, enabled: bool, minutes: &mut i64, range: RangeInclusive<i64>) {
+    ui.add_enabled_ui(enabled, |ui| {
+        ui.horizontal(|ui| {
+            if ui.small_button("−5").clicked() {
+                *minutes -= 5;
+            }
+            let slider = ui.add(egui::Slider::new(minutes, range.clone()).suffix("m"));
+            if ui.small_button("+5").clicked() {
+                *minutes += 5;
+            }
+            if enabled && slider.hovered() {
+                let scroll = ui.input(|i| i.scroll_delta.y);
+                if scroll != 0.0 {
+                    *minutes += scroll.signum() as i64;
+                }
+            }
+            *minutes = (*minutes).clamp(*range.start(), *range.end());
+        });
+    });
+}
+
+impl EpomoApp {
+    pub(super) fn settings_view(&mut self, ui: &mut egui::Ui) {
+        let Self {
+            settings,
+            history,
+            tasks,
+            timers,
+            recent_durations,
+            device_id,
+            sync_dir,
+            remote,
+            notion,
+            notion_error,
+            ntfy,
+            ntfy_error,
+            calendar,
+            calendar_sign_in,
+            calendar_error,
+            sites_blocked,
+            block_error,
+            file_error,
+            media_key_error,
+            tray_error,
+            theme_error,
+            autostart_enabled,
+            import_preview,
+            last_sync,
+            sync_error,
+            remote_result,
+            last_remote_sync,
+            remote_status,
+            lan_host,
+            lan_browser,
+            follower,
+            room_host,
+            room_code,
+            share_error,
+            ..
+        } = self;
+        let pomodoro = &timers[0];
+
+        ui.horizontal(|ui| {
+            ui.strong("Profile");
+            egui::ComboBox::from_id_source("profile")
+                .selected_text(settings.profile().name.as_str())
+                .show_ui(ui, |ui| {
+                    for (i, profile) in settings.profiles.iter().enumerate() {
+                        ui.selectable_value(&mut settings.active_profile, i, &profile.name);
+                    }
+                });
+        });
+        ui.vertical(|ui| {
+            ui.label("Interval time in minutes");
+            minutes_slider(
+                ui,
+                !pomodoro.is_running() || pomodoro.mode != PomodoroMode::Work,
+                &mut settings.profile_mut().interval_period,
+                1..=120,
+            );
+        });
+        ui.vertical(|ui| {
+            ui.label("Short break time in minutes");
+            minutes_slider(
+                ui,
+                pomodoro.mode != PomodoroMode::ShortBreak,
+                &mut settings.profile_mut().short_break_period,
+                1..=30,
+            );
+        });
+        ui.vertical(|ui| {
+            ui.label("Long break time in minutes");
+            minutes_slider(
+                ui,
+                pomodoro.mode != PomodoroMode::LongBreak,
+                &mut settings.profile_mut().long_break_period,
+                1..=120,
+            );
+        });
+        ui.vertical(|ui| {
+            ui.label("Work sessions before a long break");
+            ui.add(egui::Slider::new(
+                &mut settings.profile_mut().sessions_per_long_break,
+                1..=12,
+            ));
+        });
+        ui.horizontal(|ui| {
+            ui.label("Skipping a long break");
+            ui.selectable_value(
+                &mut settings.long_break_skip,
+                LongBreakSkip::EndCycle,
+                "ends the cycle",
+            )
+            .on_hover_text("The next long break comes after the usual number of sessions");
+            ui.selectable_value(
+                &mut settings.long_break_skip,
+                LongBreakSkip::CarryOver,
+                "saves it for later",
+            )
+            .on_hover_text("The next work session is followed by the long break instead");
+        });
+        if recent_durations.len() > 1 {
+            let idle = !pomodoro.is_running() && !pomodoro.is_paused();
+            ui.add_enabled_ui(idle, |ui| {
+                ui.horizontal_wrapped(|ui| {
+                    ui.label("Recent");
+                    for &[work, short, long] in recent_durations.iter() {
+                        let profile = settings.profile_mut();
+                        let current = [
+                            profile.interval_period,
+                            profile.short_break_period,
+                            profile.long_break_period,
+                        ] == [work, short, long];
+                        if ui
+                            .selectable_label(current, format!("{work}/{short}/{long}"))
+                            .on_hover_text(format!(
+                                "{work} min work, {short} min short breaks, {long} min long breaks"
+                            ))
+                            .on_disabled_hover_text("Stop the timer to switch")
+                            .clicked()
+                        {
+                            profile.interval_period = work;
+                            profile.short_break_period = short;
+                            profile.long_break_period = long;
+                        }
+                    }
+                });
+            });
+        }
+        ui.horizontal(|ui| {
+            ui.label("Wait between phases");
+            ui.add(
+                egui::DragValue::new(&mut settings.grace_secs)
+                    .clamp_range(0..=timer::MAX_GRACE_SECS)
+                    .suffix("s"),
+            )
+            .on_hover_text("A short countdown before the next phase starts");
+        });
+        ui.horizontal(|ui| {
+            ui.label("Vary work sessions by ±");
+            ui.add(
+                egui::DragValue::new(&mut settings.jitter_minutes)
+                    .clamp_range(0..=timer::MAX_JITTER_MINUTES)
+                    .suffix("m"),
+            )
+            .on_hover_text("Each work session is this much longer or shorter, at random");
+        });
+        ui.horizontal(|ui| {
+            ui.label("Daily limit");
+            ui.add(
+                egui::DragValue::new(&mut settings.daily_max_sessions)
+                    .clamp_range(0..=24)
+                    .suffix(" sessions"),
+            )
+            .on_hover_text("0 for no limit");
+            ui.label("or");
+            ui.add(
+                egui::DragValue::new(&mut settings.daily_max_focus_minutes)
+                    .clamp_range(0..=16 * 60)
+                    .speed(5)
+                    .suffix("m of focus"),
+            )
+            .on_hover_text("0 for no limit");
+        });
+        let limited = settings.daily_max_sessions > 0 || settings.daily_max_focus_minutes > 0;
+        ui.add_enabled(
+            limited,
+            egui::Checkbox::new(
+                &mut settings.hold_at_daily_limit,
+                "Don't start work again once it's reached",
+            ),
+        );
+        ui.collapsing("Edit profile", |ui| {
+            ui.checkbox(
+                &mut settings.follow_accent,
+                "Work in the system accent color",
+            )
+            .on_hover_text("Untick to pick the work color yourself");
+            let follow_accent = settings.follow_accent;
+            if follow_accent && accent::system_accent().is_none() {
+                ui.weak("No accent color found, using the work color below");
+            }
+            let profile = settings.profile_mut();
+            ui.text_edit_singleline(&mut profile.name);
+            ui.horizontal(|ui| {
+                ui.add_enabled_ui(!follow_accent, |ui| {
+                    ui.color_edit_button_srgb(&mut profile.work_color);
+                });
+                ui.label("Work");
+                ui.color_edit_button_srgb(&mut profile.short_break_color);
+                ui.label("Short");
+                ui.color_edit_button_srgb(&mut profile.long_break_color);
+                ui.label("Long");
+            });
+            ui.horizontal(|ui| {
+                ui.label("Sound");
+                ui.text_edit_singleline(&mut profile.sound).on_hover_text(
+                    "The name of a system sound, e.g. complete on Linux or Glass on macOS. \
+                     Leave empty for the default.",
+                );
+            });
+            ui.horizontal(|ui| {
+                if ui.button("New").clicked() {
+                    let mut profile = settings.profile().clone();
+                    profile.name = format!("Profile {}", settings.profiles.len() + 1);
+                    settings.profiles.push(profile);
+                    settings.active_profile = settings.profiles.len() - 1;
+                }
+                if ui
+                    .add_enabled(settings.profiles.len() > 1, Button::new("Delete"))
+                    .clicked()
+                {
+                    settings.profiles.remove(settings.active_profile);
+                    settings.ensure_profile();
+                }
+            });
+        });
+        ui.collapsing("Weekly schedule", |ui| {
+            egui::Grid::new("weekday_profiles").show(ui, |ui| {
+                for (day, name) in ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"]
+                    .into_iter()
+                    .zip(settings.weekday_profiles.iter_mut())
+                {
+                    ui.label(day);
+                    egui::ComboBox::from_id_source(day)
+                        .selected_text(if name.is_empty() {
+                            "—"
+                        } else {
+                            name.as_str()
+                        })
+                        .show_ui(ui, |ui| {
+                            ui.selectable_value(name, String::new(), "—");
+                            for profile in &settings.profiles {
+                                ui.selectable_value(name, profile.name.clone(), &profile.name);
+                            }
+                        });
+                    ui.end_row();
+                }
+            });
+        });
+        ui.add(egui::Checkbox::new(
+            &mut settings.show_notifs,
+            "Show notifications",
+        ));
+        if settings.show_notifs {
+            ui.collapsing("Notification text", |ui| {
+                ui.weak("{mode}, {duration}, {sessions_today} and {task} are filled in.");
+                let templates = &mut settings.notif_templates;
+                egui::Grid::new("notif_templates").show(ui, |ui| {
+                    for (name, text) in [
+                        ("Work", &mut templates.work),
+                        ("Short break", &mut templates.short_break),
+                        ("Long break", &mut templates.long_break),
+                        ("Countdown", &mut templates.countdown),
+                    ] {
+                        ui.label(name);
+                        ui.text_edit_singleline(text);
+                        ui.end_row();
+                    }
+                });
+                ui.weak("{label} is the countdown's name.");
+                if ui.button("Reset").clicked() {
+                    *templates = Default::default();
+                }
+            });
+        }
+        ui.checkbox(
+            &mut settings.media_key,
+            "Play/pause media key controls the timer",
+        )
+        .on_hover_text("Other apps won't get the key while this is on");
+        if let Some(err) = media_key_error {
+            ui.colored_label(Color32::RED, err.as_str());
+        }
+        ui.checkbox(&mut settings.tray_icon, "Show the minutes left in the tray");
+        if let Some(err) = tray_error {
+            ui.colored_label(Color32::RED, err.as_str());
+        }
+        let autostart = autostart_enabled.get_or_insert_with(|| {
+            autostart::is_enabled().unwrap_or_else(|e| {
+                tracing::warn!("could not tell whether epomo starts with the system: {e}");
+                false
+            })
+        });
+        if ui.checkbox(autostart, "Start with the system").changed() {
+            if let Err(e) = autostart::set_enabled(*autostart) {
+                *file_error = Some(format!("Could not change starting with the system: {e}"));
+                *autostart = !*autostart;
+            }
+        }
+        ui.checkbox(&mut settings.check_updates, "Check for updates once a day")
+            .on_hover_text("Asks GitHub for the latest release");
+        ui.add(
+            egui::Slider::new(&mut settings.ui_scale, 0.5..=3.0)
+                .text("Zoom")
+                .custom_formatter(|n, _| format!("{:.0}%", n * 100.0)),
+        );
+        ui.checkbox(&mut settings.tint_panel, "Tint background by mode");
+        if let Some(path) = ThemeFile::path() {
+            ui.horizontal_wrapped(|ui| {
+                ui.label("Theme file");
+                ui.weak(path.display().to_string())
+                    .on_hover_text("Colors, fonts and spacing, applied whenever the file is saved");
+            });
+        }
+        if let Some(err) = theme_error {
+            ui.colored_label(Color32::RED, err.as_str());
+        }
+        ui.checkbox(&mut settings.reduce_motion, "Reduce motion")
+            .on_hover_text("No fades, confetti or other animation");
+        ui.checkbox(&mut settings.hide_seconds, "Hide seconds")
+            .on_hover_text("Show the countdown in whole minutes, so there's less to watch");
+        ui.horizontal(|ui| {
+            ui.label("Clock");
+            for format in ClockFormat::ALL {
+                ui.selectable_value(&mut settings.clock_format, format, format.name());
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.label("In the background, update every");
+            ui.add(
+                egui::DragValue::new(&mut settings.background_repaint_secs)
+                    .clamp_range(0..=60)
+                    .suffix("s"),
+            )
+            .on_hover_text("0 keeps updating every second");
+        });
+        ui.horizontal(|ui| {
+            ui.add(egui::Checkbox::new(
+                &mut settings.dim_breaks,
+                "Dim screen on breaks",
+            ));
+            ui.add_enabled(
+                settings.dim_breaks,
+                egui::DragValue::new(&mut settings.dim_opacity)
+                    .speed(0.01)
+                    .clamp_range(0.1..=1.0),
+            );
+        });
+        if settings.dim_breaks {
+            ui.indent("dim_options", |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Cover");
+                    ui.selectable_value(
+                        &mut settings.dim_screen,
+                        OverlayScreen::Current,
+                        "this screen",
+                    );
+                    ui.selectable_value(
+                        &mut settings.dim_screen,
+                        OverlayScreen::Primary,
+                        "the primary screen",
+                    );
+                    ui.selectable_value(
+                        &mut settings.dim_screen,
+                        OverlayScreen::All,
+                        "all screens",
+                    );
+                });
+                ui.checkbox(&mut settings.dim_click_through, "Let clicks through")
+                    .on_hover_text("Notifications and other windows can come up over the overlay");
+            });
+        }
+        ui.checkbox(
+            &mut settings.lock_on_long_break,
+            "Lock the screen when a long break starts",
+        )
+        .on_hover_text(format!(
+            "You get {} seconds' warning, and can call it off",
+            LOCK_WARNING.as_secs()
+        ));
+        ui.collapsing("Break suggestions", |ui| {
+            ui.weak("One per line. Each break shows the next one.");
+            ui.text_edit_multiline(&mut settings.break_suggestions);
+        });
+        ui.add(egui::Checkbox::new(
+            &mut settings.block_sites,
+            "Block sites while working",
+        ));
+        if settings.block_sites {
+            ui.add_enabled(
+                !*sites_blocked,
+                egui::TextEdit::singleline(&mut settings.blocked_sites)
+                    .hint_text("example.com, news.site"),
+            );
+        }
+        if let Some(err) = block_error {
+            ui.colored_label(Color32::RED, err.as_str());
+        }
+        if cfg!(target_os = "linux") {
+            ui.checkbox(
+                &mut settings.inhibit_idle,
+                "Keep the screen on while working",
+            );
+        }
+        ui.checkbox(&mut settings.track_apps, "Remember which app I work in")
+            .on_hover_text(
+                "Looks at the foreground app every 15 seconds during work sessions and \
+                 notes the one used most in your history. Only app names are kept, never \
+                 window titles.",
+            );
+        if settings.track_apps {
+            ui.collapsing("Tag rules", |ui| {
+                ui.weak("Suggest a tag when the app name or window title contains some text");
+                let mut remove = None;
+                egui::Grid::new("tag_rules").show(ui, |ui| {
+                    for (i, rule) in settings.tag_rules.iter_mut().enumerate() {
+                        ui.add(
+                            egui::TextEdit::singleline(&mut rule.pattern)
+                                .hint_text("Figma")
+                                .desired_width(90.0),
+                        );
+                        ui.label("→ #");
+                        ui.add(
+                            egui::TextEdit::singleline(&mut rule.tag)
+                                .hint_text("design")
+                                .desired_width(70.0),
+                        );
+                        if ui.small_button("✖").on_hover_text("Remove").clicked() {
+                            remove = Some(i);
+                        }
+                        ui.end_row();
+                    }
+                });
+                if let Some(i) = remove {
+                    settings.tag_rules.remove(i);
+                }
+                if ui.button("Add rule").clicked() {
+                    settings.tag_rules.push(Default::default());
+                }
+                ui.checkbox(
+                    &mut settings.auto_tag,
+                    "Tag untagged sessions automatically",
+                );
+            });
+        }
+        ui.checkbox(&mut settings.ask_energy, "Ask how each work session went")
+            .on_hover_text("A 1–5 rating after each session. Stats shows how it varies");
+        ui.horizontal(|ui| {
+            if ui.button("Export settings…").clicked() {
+                if let Some(path) = rfd::FileDialog::new()
+                    .add_filter("TOML", &["toml"])
+                    .set_file_name("epomo.toml")
+                    .save_file()
+                {
+                    *file_error = settings.export(&path).err().map(|e| e.to_string());
+                }
+            }
+            if ui.button("Import settings…").clicked() {
+                if let Some(path) = rfd::FileDialog::new()
+                    .add_filter("TOML", &["toml"])
+                    .pick_file()
+                {
+                    match Settings::import(&path) {
+                        Ok(imported) => {
+                            *settings = imported;
+                            *file_error = None;
+                        }
+                        Err(e) => *file_error = Some(e.to_string()),
+                    }
+                }
+            }
+        });
+        ui.horizontal(|ui| {
+            if ui.button("Back up…").clicked() {
+                if let Some(path) = rfd::FileDialog::new()
+                    .add_filter("epomo backup", &["json"])
+                    .set_file_name(&format!(
+                        "epomo-backup-{}.json",
+                        chrono::Local::now().format("%Y-%m-%d")
+                    ))
+                    .save_file()
+                {
+                    *file_error = Backup::new(settings, history, tasks)
+                        .write(&path)
+                        .err()
+                        .map(|e| e.to_string());
+                }
+            }
+            if ui.button("Restore…").clicked() {
+                if let Some(path) = rfd::FileDialog::new()
+                    .add_filter("epomo backup", &["json"])
+                    .pick_file()
+                {
+                    match Backup::read(&path) {
+                        Ok(backup) => {
+                            *settings = backup.settings;
+                            settings.ensure_profile();
+                            *history = backup.history;
+                            *tasks = backup.tasks;
+                            *file_error = None;
+                        }
+                        Err(e) => *file_error = Some(e.to_string()),
+                    }
+                }
+            }
+        });
+        ui.collapsing("Obsidian", |ui| {
+            ui.horizontal(|ui| {
+                if ui.button("Vault…").clicked() {
+                    if let Some(dir) = rfd::FileDialog::new().pick_folder() {
+                        settings.obsidian_vault = Some(dir);
+                    }
+                }
+                if settings.obsidian_vault.is_some() && ui.button("Off").clicked() {
+                    settings.obsidian_vault = None;
+                }
+            });
+            if let Some(vault) = &settings.obsidian_vault {
+                ui.small(format!(
+                    "Adding sessions to daily notes in {}",
+                    vault.display()
+                ));
+                ui.horizontal(|ui| {
+                    ui.label("Daily note");
+                    ui.text_edit_singleline(&mut settings.obsidian_note)
+                        .on_hover_text("Path within the vault. %Y, %m and %d are the date.");
+                });
+            }
+        });
+        ui.collapsing("Daily report", |ui| {
+            ui.horizontal(|ui| {
+                if ui.button("Folder…").clicked() {
+                    if let Some(dir) = rfd::FileDialog::new().pick_folder() {
+                        settings.report_dir = Some(dir);
+                    }
+                }
+                if settings.report_dir.is_some() && ui.button("Off").clicked() {
+                    settings.report_dir = None;
+                }
+            });
+            if let Some(dir) = &settings.report_dir {
+                ui.small(format!("Writing to {}", dir.display()));
+                ui.horizontal(|ui| {
+                    ui.selectable_value(
+                        &mut settings.report_format,
+                        ReportFormat::Markdown,
+                        "Markdown",
+                    );
+                    ui.selectable_value(&mut settings.report_format, ReportFormat::Json, "JSON");
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Write at");
+                    ui.add(
+                        egui::DragValue::new(&mut settings.report_hour)
+                            .clamp_range(0..=23)
+                            .custom_formatter(|n, _| format!("{n:02}:00")),
+                    );
+                });
+            }
+        });
+        if ui.button("Import history…").clicked() {
+            if let Some(path) = rfd::FileDialog::new()
+                .add_filter("Pomofocus, Focus To-Do or similar", &["csv", "json"])
+                .pick_file()
+            {
+                match history_import::read(&path) {
+                    Ok(sessions) => {
+                        *import_preview = Some(sessions);
+                        *file_error = None;
+                    }
+                    Err(e) => *file_error = Some(e.to_string()),
+                }
+            }
+        }
+        let mut close_preview = false;
+        if let Some(sessions) = import_preview {
+            let new = sessions
+                .iter()
+                .filter(|s| {
+                    !history
+                        .sessions
+                        .iter()
+                        .any(|h| h.started_at == s.started_at && h.ended_at == s.ended_at)
+                })
+                .count();
+            let minutes: i64 = sessions.iter().map(|s| s.duration().num_minutes()).sum();
+            ui.group(|ui| {
+                match (sessions.first(), sessions.last()) {
+                    (Some(first), Some(last)) => {
+                        let day = |t: chrono::DateTime<chrono::Utc>| {
+                            t.with_timezone(&chrono::Local).format("%Y-%m-%d")
+                        };
+                        ui.label(format!(
+                            "{} sessions, {}h {}m, {} to {}",
+                            sessions.len(),
+                            minutes / 60,
+                            minutes % 60,
+                            day(first.started_at),
+                            day(last.started_at)
+                        ));
+                        ui.small(format!("{new} of them aren't in your history yet"));
+                    }
+                    _ => {
+                        ui.label("No sessions found in that file");
+                    }
+                }
+                ui.horizontal(|ui| {
+                    if ui.add_enabled(new > 0, Button::new("Import")).clicked() {
+                        history.merge(sessions.drain(..));
+                        close_preview = true;
+                    }
+                    if ui.button("Cancel").clicked() {
+                        close_preview = true;
+                    }
+                });
+            });
+        }
+        if close_preview {
+            *import_preview = None;
+        }
+        ui.horizontal(|ui| {
+            if ui.button("Sync folder…").clicked() {
+                if let Some(dir) = rfd::FileDialog::new().pick_folder() {
+                    *sync_dir = Some(dir);
+                    *last_sync = None;
+                }
+            }
+            if sync_dir.is_some() && ui.button("Stop syncing").clicked() {
+                *sync_dir = None;
+                *sync_error = None;
+            }
+        });
+        if let Some(dir) = sync_dir {
+            ui.small(format!("Syncing via {}", dir.display()));
+            ui.weak(SETTINGS_MERGE_NOTE);
+        }
+        if let Some(err) = sync_error {
+            ui.colored_label(Color32::RED, err.as_str());
+        }
+        ui.collapsing("Shared timer", |ui| {
+            if let Some(host) = lan_host {
+                ui.label(format!("Hosting, {} joined", host.participant_count()));
+                if ui.button("Stop hosting").clicked() {
+                    *lan_host = None;
+                }
+            } else if let Some(host) = room_host {
+                ui.horizontal(|ui| {
+                    ui.label("Room code");
+                    ui.strong(host.code.as_str());
+                });
+                for name in host.participants() {
+                    ui.small(name);
+                }
+                if ui.button("Close room").clicked() {
+                    *room_host = None;
+                }
+            } else if let Some(following) = follower {
+                ui.label("Following the host's timer");
+                for name in following.participants() {
+                    ui.small(name);
+                }
+                if ui.button("Leave").clicked() {
+                    *follower = None;
+                }
+            } else {
+                if ui.button("Host on this network").clicked() {
+                    match LanHost::start(&room::display_name(), &device_id[..8]) {
+                        Ok(host) => {
+                            *lan_host = Some(host);
+                            *lan_browser = None;
+                            *share_error = None;
+                        }
+                        Err(e) => *share_error = Some(e.to_string()),
+                    }
+                }
+                let mut join = None;
+                if let Some(browser) = lan_browser {
+                    for (name, addr) in &browser.hosts {
+                        if ui.button(format!("Join {name}")).clicked() {
+                            join = Some(*addr);
+                        }
+                    }
+                    if browser.hosts.is_empty() {
+                        ui.weak("Looking for timers…");
+                    }
+                } else if ui.button("Find timers").clicked() {
+                    match LanBrowser::start() {
+                        Ok(browser) => *lan_browser = Some(browser),
+                        Err(e) => *share_error = Some(e.to_string()),
+                    }
+                }
+                if let Some(addr) = join {
+                    match Follower::connect(addr, None) {
+                        Ok(client) => {
+                            *follower = Some(client);
+                            *lan_browser = None;
+                            *share_error = None;
+                        }
+                        Err(e) => *share_error = Some(e.to_string()),
+                    }
+                }
+
+                ui.separator();
+                ui.add(
+                    egui::TextEdit::singleline(&mut settings.relay_addr)
+                        .hint_text("Relay, e.g. relay.example.com:7878"),
+                );
+                let has_relay = !settings.relay_addr.is_empty();
+                if ui
+                    .add_enabled(has_relay, Button::new("Host online room"))
+                    .clicked()
+                {
+                    match RoomHost::start(&settings.relay_addr, &room::display_name()) {
+                        Ok(host) => {
+                            *room_host = Some(host);
+                            *share_error = None;
+                        }
+                        Err(e) => *share_error = Some(e.to_string()),
+                    }
+                }
+                ui.horizontal(|ui| {
+                    ui.add(
+                        egui::TextEdit::singleline(room_code)
+                            .hint_text("Code")
+                            .desired_width(60.0),
+                    );
+                    if ui
+                        .add_enabled(has_relay && !room_code.is_empty(), Button::new("Join"))
+                        .clicked()
+                    {
+                        let hello = Hello::Join {
+                            code: room_code.trim().to_uppercase(),
+                            name: room::display_name(),
+                        };
+                        match Follower::connect(settings.relay_addr.as_str(), Some(&hello)) {
+                            Ok(client) => {
+                                *follower = Some(client);
+                                *share_error = None;
+                            }
+                            Err(e) => *share_error = Some(e.to_string()),
+                        }
+                    }
+                });
+            }
+            if let Some(err) = share_error {
+                ui.colored_label(Color32::RED, err.as_str());
+            }
+        });
+        ui.collapsing("Encrypted sync", |ui| {
+            ui.weak(SETTINGS_MERGE_NOTE);
+            ui.add(egui::TextEdit::singleline(&mut remote.url).hint_text("WebDAV folder URL"));
+            ui.add(egui::TextEdit::singleline(&mut remote.username).hint_text("Username"));
+            ui.add(
+                egui::TextEdit::singleline(&mut remote.password)
+                    .password(true)
+                    .hint_text("Password"),
+            );
+            ui.add(
+                egui::TextEdit::singleline(&mut remote.passphrase)
+                    .password(true)
+                    .hint_text("Encryption passphrase"),
+            );
+            ui.horizontal(|ui| {
+                if ui
+                    .add_enabled(
+                        remote.is_configured() && remote_result.is_none(),
+                        Button::new("Sync now"),
+                    )
+                    .clicked()
+                {
+                    *last_remote_sync = None;
+                }
+                if remote_result.is_some() {
+                    ui.spinner();
+                } else if let Some(status) = remote_status {
+                    ui.small(status.as_str());
+                }
+            });
+        });
+        ui.collapsing("Notion", |ui| {
+            ui.weak(
+                "Adds each finished phase to a database with the properties Name, Mode, \
+                 Minutes, Date, Tag and Notes",
+            );
+            ui.add(
+                egui::TextEdit::singleline(&mut notion.token)
+                    .password(true)
+                    .hint_text("Integration token"),
+            );
+            ui.add(egui::TextEdit::singleline(&mut notion.database_id).hint_text("Database ID"));
+            if let Some(err) = notion_error {
+                ui.colored_label(Color32::RED, err.as_str());
+            }
+        });
+        ui.collapsing("Phone notifications", |ui| {
+            ui.weak(
+                "Pushes each phase change to an ntfy topic, or any URL that takes a \
+                 plain-text POST",
+            );
+            ui.add(
+                egui::TextEdit::singleline(&mut ntfy.url).hint_text("https://ntfy.sh/your-topic"),
+            );
+            ui.add(
+                egui::TextEdit::singleline(&mut ntfy.token)
+                    .password(true)
+                    .hint_text("Access token (optional)"),
+            );
+            ui.checkbox(&mut ntfy.work_only, "Only when work starts");
+            if let Some(err) = ntfy_error {
+                ui.colored_label(Color32::RED, err.as_str());
+            }
+        });
+        ui.collapsing("Google Calendar", |ui| {
+            ui.weak("Adds work sessions to your calendar as busy time");
+            if calendar.refresh_token.is_some() {
+                ui.horizontal(|ui| {
+                    ui.label("Signed in");
+                    if ui.button("Sign out").clicked() {
+                        calendar.refresh_token = None;
+                    }
+                });
+            } else {
+                ui.add(
+                    egui::TextEdit::singleline(&mut calendar.client_id)
+                        .hint_text("OAuth client ID"),
+                )
+                .on_hover_text("From a Google Cloud project, of the Desktop app kind");
+                ui.add(
+                    egui::TextEdit::singleline(&mut calendar.client_secret)
+                        .password(true)
+                        .hint_text("Client secret"),
+                );
+                ui.horizontal(|ui| {
+                    let ready = !calendar.client_id.trim().is_empty() && calendar_sign_in.is_none();
+                    if ui
+                        .add_enabled(ready, Button::new("Sign in with Google"))
+                        .clicked()
+                    {
+                        match gcal::sign_in(calendar) {
+                            Ok((url, rx)) => {
+                                ui.ctx().output_mut(|o| {
+                                    o.open_url = Some(egui::output::OpenUrl::new_tab(url));
+                                });
+                                *calendar_sign_in = Some(rx);
+                                *calendar_error = None;
+                            }
+                            Err(e) => *calendar_error = Some(e.to_string()),
+                        }
+                    }
+                    if calendar_sign_in.is_some() {
+                        ui.spinner();
+                        ui.small("Waiting for the browser");
+                    }
+                });
+            }
+            ui.add(egui::TextEdit::singleline(&mut calendar.calendar_id).hint_text("primary"));
+            ui.checkbox(&mut calendar.prebook, "Book work sessions as they start")
+                .on_hover_text("Sessions cut short are shortened in the calendar");
+            if let Some(err) = calendar_error {
+                ui.colored_label(Color32::RED, err.as_str());
+            }
+        });
+        if let Some(err) = file_error {
+            ui.colored_label(Color32::RED, err.as_str());
+        }
+    }
+}
@@ -0,0 +1,217 @@
+use chrono::{Datelike, Local};
+use egui::{
+    plot::{Bar, BarChart, Plot},
+    Color32,
+};
+
+use super::EpomoApp;
+use crate::{chart, history::SessionFilter, timer::PomodoroMode};
+
+/// A small non-interactive bar chart with a label under each bar.
+fn bar_chart(ui: &mut egui::Ui, id: &str, bars: &[(String, f64)], color: Color32) {
+    let labels: Vec<String> = bars.iter().map(|(label, _)| label.clone()).collect();
+    let bars = bars
+        .iter()
+        .enumerate()
+        .map(|(i, (label, value))| Bar::new(i as f64, *value).name(label))
+        .collect();
+    Plot::new(id)
+        .height(120.0)
+        .allow_drag(false)
+        .allow_zoom(false)
+        .allow_scroll(false)
+        .include_y(0.0)
+        .x_axis_formatter(move |x, _| {
+            labels
+                .get(x.round() as usize)
+                .filter(|_| x >= 0.0 && x.fract() == 0.0)
+                .cloned()
+                .unwrap_or_default()
+        })
+        .show(ui, |plot_ui| {
+            plot_ui.bar_chart(BarChart::new(bars).color(color));
+        });
+}
+
+impl EpomoApp {
+    pub(super) fn stats_view(&mut self, ui: &mut egui::Ui) {
+        let Self {
+            settings,
+            history,
+            stats_filter,
+            file_error,
+            ..
+        } = self;
+
+        let profiles = history.sessions_by_profile();
+        let tags = history.sessions_by_tag();
+        if !profiles.is_empty() || !tags.is_empty() {
+            ui.horizontal_wrapped(|ui| {
+                ui.selectable_value(stats_filter, SessionFilter::All, "All");
+                for (profile, _) in &profiles {
+                    let filter = SessionFilter::Profile(profile.clone());
+                    ui.selectable_value(stats_filter, filter, profile);
+                }
+                for (tag, _) in &tags {
+                    let filter = SessionFilter::Tag(tag.clone());
+                    ui.selectable_value(stats_filter, filter, format!("#{tag}"));
+                }
+            });
+            ui.separator();
+        }
+        let today = Local::now().date_naive();
+        let month_start = today.with_day(1).unwrap_or(today);
+        let month_by_profile = history.focus_minutes_since_by(month_start, |s| s.profile.as_ref());
+        let month_by_tag = history.focus_minutes_since_by(month_start, |s| s.tag.as_ref());
+        let history = &history.filtered(stats_filter);
+
+        let today_count = history.count_on(today);
+        let total_minutes: i64 = history
+            .sessions
+            .iter()
+            .map(|s| s.duration().num_minutes())
+            .sum();
+        ui.label(format!("Sessions today: {today_count}"));
+        ui.label(format!("Sessions in total: {}", history.sessions.len()));
+        ui.label(format!(
+            "Focus time: {}h {}m",
+            total_minutes / 60,
+            total_minutes % 60
+        ));
+        if let Some(rate) = history.abandonment_rate().filter(|&r| r > 0.0) {
+            let partial_minutes: i64 = history
+                .abandoned
+                .iter()
+                .map(|a| a.duration().num_minutes())
+                .sum();
+            ui.label(format!(
+                "Stopped early: {} ({:.0}%), {}h {}m of focus",
+                history.abandoned.len(),
+                rate * 100.0,
+                partial_minutes / 60,
+                partial_minutes % 60
+            ));
+        }
+
+        ui.separator();
+        let color = settings.color(PomodoroMode::Work);
+        let title = "Focus minutes, last 7 days";
+        let week: Vec<(String, f64)> = history
+            .focus_minutes_by_day(7)
+            .into_iter()
+            .map(|(day, minutes)| (day.weekday().to_string(), minutes as f64))
+            .collect();
+        ui.horizontal(|ui| {
+            ui.label(title);
+            if ui
+                .small_button("💾")
+                .on_hover_text("Save chart as PNG")
+                .clicked()
+            {
+                if let Some(path) = rfd::FileDialog::new()
+                    .add_filter("PNG", &["png"])
+                    .set_file_name(&format!("epomo-week-{}.png", today.format("%Y-%m-%d")))
+                    .save_file()
+                {
+                    *file_error = chart::save_png(title, &week, color, &path)
+                        .err()
+                        .map(|e| e.to_string());
+                }
+            }
+        });
+        bar_chart(ui, "week", &week, color);
+
+        ui.collapsing("When you focus best", |ui| {
+            ui.label("Sessions by hour of day");
+            let hours: Vec<(String, f64)> = history
+                .sessions_by_hour()
+                .iter()
+                .enumerate()
+                .map(|(hour, n)| (hour.to_string(), *n as f64))
+                .collect();
+            bar_chart(ui, "by_hour", &hours, color);
+            if !history.abandoned.is_empty() {
+                ui.label("Stopped early by hour of day, %");
+                let rates: Vec<(String, f64)> = history
+                    .abandonment_by_hour()
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(hour, rate)| Some((hour.to_string(), (*rate)? * 100.0)))
+                    .collect();
+                bar_chart(ui, "abandoned_by_hour", &rates, color);
+            }
+            ui.label("Sessions by weekday");
+            let weekdays: Vec<(String, f64)> = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"]
+                .into_iter()
+                .zip(history.sessions_by_weekday())
+                .map(|(day, n)| (day.to_owned(), n as f64))
+                .collect();
+            bar_chart(ui, "by_weekday", &weekdays, color);
+        });
+        if history.sessions.iter().any(|s| s.energy.is_some()) {
+            ui.collapsing("Energy", |ui| {
+                ui.weak("Average rating after sessions, from 1 (drained) to 5 (energised)");
+                // Groups without ratings are left out rather than shown as 0.
+                let rated = |labels: [&str; 4], averages: [Option<f64>; 4]| {
+                    labels
+                        .into_iter()
+                        .zip(averages)
+                        .filter_map(|(label, average)| Some((label.to_owned(), average?)))
+                        .collect::<Vec<_>>()
+                };
+                ui.label("By time of day");
+                let times = rated(
+                    ["Night", "Morning", "Afternoon", "Evening"],
+                    history.energy_by_time_of_day(),
+                );
+                bar_chart(ui, "energy_by_time", &times, color);
+                ui.label("By session length");
+                let lengths = rated(
+                    ["<20 min", "20–34 min", "35–49 min", "50+ min"],
+                    history.energy_by_length(),
+                );
+                bar_chart(ui, "energy_by_length", &lengths, color);
+            });
+        }
+        if month_by_profile.len() + month_by_tag.len() > 1 {
+            ui.collapsing("This month", |ui| {
+                ui.weak("Focus time per profile and tag, across all sessions");
+                egui::Grid::new("month").striped(true).show(ui, |ui| {
+                    let tags = month_by_tag.into_iter().map(|(t, m)| (format!("#{t}"), m));
+                    for (name, minutes) in month_by_profile.into_iter().chain(tags) {
+                        ui.label(name);
+                        ui.label(format!("{}h {}m", minutes / 60, minutes % 60));
+                        ui.end_row();
+                    }
+                });
+            });
+        }
+        let tags = history.sessions_by_tag();
+        if !tags.is_empty() {
+            ui.collapsing("Tags", |ui| {
+                egui::Grid::new("tags").show(ui, |ui| {
+                    for (tag, count) in tags {
+                        ui.label(format!("#{tag}"));
+                        ui.label(format!("{count} sessions"));
+                        ui.end_row();
+                    }
+                });
+            });
+        }
+        let apps = history.sessions_by_app();
+        if !apps.is_empty() {
+            ui.collapsing("Apps", |ui| {
+                egui::Grid::new("apps").show(ui, |ui| {
+                    for (app, count) in apps {
+                        ui.label(format!("Mostly {app}"));
+                        ui.label(format!("{count} sessions"));
+                        ui.end_row();
+                    }
+                });
+            });
+        }
+        if let Some(err) = file_error {
+            ui.colored_label(Color32::RED, err.as_str());
+        }
+    }
+}
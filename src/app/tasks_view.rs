@@ -0,0 +1,53 @@
+use super::EpomoApp;
+use crate::{clock::format_clock, tasks};
+
+impl EpomoApp {
+    pub(super) fn tasks_view(&mut self, ui: &mut egui::Ui) {
+        let Self {
+            settings,
+            tasks,
+            timers,
+            new_task_title,
+            new_task_estimate,
+            ..
+        } = self;
+
+        ui.heading("Plan");
+        let mut remove_task = None;
+        for (i, task) in tasks.tasks.iter_mut().enumerate() {
+            ui.horizontal(|ui| {
+                ui.checkbox(&mut task.done, "");
+                ui.add(egui::TextEdit::singleline(&mut task.title).desired_width(90.0));
+                ui.label(format!("{}/", task.completed));
+                ui.add(egui::DragValue::new(&mut task.estimate).clamp_range(1..=20));
+                if ui.small_button("✖").clicked() {
+                    remove_task = Some(i);
+                }
+            });
+        }
+        if let Some(i) = remove_task {
+            tasks.tasks.remove(i);
+        }
+        ui.horizontal(|ui| {
+            let response = ui.add(
+                egui::TextEdit::singleline(new_task_title)
+                    .hint_text("New task")
+                    .desired_width(90.0),
+            );
+            ui.add(egui::DragValue::new(new_task_estimate).clamp_range(1..=20));
+            let submitted = response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+            if (ui.button("Add").clicked() || submitted) && !new_task_title.trim().is_empty() {
+                tasks.add(new_task_title.trim().to_owned(), *new_task_estimate);
+                new_task_title.clear();
+            }
+        });
+        let remaining = tasks.remaining_pomodoros();
+        if remaining > 0 {
+            let finish = tasks::projected_finish(&timers[0], settings, remaining);
+            ui.small(format!(
+                "{remaining} pomodoros left, done around {}",
+                format_clock(&finish.with_timezone(&chrono::Local), settings.clock_format)
+            ));
+        }
+    }
+}
@@ -0,0 +1,410 @@
+use chrono::{Datelike, NaiveTime};
+use egui::{Button, Color32};
+
+use super::{animated_color, format_duration, format_time, EpomoApp};
+use crate::{
+    app_usage::AppSampler,
+    clock::format_clock,
+    settings::Settings,
+    timer::{self, PomodoroMode, Timer},
+};
+
+/// One dot per work session in the cycle: filled once done, ringed for the current one.
+fn cycle_dots(ui: &mut egui::Ui, pomodoro: &Timer, per_long_break: usize, color: Color32) {
+    let per_long_break = per_long_break.max(1);
+    // The count wraps round as the long break starts, but the cycle isn't over until
+    // it ends.
+    let done = match pomodoro.mode {
+        PomodoroMode::LongBreak => per_long_break,
+        _ => pomodoro.session_count % per_long_break,
+    };
+    let radius = 5.0;
+    let spacing = 6.0;
+    let (rect, _) = ui.allocate_exact_size(
+        egui::vec2(
+            per_long_break as f32 * (2.0 * radius + spacing),
+            2.0 * radius + 2.0,
+        ),
+        egui::Sense::hover(),
+    );
+    let painter = ui.painter();
+    for i in 0..per_long_break {
+        let center = egui::pos2(
+            rect.left() + radius + i as f32 * (2.0 * radius + spacing),
+            rect.center().y,
+        );
+        if i < done {
+            painter.circle_filled(center, radius, color);
+        } else if i == done && pomodoro.mode == PomodoroMode::Work {
+            painter.circle(
+                center,
+                radius,
+                color.linear_multiply(0.3),
+                egui::Stroke::new(2.0, color),
+            );
+        } else {
+            painter.circle_stroke(center, radius, egui::Stroke::new(1.0, color));
+        }
+    }
+}
+
+/// What each rating after a work session stands for, from 1 up.
+const ENERGY_LABELS: [&str; 5] = ["Drained", "Tired", "Okay", "Good", "Energised"];
+
+/// How many of the most used tags get a chip.
+const TAG_CHIPS: usize = 6;
+
+/// Starts a work phase, running up to `until` if given.
+pub(super) fn start_pomodoro(
+    pomodoro: &mut Timer,
+    settings: &mut Settings,
+    until: Option<NaiveTime>,
+) {
+    match until {
+        Some(time) => pomodoro.start_until(timer::next_occurrence(time)),
+        None => pomodoro.start(settings),
+    }
+}
+
+impl EpomoApp {
+    /// The clock time to work until when starting, if "Work until" is ticked.
+    pub(super) fn until_time(&self) -> Option<NaiveTime> {
+        NaiveTime::from_hms_opt(self.until_hour, self.until_minute, 0).filter(|_| self.work_until)
+    }
+
+    pub(super) fn timer_view(&mut self, ui: &mut egui::Ui) {
+        let until = self.until_time();
+        let Self {
+            settings,
+            history,
+            timers,
+            tag,
+            new_tag,
+            app_sampler,
+            energy_due,
+            abandon_reason,
+            new_timer_label,
+            new_timer_minutes,
+            work_until,
+            until_hour,
+            until_minute,
+            follower,
+            ..
+        } = self;
+        let (pomodoro, countdowns) = timers
+            .split_first_mut()
+            .expect("the pomodoro timer always exists");
+
+        ui.heading(settings.profile().name.as_str());
+        if let Some(started_at) = *energy_due {
+            ui.horizontal(|ui| {
+                ui.label("How was that session?");
+                for (energy, hover) in (1..=5).zip(ENERGY_LABELS) {
+                    if ui.button(energy.to_string()).on_hover_text(hover).clicked() {
+                        history.rate(started_at, energy);
+                        *energy_due = None;
+                    }
+                }
+                if ui.small_button("✖").on_hover_text("Skip").clicked() {
+                    *energy_due = None;
+                }
+            });
+        }
+        if let Some((started_at, reason)) = abandon_reason {
+            let mut done = false;
+            ui.horizontal(|ui| {
+                ui.label("Stopped early. Why?");
+                let edit = ui.add(
+                    egui::TextEdit::singleline(reason)
+                        .hint_text("Meeting, interrupted…")
+                        .desired_width(140.0),
+                );
+                let entered = edit.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+                if entered || ui.button("Save").clicked() {
+                    let reason = reason.trim();
+                    if !reason.is_empty() {
+                        history.set_abandon_reason(*started_at, reason.to_owned());
+                    }
+                    done = true;
+                }
+                if ui.small_button("✖").on_hover_text("Skip").clicked() {
+                    done = true;
+                }
+            });
+            if done {
+                *abandon_reason = None;
+            }
+        }
+        // The most used tags, plus the current one if it's new, as one-click starts.
+        let mut chips: Vec<(String, usize)> = history.sessions_by_tag();
+        chips.truncate(TAG_CHIPS);
+        if let Some(current) = tag.as_ref().filter(|t| !chips.iter().any(|(c, _)| c == *t)) {
+            chips.push((current.clone(), 0));
+        }
+        ui.add_enabled_ui(follower.is_none(), |ui| {
+            ui.horizontal_wrapped(|ui| {
+                for (name, count) in chips {
+                    let selected = tag.as_deref() == Some(name.as_str());
+                    let hover = if pomodoro.is_running() || pomodoro.is_paused() {
+                        format!("{count} sessions. Click to tag this one")
+                    } else {
+                        format!("{count} sessions. Click to start one")
+                    };
+                    if ui
+                        .selectable_label(selected, format!("#{name}"))
+                        .on_hover_text(hover)
+                        .clicked()
+                    {
+                        if selected && (pomodoro.is_running() || pomodoro.is_paused()) {
+                            *tag = None;
+                        } else {
+                            *tag = Some(name);
+                            if !pomodoro.is_running() && !pomodoro.is_paused() {
+                                start_pomodoro(pomodoro, settings, until);
+                            }
+                        }
+                    }
+                }
+                let edit = ui.add(
+                    egui::TextEdit::singleline(new_tag)
+                        .hint_text("+ tag")
+                        .desired_width(60.0),
+                );
+                if edit.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                    let name = new_tag.trim().trim_start_matches('#').trim();
+                    *tag = (!name.is_empty()).then(|| name.to_owned());
+                    new_tag.clear();
+                }
+            });
+            let suggested = app_sampler
+                .as_ref()
+                .and_then(AppSampler::suggested_tag)
+                .filter(|_| tag.is_none());
+            if let Some(suggested) = suggested {
+                let hover = if settings.auto_tag {
+                    "Your tag rules will tag this session unless you pick another tag"
+                } else {
+                    "From your tag rules. Click to tag this session"
+                };
+                if ui
+                    .button(format!("Looks like #{suggested}"))
+                    .on_hover_text(hover)
+                    .clicked()
+                {
+                    *tag = Some(suggested);
+                }
+            }
+        });
+        ui.add_enabled_ui(follower.is_none(), |ui| {
+            ui.horizontal(|ui| {
+                let active = pomodoro.is_running() || pomodoro.is_paused();
+                if pomodoro.is_paused() {
+                    if ui.button("Resume").on_hover_text("Alt+S").clicked() {
+                        pomodoro.resume();
+                    }
+                } else if pomodoro.is_running() {
+                    if ui.button("Pause").on_hover_text("Alt+P").clicked() {
+                        pomodoro.pause();
+                    }
+                } else if ui.button("Start").on_hover_text("Alt+S").clicked() {
+                    start_pomodoro(pomodoro, settings, until);
+                }
+                if ui
+                    .add_enabled(active, Button::new("Skip"))
+                    .on_hover_text("Alt+N")
+                    .clicked()
+                {
+                    pomodoro.skip(settings);
+                }
+                if ui
+                    .add_enabled(active, Button::new("Stop"))
+                    .on_hover_text("Alt+X")
+                    .clicked()
+                {
+                    pomodoro.stop();
+                }
+            });
+        });
+        ui.add_enabled_ui(!pomodoro.is_running() && !pomodoro.is_paused(), |ui| {
+            ui.horizontal(|ui| {
+                ui.checkbox(work_until, "Work until");
+                ui.add(egui::DragValue::new(until_hour).clamp_range(0..=23));
+                ui.label(":");
+                ui.add(
+                    egui::DragValue::new(until_minute)
+                        .clamp_range(0..=59)
+                        .custom_formatter(|n, _| format!("{n:02}")),
+                );
+            });
+        });
+        // Countdown
+        if let Some(target) = pomodoro.target {
+            ui.label(format!(
+                "Working until {}",
+                format_clock(&target.with_timezone(&chrono::Local), settings.clock_format)
+            ));
+        }
+        if let Some(grace) = pomodoro.grace_left() {
+            // Rounded up, so it never reads 0 while still waiting.
+            let secs = (grace.num_milliseconds() + 999) / 1000;
+            ui.label(format!("{} starts in {secs} s", pomodoro.mode));
+        }
+        if let Some(time_left) = pomodoro.time_left() {
+            // Grow the countdown with the window so it stays readable on large displays.
+            let size = (ui.available_width() / 8.0).clamp(18.0, 96.0);
+            let color = animated_color(ui.ctx(), "countdown_color", settings.color(pomodoro.mode));
+            egui::Frame::none()
+                .fill(color.linear_multiply(0.15))
+                .rounding(4.0)
+                .inner_margin(4.0)
+                .show(ui, |ui| {
+                    ui.add(
+                        egui::Label::new(
+                            egui::RichText::new(format_duration(
+                                time_left,
+                                pomodoro.mode,
+                                settings.hide_seconds,
+                            ))
+                            .size(size)
+                            .color(color),
+                        )
+                        .sense(egui::Sense::click()),
+                    )
+                    .context_menu(|ui| {
+                        ui.add_enabled_ui(follower.is_none(), |ui| {
+                            if pomodoro.is_paused() {
+                                if ui.button("Resume").clicked() {
+                                    pomodoro.resume();
+                                    ui.close_menu();
+                                }
+                            } else if ui.button("Pause").clicked() {
+                                pomodoro.pause();
+                                ui.close_menu();
+                            }
+                            if ui.button("Skip").clicked() {
+                                pomodoro.skip(settings);
+                                ui.close_menu();
+                            }
+                            if ui.button("+5 min").clicked() {
+                                pomodoro.extend(chrono::Duration::minutes(5));
+                                ui.close_menu();
+                            }
+                        });
+                        if ui.button("Copy remaining time").clicked() {
+                            ui.output_mut(|o| o.copied_text = format_time(time_left));
+                            ui.close_menu();
+                        }
+                        ui.menu_button("Switch profile", |ui| {
+                            for (i, profile) in settings.profiles.iter().enumerate() {
+                                if ui
+                                    .selectable_label(i == settings.active_profile, &profile.name)
+                                    .clicked()
+                                {
+                                    settings.active_profile = i;
+                                    ui.close_menu();
+                                }
+                            }
+                        });
+                    });
+                });
+            if pomodoro.mode != PomodoroMode::Work {
+                if let Some(suggestion) = settings.break_suggestion(pomodoro.session_count) {
+                    ui.label(egui::RichText::new(suggestion).italics());
+                }
+            }
+            cycle_dots(
+                ui,
+                pomodoro,
+                settings.profile().sessions_per_long_break,
+                color,
+            );
+            if let Some((start, end)) = pomodoro.long_break_forecast(settings) {
+                let clock = |at: chrono::DateTime<chrono::Utc>| {
+                    format_clock(&at.with_timezone(&chrono::Local), settings.clock_format)
+                };
+                let text = if pomodoro.mode == PomodoroMode::LongBreak {
+                    format!("Cycle ends ~{}", clock(end))
+                } else {
+                    format!(
+                        "Long break at ~{}, cycle ends ~{}",
+                        clock(start),
+                        clock(end)
+                    )
+                };
+                ui.weak(text);
+            }
+            let today = chrono::Local::now().date_naive();
+            let monday =
+                today - chrono::Duration::days(today.weekday().num_days_from_monday() as i64);
+            ui.label(format!(
+                "Sessions: {} today, {} this week, {} in total",
+                history.count_on(today),
+                history.count_since(monday),
+                history.sessions.len()
+            ));
+        }
+        // Also shown once work is held back for the day, when nothing is running.
+        if pomodoro
+            .today
+            .reached(settings, chrono::Local::now().date_naive())
+        {
+            ui.colored_label(
+                Color32::from_rgb(230, 160, 40),
+                "You've reached today's limit. Time to wind down.",
+            );
+        }
+
+        ui.separator();
+        let mut new_timer = None;
+        let mut remove_timer = None;
+        for (i, countdown) in countdowns.iter_mut().enumerate() {
+            ui.horizontal(|ui| {
+                ui.label(countdown.label.as_str());
+                match countdown.time_left() {
+                    Some(time_left) => {
+                        ui.monospace(format_time(time_left));
+                        if ui.small_button("■").clicked() {
+                            countdown.stop();
+                        }
+                    }
+                    None => {
+                        ui.weak(format!("{}m", countdown.minutes));
+                        if ui.small_button("▶").clicked() {
+                            countdown.start(settings);
+                        }
+                    }
+                }
+                if ui.small_button("✖").clicked() {
+                    // Index 0 is the pomodoro.
+                    remove_timer = Some(i + 1);
+                }
+            });
+        }
+        ui.horizontal(|ui| {
+            ui.add(
+                egui::TextEdit::singleline(new_timer_label)
+                    .hint_text("Laundry")
+                    .desired_width(80.0),
+            );
+            ui.add(
+                egui::DragValue::new(new_timer_minutes)
+                    .clamp_range(1..=24 * 60)
+                    .suffix("m"),
+            );
+            if ui.button("Add timer").clicked() {
+                let label = match new_timer_label.trim() {
+                    "" => "Timer".to_owned(),
+                    label => label.to_owned(),
+                };
+                let mut timer = Timer::countdown(label, *new_timer_minutes);
+                timer.start(settings);
+                new_timer = Some(timer);
+                new_timer_label.clear();
+            }
+        });
+        if let Some(i) = remove_timer {
+            timers.remove(i);
+        }
+        timers.extend(new_timer);
+    }
+}
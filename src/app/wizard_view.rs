@@ -0,0 +1,100 @@
+use egui::Color32;
+
+use super::EpomoApp;
+use crate::autostart;
+
+/// Durations offered on the first step: name, work, short break and long break minutes.
+const PRESETS: [(&str, i64, i64, i64); 3] = [
+    ("Classic", 25, 5, 15),
+    ("Short bursts", 15, 3, 10),
+    ("Deep work", 50, 10, 30),
+];
+const STEPS: usize = 3;
+
+impl EpomoApp {
+    /// Walks through the basics on first launch: durations, notifications and starting
+    /// with the system.
+    pub(super) fn show_wizard(&mut self, ctx: &egui::Context) {
+        let Self {
+            settings,
+            wizard_step,
+            wizard_autostart,
+            autostart_enabled,
+            file_error,
+            ..
+        } = self;
+        let Some(step) = wizard_step else {
+            return;
+        };
+
+        let mut finished = false;
+        egui::Window::new("Welcome to epomo")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .show(ctx, |ui| {
+                ui.weak(format!("Step {} of {STEPS}", *step + 1));
+                match *step {
+                    0 => {
+                        ui.label("How long should work sessions and breaks be?");
+                        let profile = settings.profile_mut();
+                        for (name, work, short, long) in PRESETS {
+                            let selected = (
+                                profile.interval_period,
+                                profile.short_break_period,
+                                profile.long_break_period,
+                            ) == (work, short, long);
+                            let text =
+                                format!("{name}: {work} min work, {short}/{long} min breaks");
+                            if ui.radio(selected, text).clicked() {
+                                profile.interval_period = work;
+                                profile.short_break_period = short;
+                                profile.long_break_period = long;
+                            }
+                        }
+                        ui.weak("You can fine-tune these in the settings later.");
+                    }
+                    1 => {
+                        ui.label("How should epomo tell you a phase is over?");
+                        ui.checkbox(&mut settings.show_notifs, "Show notifications");
+                        ui.checkbox(&mut settings.dim_breaks, "Dim the screen on breaks");
+                    }
+                    _ => {
+                        ui.label("Start epomo when you log in?");
+                        ui.checkbox(wizard_autostart, "Start with the system");
+                    }
+                }
+                ui.separator();
+                ui.horizontal(|ui| {
+                    if ui.button("Skip").clicked() {
+                        finished = true;
+                    }
+                    ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                        if *step + 1 < STEPS {
+                            if ui.button("Next").clicked() {
+                                *step += 1;
+                            }
+                        } else if ui.button("Done").clicked() {
+                            if *wizard_autostart {
+                                if let Err(e) = autostart::set_enabled(true) {
+                                    *file_error =
+                                        Some(format!("Could not start with the system: {e}"));
+                                }
+                                *autostart_enabled = None;
+                            }
+                            finished = true;
+                        }
+                        if *step > 0 && ui.button("Back").clicked() {
+                            *step -= 1;
+                        }
+                    });
+                });
+                if let Some(err) = file_error {
+                    ui.colored_label(Color32::RED, err.as_str());
+                }
+            });
+        if finished {
+            *wizard_step = None;
+        }
+    }
+}
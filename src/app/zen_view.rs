@@ -0,0 +1,54 @@
+use egui::{Color32, RichText};
+
+use super::{animated_color, format_countdown, EpomoApp};
+
+impl EpomoApp {
+    /// Toggles zen mode on F (and leaves it on Esc), and while it's on fills the window
+    /// with nothing but the countdown on the mode color. Returns whether it took over.
+    pub(super) fn show_zen(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) -> bool {
+        let Self {
+            settings,
+            timers,
+            zen,
+            ..
+        } = self;
+        let pomodoro = &timers[0];
+
+        let toggle = !ctx.wants_keyboard_input()
+            && ctx.input(|i| {
+                i.key_pressed(egui::Key::F) || (*zen && i.key_pressed(egui::Key::Escape))
+            });
+        if toggle {
+            *zen = !*zen;
+            frame.set_fullscreen(*zen);
+        }
+        if !*zen {
+            return false;
+        }
+
+        let time_left = pomodoro
+            .time_left()
+            .unwrap_or_else(|| pomodoro.length(settings));
+        let color = animated_color(ctx, "zen_color", settings.color(pomodoro.mode));
+        egui::CentralPanel::default()
+            .frame(egui::Frame::none().fill(color))
+            .show(ctx, |ui| {
+                let size = (ui.available_width() / 5.0).min(ui.available_height() / 2.0);
+                ui.vertical_centered(|ui| {
+                    ui.add_space((ui.available_height() - size * 1.5).max(0.0) / 2.0);
+                    ui.label(
+                        RichText::new(format_countdown(time_left, settings.hide_seconds))
+                            .size(size)
+                            .color(Color32::BLACK),
+                    );
+                    ui.label(
+                        RichText::new(pomodoro.mode.to_string())
+                            .size(size / 4.0)
+                            .color(Color32::from_black_alpha(160)),
+                    );
+                });
+            });
+        self.schedule_repaint(ctx);
+        true
+    }
+}
@@ -0,0 +1,93 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc, Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use crate::settings::TagRule;
+
+/// How often the foreground app is looked at.
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Notes which app is in the foreground every few seconds on a background thread,
+/// until dropped, and which tag rules it matches. Only app names are kept, never window
+/// titles; those are only checked against the rules.
+pub struct AppSampler {
+    counts: Arc<Mutex<HashMap<String, u32>>>,
+    tag_counts: Arc<Mutex<HashMap<String, u32>>>,
+    paused: Arc<AtomicBool>,
+    // Dropping this wakes the thread up and ends it.
+    _stop: mpsc::Sender<()>,
+}
+
+impl AppSampler {
+    pub fn start(rules: Vec<TagRule>) -> Self {
+        let counts = Arc::new(Mutex::new(HashMap::new()));
+        let tag_counts = Arc::new(Mutex::new(HashMap::new()));
+        let paused = Arc::new(AtomicBool::new(false));
+        let (stop, stopped) = mpsc::channel::<()>();
+        {
+            let counts = counts.clone();
+            let tag_counts = tag_counts.clone();
+            let paused = paused.clone();
+            std::thread::spawn(move || {
+                while let Err(mpsc::RecvTimeoutError::Timeout) =
+                    stopped.recv_timeout(SAMPLE_INTERVAL)
+                {
+                    if paused.load(Ordering::Relaxed) {
+                        continue;
+                    }
+                    let Ok(window) = active_win_pos_rs::get_active_window() else {
+                        continue;
+                    };
+                    if window.app_name.is_empty() {
+                        continue;
+                    }
+                    if let Ok(mut tag_counts) = tag_counts.lock() {
+                        for rule in rules
+                            .iter()
+                            .filter(|r| r.matches(&window.app_name, &window.title))
+                        {
+                            *tag_counts.entry(rule.tag.trim().to_owned()).or_default() += 1;
+                        }
+                    }
+                    if let Ok(mut counts) = counts.lock() {
+                        *counts.entry(window.app_name).or_default() += 1;
+                    }
+                }
+            });
+        }
+        Self {
+            counts,
+            tag_counts,
+            paused,
+            _stop: stop,
+        }
+    }
+
+    /// Stops counting while the timer is paused.
+    pub fn set_paused(&self, paused: bool) {
+        self.paused.store(paused, Ordering::Relaxed);
+    }
+
+    /// The app seen most often so far, if any was seen at all.
+    pub fn dominant(&self) -> Option<String> {
+        most_seen(&self.counts)
+    }
+
+    /// The tag whose rules matched most often so far, if any matched at all.
+    pub fn suggested_tag(&self) -> Option<String> {
+        most_seen(&self.tag_counts)
+    }
+}
+
+fn most_seen(counts: &Mutex<HashMap<String, u32>>) -> Option<String> {
+    let counts = counts.lock().ok()?;
+    counts
+        .iter()
+        .max_by_key(|(_, n)| **n)
+        .map(|(name, _)| name.clone())
+}
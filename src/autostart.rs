@@ -0,0 +1,33 @@
+use std::io;
+
+use auto_launch::{AutoLaunch, AutoLaunchBuilder};
+
+fn other(e: impl std::fmt::Display) -> io::Error {
+    io::Error::new(io::ErrorKind::Other, e.to_string())
+}
+
+fn launcher() -> io::Result<AutoLaunch> {
+    let exe = std::env::current_exe()?;
+    AutoLaunchBuilder::new()
+        .set_app_name("epomo")
+        .set_app_path(&exe.to_string_lossy())
+        .set_use_launch_agent(true)
+        .build()
+        .map_err(other)
+}
+
+/// Whether epomo starts when the user logs in.
+pub fn is_enabled() -> io::Result<bool> {
+    launcher()?.is_enabled().map_err(other)
+}
+
+/// Makes epomo start when the user logs in, or stops it from doing so.
+pub fn set_enabled(enabled: bool) -> io::Result<()> {
+    let launcher = launcher()?;
+    if enabled {
+        launcher.enable()
+    } else {
+        launcher.disable()
+    }
+    .map_err(other)
+}
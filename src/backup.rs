@@ -0,0 +1,104 @@
+use std::{io, path::Path};
+
+use chrono::{DateTime, Utc};
+
+use crate::{history::History, settings::Settings, tasks::TaskList};
+
+/// Bumped whenever the backup layout changes; `migrate` upgrades older files.
+pub const BACKUP_VERSION: u32 = 3;
+
+/// Everything epomo knows, in one file.
+#[derive(serde::Deserialize, serde::Serialize)]
+pub struct Backup {
+    pub version: u32,
+    pub created_at: DateTime<Utc>,
+    pub settings: Settings,
+    pub history: History,
+    #[serde(default)]
+    pub tasks: TaskList,
+}
+
+impl Backup {
+    pub fn new(settings: &Settings, history: &History, tasks: &TaskList) -> Self {
+        Self {
+            version: BACKUP_VERSION,
+            created_at: Utc::now(),
+            settings: settings.clone(),
+            history: history.clone(),
+            tasks: tasks.clone(),
+        }
+    }
+
+    pub fn write(&self, path: &Path) -> io::Result<()> {
+        let file = std::fs::File::create(path)?;
+        serde_json::to_writer_pretty(io::BufWriter::new(file), self).map_err(io::Error::from)
+    }
+
+    pub fn read(path: &Path) -> io::Result<Self> {
+        let file = std::fs::File::open(path)?;
+        let value: serde_json::Value = serde_json::from_reader(io::BufReader::new(file))?;
+        let value = migrate(value)?;
+        serde_json::from_value(value).map_err(io::Error::from)
+    }
+}
+
+/// Upgrades a backup written by an older epomo to the current layout, one version at
+/// a time.
+fn migrate(mut value: serde_json::Value) -> io::Result<serde_json::Value> {
+    loop {
+        let version = value.get("version").and_then(|v| v.as_u64()).unwrap_or(0);
+        match version {
+            // Version 1 had a single set of durations instead of profiles.
+            1 => {
+                let settings = &mut value["settings"];
+                let mut profile = serde_json::json!({ "name": "Default" });
+                for key in ["interval_period", "long_break_period", "short_break_period"] {
+                    if let Some(minutes) = settings.get(key) {
+                        profile[key] = minutes.clone();
+                    }
+                }
+                settings["profiles"] = serde_json::json!([profile]);
+                value["version"] = 2.into();
+            }
+            // Version 2 predates the task list, which simply starts out empty.
+            2 => value["version"] = 3.into(),
+            v if v == u64::from(BACKUP_VERSION) => return Ok(value),
+            v => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("unsupported backup version {v}"),
+                ))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn version_1_backups_get_a_profile_and_an_empty_task_list() {
+        let old = serde_json::json!({
+            "version": 1,
+            "created_at": "2023-03-01T09:00:00Z",
+            "settings": { "interval_period": 50, "short_break_period": 10 },
+            "history": { "sessions": [] },
+        });
+        let value = migrate(old).unwrap();
+        assert_eq!(value["version"], BACKUP_VERSION);
+        let backup: Backup = serde_json::from_value(value).unwrap();
+        let profile = backup.settings.profile();
+        assert_eq!(profile.name, "Default");
+        assert_eq!(profile.interval_period, 50);
+        assert_eq!(profile.short_break_period, 10);
+        assert_eq!(profile.long_break_period, 15);
+        assert!(backup.tasks.tasks.is_empty());
+    }
+
+    #[test]
+    fn backups_from_a_newer_epomo_are_refused() {
+        let newer = serde_json::json!({ "version": BACKUP_VERSION + 1 });
+        assert!(migrate(newer).is_err());
+    }
+}
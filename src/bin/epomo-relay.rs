@@ -0,0 +1,167 @@
+//! A tiny relay that lets epomo instances share a timer across the internet.
+//!
+//! A host opens a room under a code and every line it sends is passed on to whoever
+//! joined with that code. The relay knows nothing about timers beyond that, apart from
+//! telling everyone in a room who else is there.
+//!
+//! Usage: `epomo-relay [ADDRESS]`, listening on `0.0.0.0:7878` by default.
+
+use std::{
+    collections::HashMap,
+    io::{self, BufRead, BufReader, Read, Write},
+    net::{Shutdown, TcpListener, TcpStream},
+    sync::{mpsc, Arc, Mutex},
+    time::Duration,
+};
+
+/// Clients send at least an empty line every 30 seconds, so one silent for longer has
+/// gone away without saying so.
+const READ_TIMEOUT: Duration = Duration::from_secs(90);
+/// How long a write may stall before the participant is given up on.
+const WRITE_TIMEOUT: Duration = Duration::from_secs(10);
+/// The longest line accepted. Timer lines are well under a kilobyte.
+const MAX_LINE: u64 = 64 * 1024;
+/// Lines waiting to go out to one participant. Anyone further behind is dropped, so one
+/// slow connection can't hold up the rest of the room.
+const QUEUE: usize = 16;
+
+/// Writes the lines sent to the returned channel to `stream` on a thread of its own, and
+/// shuts the connection down once the channel is dropped or a write fails.
+fn spawn_writer(mut stream: TcpStream) -> mpsc::SyncSender<String> {
+    let (tx, rx) = mpsc::sync_channel::<String>(QUEUE);
+    std::thread::spawn(move || {
+        for line in rx {
+            if stream.write_all(line.as_bytes()).is_err() {
+                break;
+            }
+        }
+        let _ = stream.shutdown(Shutdown::Both);
+    });
+    tx
+}
+
+/// The next line from `reader` without its newline, or `None` at the end. Lines longer
+/// than [`MAX_LINE`] are an error.
+fn read_line(reader: &mut impl BufRead) -> io::Result<Option<String>> {
+    let mut line = String::new();
+    reader.by_ref().take(MAX_LINE).read_line(&mut line)?;
+    if line.is_empty() {
+        return Ok(None);
+    }
+    if !line.ends_with('\n') {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "line too long"));
+    }
+    line.pop();
+    Ok(Some(line))
+}
+
+struct Room {
+    host_name: String,
+    host: mpsc::SyncSender<String>,
+    /// The last timer line, for people joining mid-session.
+    last: Option<String>,
+    joiners: Vec<(usize, String, mpsc::SyncSender<String>)>,
+}
+
+impl Room {
+    /// Queues `line` for every joiner, dropping those who can't keep up.
+    fn send_joiners(&mut self, line: &str) {
+        self.joiners
+            .retain(|(_, _, joiner)| joiner.try_send(line.to_owned()).is_ok());
+    }
+
+    fn send_participants(&mut self) {
+        let mut names = vec![self.host_name.clone()];
+        names.extend(self.joiners.iter().map(|(_, name, _)| name.clone()));
+        let line = format!("{}\n", serde_json::json!({ "Participants": names }));
+        let _ = self.host.try_send(line.clone());
+        self.send_joiners(&line);
+    }
+}
+
+type Rooms = Arc<Mutex<HashMap<String, Room>>>;
+
+fn main() -> io::Result<()> {
+    let addr = std::env::args()
+        .nth(1)
+        .unwrap_or_else(|| "0.0.0.0:7878".to_owned());
+    let listener = TcpListener::bind(&addr)?;
+    println!("epomo relay listening on {addr}");
+
+    let rooms = Rooms::default();
+    for (id, stream) in listener.incoming().flatten().enumerate() {
+        let rooms = rooms.clone();
+        std::thread::spawn(move || {
+            let _ = handle(id, stream, &rooms);
+        });
+    }
+    Ok(())
+}
+
+fn handle(id: usize, stream: TcpStream, rooms: &Rooms) -> io::Result<()> {
+    stream.set_read_timeout(Some(READ_TIMEOUT))?;
+    stream.set_write_timeout(Some(WRITE_TIMEOUT))?;
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let hello: serde_json::Value = match read_line(&mut reader)? {
+        Some(line) => serde_json::from_str(&line)?,
+        None => return Ok(()),
+    };
+
+    if let Some(host) = hello.get("Host") {
+        let code = host["code"].as_str().unwrap_or_default().to_owned();
+        let name = host["name"].as_str().unwrap_or("Host").to_owned();
+        {
+            let mut rooms = rooms.lock().unwrap();
+            if code.is_empty() || rooms.contains_key(&code) {
+                return stream.shutdown(Shutdown::Both);
+            }
+            let mut room = Room {
+                host_name: name,
+                host: spawn_writer(stream.try_clone()?),
+                last: None,
+                joiners: Vec::new(),
+            };
+            room.send_participants();
+            rooms.insert(code.clone(), room);
+        }
+        while let Ok(Some(line)) = read_line(&mut reader) {
+            // Empty lines only keep the connection alive.
+            if line.is_empty() {
+                continue;
+            }
+            let line = format!("{line}\n");
+            let mut rooms = rooms.lock().unwrap();
+            if let Some(room) = rooms.get_mut(&code) {
+                room.send_joiners(&line);
+                room.last = Some(line);
+            }
+        }
+        // The host left, so the room goes with it, and dropping the joiners' channels
+        // disconnects them.
+        rooms.lock().unwrap().remove(&code);
+        let _ = stream.shutdown(Shutdown::Both);
+    } else if let Some(join) = hello.get("Join") {
+        let code = join["code"].as_str().unwrap_or_default().to_uppercase();
+        let name = join["name"].as_str().unwrap_or("Guest").to_owned();
+        {
+            let mut rooms = rooms.lock().unwrap();
+            let Some(room) = rooms.get_mut(&code) else {
+                return stream.shutdown(Shutdown::Both);
+            };
+            let writer = spawn_writer(stream.try_clone()?);
+            if let Some(last) = &room.last {
+                let _ = writer.try_send(last.clone());
+            }
+            room.joiners.push((id, name, writer));
+            room.send_participants();
+        }
+        // Joiners only send keepalives; this just waits for them to leave.
+        while let Ok(Some(_)) = read_line(&mut reader) {}
+        if let Some(room) = rooms.lock().unwrap().get_mut(&code) {
+            room.joiners.retain(|(joiner, _, _)| *joiner != id);
+            room.send_participants();
+        }
+        let _ = stream.shutdown(Shutdown::Both);
+    }
+    Ok(())
+}
@@ -0,0 +1,152 @@
+use std::{
+    io::{self, Write},
+    path::PathBuf,
+    process::Command,
+};
+
+const BEGIN_MARKER: &str = "# epomo block start";
+const END_MARKER: &str = "# epomo block end";
+
+fn hosts_path() -> PathBuf {
+    if cfg!(windows) {
+        PathBuf::from(r"C:\Windows\System32\drivers\etc\hosts")
+    } else {
+        PathBuf::from("/etc/hosts")
+    }
+}
+
+/// Splits the user's comma or whitespace separated site list into bare domains.
+pub fn parse_domains(list: &str) -> Vec<String> {
+    list.split(|c: char| c == ',' || c.is_whitespace())
+        .map(|s| {
+            s.trim()
+                .trim_start_matches("https://")
+                .trim_start_matches("http://")
+                .trim_end_matches('/')
+                .to_lowercase()
+        })
+        .filter(|s| !s.is_empty())
+        .collect()
+}
+
+/// Removes our block (if any) from the hosts file contents.
+fn strip_block(hosts: &str) -> String {
+    let mut out = String::new();
+    let mut inside = false;
+    for line in hosts.lines() {
+        match line.trim() {
+            BEGIN_MARKER => inside = true,
+            END_MARKER => inside = false,
+            _ if !inside => {
+                out.push_str(line);
+                out.push('\n');
+            }
+            _ => {}
+        }
+    }
+    out
+}
+
+fn with_block(hosts: &str, domains: &[String]) -> String {
+    let mut out = strip_block(hosts);
+    if domains.is_empty() {
+        return out;
+    }
+    out.push_str(BEGIN_MARKER);
+    out.push('\n');
+    for domain in domains {
+        out.push_str(&format!("0.0.0.0 {domain}\n"));
+        if !domain.starts_with("www.") {
+            out.push_str(&format!("0.0.0.0 www.{domain}\n"));
+        }
+    }
+    out.push_str(END_MARKER);
+    out.push('\n');
+    out
+}
+
+/// Redirects `domains` to 0.0.0.0 in the hosts file, or lifts the block when empty.
+///
+/// The hosts file is usually only writable by an administrator, so when a plain write
+/// is refused we retry through the platform's elevation prompt. This blocks until the
+/// prompt is answered, so call it off the UI thread.
+pub fn set_blocked(domains: &[String]) -> io::Result<()> {
+    let path = hosts_path();
+    let contents = with_block(&std::fs::read_to_string(&path)?, domains);
+    match std::fs::write(&path, &contents) {
+        Err(e) if e.kind() == io::ErrorKind::PermissionDenied => write_elevated(&contents),
+        res => res,
+    }
+}
+
+/// Copies `contents` over the hosts file as an administrator. The copy is made from a
+/// randomly named file, only the user can read or write, in a directory only they can
+/// enter, so nobody else can swap in their own hosts file before it's copied.
+fn write_elevated(contents: &str) -> io::Result<()> {
+    let dir = tempfile::Builder::new().prefix("epomo-").tempdir()?;
+    #[cfg(unix)]
+    std::fs::set_permissions(
+        dir.path(),
+        std::os::unix::fs::PermissionsExt::from_mode(0o700),
+    )?;
+    let mut file = tempfile::Builder::new()
+        .prefix("hosts-")
+        .tempfile_in(dir.path())?;
+    file.write_all(contents.as_bytes())?;
+    file.as_file().sync_all()?;
+    let tmp = file.path();
+    let hosts = hosts_path();
+    let status = if cfg!(windows) {
+        Command::new("powershell")
+            .args(["-NoProfile", "-Command"])
+            .arg(format!(
+                "Start-Process -Verb RunAs -Wait -WindowStyle Hidden cmd -ArgumentList '/c copy /y \"{}\" \"{}\"'",
+                tmp.display(),
+                hosts.display()
+            ))
+            .status()?
+    } else if cfg!(target_os = "macos") {
+        Command::new("osascript")
+            .arg("-e")
+            .arg(format!(
+                "do shell script \"cp '{}' '{}'\" with administrator privileges",
+                tmp.display(),
+                hosts.display()
+            ))
+            .status()?
+    } else {
+        Command::new("pkexec")
+            .arg("cp")
+            .arg(tmp)
+            .arg(&hosts)
+            .status()?
+    };
+    if status.success() {
+        Ok(())
+    } else {
+        Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            "could not get permission to edit the hosts file",
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HOSTS: &str = "127.0.0.1 localhost\n::1 localhost\n";
+
+    #[test]
+    fn blocking_twice_leaves_one_block_and_lifting_it_restores_the_file() {
+        let domains = parse_domains("https://example.com/, www.news.org");
+        let blocked = with_block(HOSTS, &domains);
+        assert_eq!(with_block(&blocked, &domains), blocked);
+        assert_eq!(blocked.matches(BEGIN_MARKER).count(), 1);
+        assert!(blocked.contains("0.0.0.0 example.com\n0.0.0.0 www.example.com\n"));
+        assert!(!blocked.contains("www.www."));
+
+        assert_eq!(with_block(&blocked, &[]), HOSTS);
+        assert_eq!(strip_block(&strip_block(&blocked)), HOSTS);
+    }
+}
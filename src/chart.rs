@@ -0,0 +1,169 @@
+use std::{io, path::Path};
+
+use ab_glyph::{Font, FontRef, PxScale, ScaleFont};
+use egui::Color32;
+use image::{Rgba, RgbaImage};
+
+const WIDTH: u32 = 720;
+const HEIGHT: u32 = 360;
+const MARGIN: f32 = 48.0;
+const BACKGROUND: Rgba<u8> = Rgba([255, 255, 255, 255]);
+const TEXT: Rgba<u8> = Rgba([40, 40, 40, 255]);
+
+fn fill_rect(img: &mut RgbaImage, x0: f32, y0: f32, x1: f32, y1: f32, color: Rgba<u8>) {
+    let (x0, x1) = (x0.max(0.0) as u32, (x1 as u32).min(img.width()));
+    let (y0, y1) = (y0.max(0.0) as u32, (y1 as u32).min(img.height()));
+    for y in y0..y1 {
+        for x in x0..x1 {
+            img.put_pixel(x, y, color);
+        }
+    }
+}
+
+/// The font egui draws text with, for drawing into images.
+pub(crate) fn default_font(fonts: &egui::FontDefinitions) -> io::Result<FontRef<'_>> {
+    let data = &fonts
+        .font_data
+        .get("Ubuntu-Light")
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no font to draw text with"))?
+        .font;
+    FontRef::try_from_slice(data)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))
+}
+
+/// Draws `text` centered on `center_x` with its baseline at `baseline`.
+pub(crate) fn draw_text(
+    img: &mut RgbaImage,
+    font: &FontRef<'_>,
+    text: &str,
+    size: f32,
+    color: Rgba<u8>,
+    center_x: f32,
+    baseline: f32,
+) {
+    let scaled = font.as_scaled(PxScale::from(size));
+    let width: f32 = text
+        .chars()
+        .map(|c| scaled.h_advance(scaled.glyph_id(c)))
+        .sum();
+    let mut x = center_x - width / 2.0;
+    for c in text.chars() {
+        let id = scaled.glyph_id(c);
+        let glyph = id.with_scale_and_position(size, ab_glyph::point(x, baseline));
+        x += scaled.h_advance(id);
+        let Some(outline) = font.outline_glyph(glyph) else {
+            continue;
+        };
+        let bounds = outline.px_bounds();
+        outline.draw(|gx, gy, coverage| {
+            let px = bounds.min.x as i32 + gx as i32;
+            let py = bounds.min.y as i32 + gy as i32;
+            if px < 0 || py < 0 || px >= img.width() as i32 || py >= img.height() as i32 {
+                return;
+            }
+            let pixel = img.get_pixel_mut(px as u32, py as u32);
+            for i in 0..3 {
+                let (bg, fg) = (pixel[i] as f32, color[i] as f32);
+                pixel[i] = (bg + (fg - bg) * coverage) as u8;
+            }
+        });
+    }
+}
+
+/// Draws a `size` pixel square icon: a disc in the phase's color with the minutes left
+/// on it, or an empty disc when the timer isn't running. Past 99 minutes it shows whole
+/// hours instead.
+pub(crate) fn badge(minutes: Option<i64>, color: Color32, size: u32) -> RgbaImage {
+    let mut img = RgbaImage::new(size, size);
+    let center = size as f32 / 2.0;
+    let fill = Rgba([color.r(), color.g(), color.b(), 255]);
+    for (x, y, pixel) in img.enumerate_pixels_mut() {
+        let (dx, dy) = (x as f32 + 0.5 - center, y as f32 + 0.5 - center);
+        // A pixel of soft edge so the disc doesn't look jagged.
+        let coverage = (center - (dx * dx + dy * dy).sqrt()).clamp(0.0, 1.0);
+        if coverage > 0.0 {
+            *pixel = Rgba([fill[0], fill[1], fill[2], (coverage * 255.0) as u8]);
+        }
+    }
+    if let Some(minutes) = minutes {
+        let text = if minutes < 100 {
+            minutes.to_string()
+        } else {
+            format!("{}h", minutes / 60)
+        };
+        let fonts = egui::FontDefinitions::default();
+        match default_font(&fonts) {
+            Ok(font) => {
+                let white = Rgba([255, 255, 255, 255]);
+                let scale = size as f32 / 32.0;
+                draw_text(
+                    &mut img,
+                    &font,
+                    &text,
+                    22.0 * scale,
+                    white,
+                    center,
+                    24.0 * scale,
+                );
+            }
+            Err(e) => tracing::warn!("could not draw the icon's text: {e}"),
+        }
+    }
+    img
+}
+
+/// Renders a bar chart with a title and a label and value per bar, and saves it as a
+/// PNG. This draws the image itself rather than capturing the screen, so it works
+/// whatever size the window is.
+pub fn save_png(
+    title: &str,
+    bars: &[(String, f64)],
+    color: Color32,
+    path: &Path,
+) -> io::Result<()> {
+    let fonts = egui::FontDefinitions::default();
+    let font = default_font(&fonts)?;
+
+    let mut img = RgbaImage::from_pixel(WIDTH, HEIGHT, BACKGROUND);
+    draw_text(&mut img, &font, title, 20.0, TEXT, WIDTH as f32 / 2.0, 30.0);
+
+    let bottom = HEIGHT as f32 - MARGIN;
+    let top = MARGIN + 20.0;
+    fill_rect(
+        &mut img,
+        MARGIN,
+        bottom,
+        WIDTH as f32 - MARGIN,
+        bottom + 1.0,
+        TEXT,
+    );
+
+    let max = bars.iter().map(|(_, v)| *v).fold(0.0, f64::max).max(1.0);
+    let slot = (WIDTH as f32 - 2.0 * MARGIN) / bars.len().max(1) as f32;
+    let bar_color = Rgba([color.r(), color.g(), color.b(), 255]);
+    for (i, (label, value)) in bars.iter().enumerate() {
+        let center = MARGIN + slot * (i as f32 + 0.5);
+        let height = (bottom - top) * (*value / max) as f32;
+        fill_rect(
+            &mut img,
+            center - slot * 0.35,
+            bottom - height,
+            center + slot * 0.35,
+            bottom,
+            bar_color,
+        );
+        draw_text(&mut img, &font, label, 14.0, TEXT, center, bottom + 20.0);
+        draw_text(
+            &mut img,
+            &font,
+            &format!("{value:.0}"),
+            13.0,
+            TEXT,
+            center,
+            bottom - height - 6.0,
+        );
+    }
+
+    img.save(path)
+        .map_err(|e| io::Error::new(io::ErrorKind::Other, e.to_string()))
+}
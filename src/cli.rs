@@ -0,0 +1,83 @@
+use std::path::PathBuf;
+
+pub const USAGE: &str = "\
+Usage: epomo [OPTIONS]
+
+Options:
+  --data-dir <PATH>  Keep all state in PATH instead of the usual place. An
+                     epomo.portable file next to the executable does the same
+                     with a data folder beside it
+  --log-file <PATH>  Also write logs to PATH, starting a new file every day
+                     (or set EPOMO_LOG_FILE)
+  --json-rpc         Run without a window, reading commands from stdin and
+                     writing events to stdout, one JSON object per line
+  -h, --help         Print this help
+";
+
+/// Command line options.
+pub struct Args {
+    pub data_dir: Option<PathBuf>,
+    pub log_file: Option<PathBuf>,
+    pub json_rpc: bool,
+    /// Runs phases this many times faster. Left out of the usage text as it's only
+    /// meant for trying things out during development.
+    pub time_scale: u32,
+    pub help: bool,
+}
+
+impl Default for Args {
+    fn default() -> Self {
+        Self {
+            data_dir: None,
+            log_file: None,
+            json_rpc: false,
+            time_scale: 1,
+            help: false,
+        }
+    }
+}
+
+impl Args {
+    /// Reads the options the app was started with, falling back to environment
+    /// variables for those not given.
+    pub fn from_env() -> Result<Self, String> {
+        let mut args = Self::parse(std::env::args().skip(1))?;
+        if args.log_file.is_none() {
+            args.log_file = std::env::var_os("EPOMO_LOG_FILE").map(PathBuf::from);
+        }
+        Ok(args)
+    }
+
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Self, String> {
+        let mut parsed = Self::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            // Accept both `--flag value` and `--flag=value`.
+            let (flag, inline) = match arg.split_once('=') {
+                Some((flag, value)) if flag.starts_with("--") => {
+                    (flag.to_owned(), Some(value.to_owned()))
+                }
+                _ => (arg, None),
+            };
+            let mut value = || {
+                inline
+                    .clone()
+                    .or_else(|| args.next())
+                    .ok_or_else(|| format!("{flag} needs a value"))
+            };
+            match flag.as_str() {
+                "--data-dir" => parsed.data_dir = Some(PathBuf::from(value()?)),
+                "--log-file" => parsed.log_file = Some(PathBuf::from(value()?)),
+                "--time-scale" => {
+                    parsed.time_scale = value()?
+                        .parse()
+                        .map_err(|_| "--time-scale needs a whole number".to_owned())?
+                }
+                "--json-rpc" => parsed.json_rpc = true,
+                "-h" | "--help" => parsed.help = true,
+                _ => return Err(format!("unknown option {flag}")),
+            }
+        }
+        Ok(parsed)
+    }
+}
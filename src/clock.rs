@@ -0,0 +1,80 @@
+use chrono::{DateTime, Locale, NaiveDate, TimeZone, Utc};
+
+/// How wall-clock times are shown.
+#[derive(Clone, Copy, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+pub enum ClockFormat {
+    /// Whatever the system locale uses.
+    #[default]
+    System,
+    TwentyFourHour,
+    TwelveHour,
+}
+
+impl ClockFormat {
+    pub const ALL: [ClockFormat; 3] = [
+        ClockFormat::System,
+        ClockFormat::TwentyFourHour,
+        ClockFormat::TwelveHour,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ClockFormat::System => "System",
+            ClockFormat::TwentyFourHour => "24-hour",
+            ClockFormat::TwelveHour => "12-hour",
+        }
+    }
+}
+
+/// A duration as `hh:mm:ss`.
+pub fn format_time(duration: chrono::Duration) -> String {
+    format!(
+        "{:02}:{:02}:{:02}",
+        duration.num_hours(),
+        duration.num_minutes() % 60,
+        duration.num_seconds() % 60,
+    )
+}
+
+/// The OS's locale, e.g. `en_US`, or POSIX when it's unknown.
+fn locale() -> Locale {
+    sys_locale::get_locale()
+        .and_then(|name| {
+            let name = name.replace('-', "_");
+            // Drop any encoding or modifier, as in `de_DE.UTF-8`.
+            let name = name.split(['.', '@']).next().unwrap_or_default();
+            Locale::try_from(name).ok()
+        })
+        .unwrap_or(Locale::POSIX)
+}
+
+/// Whether the locale's own time format has no hour 13.
+fn uses_12_hour(locale: Locale) -> bool {
+    let Some(afternoon) = NaiveDate::from_ymd_opt(2000, 1, 1).and_then(|d| d.and_hms_opt(13, 0, 0))
+    else {
+        return false;
+    };
+    !Utc.from_utc_datetime(&afternoon)
+        .format_localized("%X", locale)
+        .to_string()
+        .contains("13")
+}
+
+/// Formats the hours and minutes of `time` in its own timezone, in the user's locale.
+pub fn format_clock<Tz: TimeZone>(time: &DateTime<Tz>, format: ClockFormat) -> String
+where
+    Tz::Offset: std::fmt::Display,
+{
+    let locale = locale();
+    let twelve_hour = match format {
+        ClockFormat::System => uses_12_hour(locale),
+        ClockFormat::TwentyFourHour => false,
+        ClockFormat::TwelveHour => true,
+    };
+    let pattern = if twelve_hour { "%-I:%M %p" } else { "%H:%M" };
+    // Some locales have no AM/PM names, which leaves a trailing space.
+    time.format_localized(pattern, locale)
+        .to_string()
+        .trim()
+        .to_owned()
+}
@@ -0,0 +1,128 @@
+use std::sync::mpsc;
+
+use crate::{
+    events::{EventBus, TimerEvent},
+    settings::Settings,
+    timer::Timer,
+};
+
+/// epomo's pomodoro logic on its own: a timer cycling through work and breaks, the
+/// settings it runs on, and whoever wants to hear about it.
+///
+/// Every method that changes the timer hands the resulting [`TimerEvent`]s to the
+/// subscribers straight away. `settings` can also be changed directly, and so can
+/// `timer` through its own methods, like [`Timer::set_ends_at`]; any events that queues
+/// go out on the next [`Engine::tick`]. Its fields are there to read.
+///
+/// The engine saves and loads with serde, running phase included. Call
+/// [`Engine::restore`] after loading one.
+#[derive(Default, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct Engine {
+    pub timer: Timer,
+    #[serde(deserialize_with = "repaired")]
+    pub settings: Settings,
+    #[serde(skip)]
+    events: EventBus,
+}
+
+impl Engine {
+    pub fn new(settings: Settings) -> Self {
+        Self {
+            settings,
+            ..Default::default()
+        }
+    }
+
+    /// A channel that gets every event from now on.
+    pub fn subscribe(&mut self) -> mpsc::Receiver<TimerEvent> {
+        self.events.subscribe()
+    }
+
+    /// Calls `callback` with every event from now on, on whichever thread drives the
+    /// engine.
+    pub fn on_event(&mut self, callback: impl FnMut(&TimerEvent) + Send + 'static) {
+        self.events.on_event(callback);
+    }
+
+    /// Moves on to the next phase if the current one is over. Call it regularly, e.g.
+    /// once a second; phases that ran out in between are caught up on. Returns whether
+    /// anything happened.
+    pub fn tick(&mut self) -> bool {
+        let changed = self.timer.tick(&mut self.settings);
+        let published = self.publish();
+        changed || published
+    }
+
+    /// Starts a work phase.
+    pub fn start(&mut self) {
+        self.timer.start(&mut self.settings);
+        self.publish();
+    }
+
+    pub fn pause(&mut self) {
+        self.timer.pause();
+        self.publish();
+    }
+
+    pub fn resume(&mut self) {
+        self.timer.resume();
+        self.publish();
+    }
+
+    /// Cuts the current phase short and starts the next one.
+    pub fn skip(&mut self) {
+        self.timer.skip(&mut self.settings);
+        self.publish();
+    }
+
+    pub fn stop(&mut self) {
+        self.timer.stop();
+        self.publish();
+    }
+
+    /// Picks up a loaded engine's phase where it left off. See [`Timer::restore`].
+    pub fn restore(&mut self) {
+        self.settings.ensure_profile();
+        self.timer.restore(&mut self.settings);
+        self.publish();
+    }
+
+    /// Hands the timer's queued events to the subscribers. Returns whether there were
+    /// any.
+    fn publish(&mut self) -> bool {
+        let events = self.timer.take_events();
+        let any = !events.is_empty();
+        for event in events {
+            self.events.publish(event);
+        }
+        any
+    }
+}
+
+/// Reads the settings with [`Settings::ensure_profile`] applied, so a loaded engine
+/// always has a profile to run on.
+fn repaired<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<Settings, D::Error> {
+    let mut settings = <Settings as serde::Deserialize>::deserialize(deserializer)?;
+    settings.ensure_profile();
+    Ok(settings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn engines_without_profiles_get_one() {
+        let mut engine: Engine =
+            serde_json::from_str(r#"{ "settings": { "profiles": [] } }"#).expect("engine loads");
+        assert_eq!(engine.settings.profiles.len(), 1);
+        engine.start();
+        assert!(engine.timer.is_running());
+
+        let mut engine = Engine::default();
+        engine.settings.profiles.clear();
+        engine.restore();
+        assert_eq!(engine.settings.profiles.len(), 1);
+    }
+}
@@ -0,0 +1,41 @@
+use std::{
+    fs::OpenOptions,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    sync::mpsc,
+};
+
+use chrono::{DateTime, Utc};
+
+use crate::events::TimerEvent;
+
+/// One line of `events.jsonl`.
+#[derive(serde::Deserialize, serde::Serialize)]
+pub struct LoggedEvent {
+    pub logged_at: DateTime<Utc>,
+    pub event: TimerEvent,
+}
+
+fn append(path: &Path, event: TimerEvent) -> io::Result<()> {
+    let line = serde_json::to_string(&LoggedEvent {
+        logged_at: Utc::now(),
+        event,
+    })?;
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{line}")
+}
+
+/// Appends every event from `events` to the file at `path` on a background thread, so
+/// a slow disk never holds up the UI. Stops when the bus goes away.
+pub fn spawn(path: PathBuf, events: mpsc::Receiver<TimerEvent>) {
+    std::thread::spawn(move || {
+        if let Some(dir) = path.parent() {
+            let _ = std::fs::create_dir_all(dir);
+        }
+        for event in events {
+            if let Err(e) = append(&path, event) {
+                tracing::warn!("could not write {}: {e}", path.display());
+            }
+        }
+    });
+}
@@ -0,0 +1,119 @@
+use std::sync::mpsc;
+
+use chrono::{DateTime, Utc};
+
+use crate::timer::PomodoroMode;
+
+/// Something that happened to a timer. Timers queue these as they change, and the app
+/// (or an [`Engine`](crate::Engine)) hands them to every subscriber of its [`EventBus`].
+#[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub enum TimerEvent {
+    PhaseStarted {
+        mode: PomodoroMode,
+        ends_at: DateTime<Utc>,
+    },
+    /// A phase ran out. `next` is the phase that follows it.
+    PhaseEnded {
+        mode: PomodoroMode,
+        next: PomodoroMode,
+        started_at: DateTime<Utc>,
+        ended_at: DateTime<Utc>,
+    },
+    Paused {
+        mode: PomodoroMode,
+        at: DateTime<Utc>,
+    },
+    Resumed {
+        mode: PomodoroMode,
+        ends_at: DateTime<Utc>,
+    },
+    /// A phase was cut short by the user and `next` started right away.
+    Skipped {
+        mode: PomodoroMode,
+        next: PomodoroMode,
+        at: DateTime<Utc>,
+    },
+    /// The pomodoro timer was stopped partway through `mode`, which ran from
+    /// `started_at`.
+    Stopped {
+        mode: PomodoroMode,
+        started_at: Option<DateTime<Utc>>,
+        at: DateTime<Utc>,
+    },
+    /// One of the extra countdowns ran out.
+    CountdownFinished { label: String, at: DateTime<Utc> },
+    /// A work phase took the day's sessions or focus time to the daily limit.
+    DailyLimitReached { sessions: u32, focus_minutes: i64 },
+    /// A break ended with the daily limit reached, so the timer stopped instead of
+    /// starting work.
+    HeldForTheDay { at: DateTime<Utc> },
+    /// Several phases went by at once because the timer wasn't ticked for a while. Each
+    /// of them has its own events before this one; `mode` is the phase running now.
+    CaughtUp {
+        phases: usize,
+        sessions: usize,
+        mode: PomodoroMode,
+    },
+}
+
+impl std::fmt::Display for TimerEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TimerEvent::PhaseStarted { mode, .. } => write!(f, "{mode} started"),
+            TimerEvent::PhaseEnded { mode, .. } => write!(f, "{mode} ended"),
+            TimerEvent::Paused { mode, .. } => write!(f, "{mode} paused"),
+            TimerEvent::Resumed { mode, .. } => write!(f, "{mode} resumed"),
+            TimerEvent::Skipped { mode, .. } => write!(f, "{mode} skipped"),
+            TimerEvent::Stopped { .. } => write!(f, "Timer stopped"),
+            TimerEvent::CountdownFinished { label, .. } => write!(f, "{label} finished"),
+            TimerEvent::DailyLimitReached {
+                sessions,
+                focus_minutes,
+            } => write!(
+                f,
+                "Daily limit reached: {sessions} sessions, {}h {}m of focus",
+                focus_minutes / 60,
+                focus_minutes % 60
+            ),
+            TimerEvent::HeldForTheDay { .. } => write!(f, "Work held for the day"),
+            TimerEvent::CaughtUp {
+                phases, sessions, ..
+            } => write!(
+                f,
+                "Caught up on {phases} phases ({sessions} work sessions) that went by unseen"
+            ),
+        }
+    }
+}
+
+/// A callback registered with [`EventBus::on_event`].
+type Listener = Box<dyn FnMut(&TimerEvent) + Send>;
+
+/// Fans timer events out to any number of subscribers, each with its own channel, and
+/// to any number of callbacks.
+#[derive(Default)]
+pub struct EventBus {
+    subscribers: Vec<mpsc::Sender<TimerEvent>>,
+    callbacks: Vec<Listener>,
+}
+
+impl EventBus {
+    pub fn subscribe(&mut self) -> mpsc::Receiver<TimerEvent> {
+        let (tx, rx) = mpsc::channel();
+        self.subscribers.push(tx);
+        rx
+    }
+
+    /// Calls `callback` with every event published from now on.
+    pub fn on_event(&mut self, callback: impl FnMut(&TimerEvent) + Send + 'static) {
+        self.callbacks.push(Box::new(callback));
+    }
+
+    /// Sends `event` to every subscriber, forgetting those that hung up.
+    pub fn publish(&mut self, event: TimerEvent) {
+        for callback in &mut self.callbacks {
+            callback(&event);
+        }
+        self.subscribers.retain(|tx| tx.send(event.clone()).is_ok());
+    }
+}
@@ -0,0 +1,277 @@
+use std::{
+    collections::HashMap,
+    io::{self, BufRead, BufReader, Write},
+    net::TcpListener,
+    sync::mpsc,
+    time::{Duration, Instant},
+};
+
+use base64::Engine as _;
+use chacha20poly1305::aead::rand_core::{OsRng, RngCore};
+use chrono::{DateTime, Utc};
+use sha2::{Digest, Sha256};
+
+use crate::retry::{Failure, Worker};
+
+const AUTH_URL: &str = "https://accounts.google.com/o/oauth2/v2/auth";
+const TOKEN_URL: &str = "https://oauth2.googleapis.com/token";
+const EVENTS_URL: &str = "https://www.googleapis.com/calendar/v3/calendars";
+/// Only lets epomo manage events, not read or change the calendars themselves.
+const SCOPE: &str = "https://www.googleapis.com/auth/calendar.events";
+/// How long to wait for the browser to come back after sign-in.
+const SIGN_IN_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+
+/// A Google Cloud OAuth client of the "Desktop app" kind, and the calendar to write to.
+/// Google doesn't treat a desktop app's client secret as secret, but the refresh token
+/// gives access to the calendar, so this is kept out of the exportable settings.
+#[derive(Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct CalendarConfig {
+    pub client_id: String,
+    pub client_secret: String,
+    /// `primary`, or a calendar's ID from its Google Calendar settings.
+    pub calendar_id: String,
+    /// Set once signed in.
+    pub refresh_token: Option<String>,
+    /// Add work phases as they start, planned end and all, instead of once they're done.
+    /// A phase cut short is then shortened to match.
+    pub prebook: bool,
+}
+
+impl Default for CalendarConfig {
+    fn default() -> Self {
+        Self {
+            client_id: String::new(),
+            client_secret: String::new(),
+            calendar_id: "primary".to_owned(),
+            refresh_token: None,
+            prebook: false,
+        }
+    }
+}
+
+impl CalendarConfig {
+    pub fn is_signed_in(&self) -> bool {
+        !self.client_id.trim().is_empty() && self.refresh_token.is_some()
+    }
+}
+
+fn other(e: impl ToString) -> io::Error {
+    io::Error::new(io::ErrorKind::Other, e.to_string())
+}
+
+#[derive(serde::Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    expires_in: u64,
+    refresh_token: Option<String>,
+}
+
+/// Waits for Google to send the browser back to `listener` and returns the code it
+/// carries.
+fn receive_code(listener: &TcpListener, state: &str) -> io::Result<String> {
+    let started = Instant::now();
+    listener.set_nonblocking(true)?;
+    let mut stream = loop {
+        match listener.accept() {
+            Ok((stream, _)) => break stream,
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => {
+                if started.elapsed() > SIGN_IN_TIMEOUT {
+                    return Err(io::Error::new(
+                        io::ErrorKind::TimedOut,
+                        "sign-in wasn't finished in time",
+                    ));
+                }
+                std::thread::sleep(Duration::from_millis(200));
+            }
+            Err(e) => return Err(e),
+        }
+    };
+    stream.set_nonblocking(false)?;
+    let mut request_line = String::new();
+    BufReader::new(&stream).read_line(&mut request_line)?;
+    // e.g. `GET /?state=...&code=...&scope=... HTTP/1.1`
+    let target = request_line.split_whitespace().nth(1).unwrap_or_default();
+    let query = url::Url::parse(&format!("http://localhost{target}")).map_err(other)?;
+    let params: HashMap<String, String> = query.query_pairs().into_owned().collect();
+    let reply = if params.contains_key("code") {
+        "You're signed in. You can close this tab and go back to epomo."
+    } else {
+        "Signing in didn't work. You can close this tab and try again from epomo."
+    };
+    let _ = write!(
+        stream,
+        "HTTP/1.1 200 OK\r\nContent-Type: text/plain; charset=utf-8\r\nConnection: close\r\n\r\n{reply}"
+    );
+    if params.get("state").map(String::as_str) != Some(state) {
+        return Err(other("the sign-in reply didn't match the request"));
+    }
+    match (params.get("code"), params.get("error")) {
+        (Some(code), _) => Ok(code.clone()),
+        (None, Some(error)) => Err(other(format!("Google said {error}"))),
+        (None, None) => Err(other("the sign-in reply had no code")),
+    }
+}
+
+/// Signs in with Google through the browser, using a loopback redirect with PKCE as
+/// Google recommends for desktop apps. Returns the URL to open, and a channel that gets
+/// the refresh token once the user has agreed.
+pub fn sign_in(
+    config: &CalendarConfig,
+) -> io::Result<(String, mpsc::Receiver<io::Result<String>>)> {
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    let redirect_uri = format!("http://127.0.0.1:{}", listener.local_addr()?.port());
+    let random = || {
+        let mut bytes = [0; 48];
+        OsRng.fill_bytes(&mut bytes);
+        base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(bytes)
+    };
+    let (verifier, state) = (random(), random());
+    let challenge = base64::engine::general_purpose::URL_SAFE_NO_PAD
+        .encode(Sha256::digest(verifier.as_bytes()));
+    let url = url::Url::parse_with_params(
+        AUTH_URL,
+        &[
+            ("client_id", config.client_id.trim()),
+            ("redirect_uri", &redirect_uri),
+            ("response_type", "code"),
+            ("scope", SCOPE),
+            ("code_challenge", &challenge),
+            ("code_challenge_method", "S256"),
+            ("state", &state),
+            // Ask for a refresh token, and for it again if signing in a second time.
+            ("access_type", "offline"),
+            ("prompt", "consent"),
+        ],
+    )
+    .map_err(other)?;
+
+    let config = config.clone();
+    let (tx, rx) = mpsc::channel();
+    std::thread::spawn(move || {
+        let res = receive_code(&listener, &state).and_then(|code| {
+            let response: TokenResponse = ureq::post(TOKEN_URL)
+                .send_form(&[
+                    ("client_id", config.client_id.trim()),
+                    ("client_secret", config.client_secret.trim()),
+                    ("code", &code),
+                    ("code_verifier", &verifier),
+                    ("grant_type", "authorization_code"),
+                    ("redirect_uri", &redirect_uri),
+                ])
+                .map_err(other)?
+                .into_json()?;
+            response
+                .refresh_token
+                .ok_or_else(|| other("Google didn't hand out a refresh token"))
+        });
+        let _ = tx.send(res);
+    });
+    Ok((url.to_string(), rx))
+}
+
+/// A change to make to the calendar. `key` ties an event to the phase it's for.
+pub enum Job {
+    Add {
+        key: DateTime<Utc>,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        summary: String,
+    },
+    /// Moves the end of the event added for `key`, when a phase ends early.
+    EndAt {
+        key: DateTime<Utc>,
+        end: DateTime<Utc>,
+    },
+}
+
+/// Talks to the Calendar API, keeping an access token for as long as it lasts.
+#[derive(Default)]
+struct Client {
+    access_token: Option<(String, Instant)>,
+    /// Events added so far, by the key they were added with.
+    events: HashMap<DateTime<Utc>, String>,
+}
+
+impl Client {
+    fn token(&mut self, config: &CalendarConfig) -> Result<String, Failure> {
+        if let Some((token, expires)) = &self.access_token {
+            if Instant::now() < *expires {
+                return Ok(token.clone());
+            }
+        }
+        let refresh_token = config.refresh_token.clone().unwrap_or_default();
+        let response: TokenResponse = ureq::post(TOKEN_URL)
+            .send_form(&[
+                ("client_id", config.client_id.trim()),
+                ("client_secret", config.client_secret.trim()),
+                ("refresh_token", &refresh_token),
+                ("grant_type", "refresh_token"),
+            ])
+            .map_err(|e| Failure::from_ureq("Google", e))?
+            .into_json()
+            .map_err(|e| Failure::Transient(e.to_string()))?;
+        // Renewed a minute early so it can't run out mid-request.
+        let lifetime = Duration::from_secs(response.expires_in.saturating_sub(60));
+        self.access_token = Some((response.access_token.clone(), Instant::now() + lifetime));
+        Ok(response.access_token)
+    }
+
+    fn events_url(config: &CalendarConfig) -> String {
+        let calendar: String =
+            url::form_urlencoded::byte_serialize(config.calendar_id.trim().as_bytes()).collect();
+        format!("{EVENTS_URL}/{calendar}/events")
+    }
+
+    fn run(&mut self, config: &CalendarConfig, job: &Job) -> Result<(), Failure> {
+        let token = self.token(config)?;
+        let auth = format!("Bearer {token}");
+        match job {
+            Job::Add {
+                key,
+                start,
+                end,
+                summary,
+            } => {
+                #[derive(serde::Deserialize)]
+                struct Created {
+                    id: String,
+                }
+                let created: Created = ureq::post(&Self::events_url(config))
+                    .set("Authorization", &auth)
+                    .send_json(serde_json::json!({
+                        "summary": summary,
+                        "start": { "dateTime": start.to_rfc3339() },
+                        "end": { "dateTime": end.to_rfc3339() },
+                        // Shows as busy to others.
+                        "transparency": "opaque",
+                    }))
+                    .map_err(|e| Failure::from_ureq("Google Calendar", e))?
+                    .into_json()
+                    .map_err(|e| Failure::Transient(e.to_string()))?;
+                self.events.insert(*key, created.id);
+            }
+            Job::EndAt { key, end } => {
+                let Some(id) = self.events.get(key) else {
+                    // Never made it to the calendar, so there's nothing to move.
+                    return Ok(());
+                };
+                ureq::request("PATCH", &format!("{}/{id}", Self::events_url(config)))
+                    .set("Authorization", &auth)
+                    .send_json(serde_json::json!({ "end": { "dateTime": end.to_rfc3339() } }))
+                    .map_err(|e| Failure::from_ureq("Google Calendar", e))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Adds focus blocks to a Google Calendar, retrying while Google can't be reached.
+pub type CalendarSync = Worker<CalendarConfig, Job>;
+
+pub fn updater(config: CalendarConfig) -> CalendarSync {
+    let mut client = Client::default();
+    Worker::start("Calendar update", config, move |config, job| {
+        client.run(config, job)
+    })
+}
@@ -0,0 +1,499 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Datelike, FixedOffset, Local, NaiveDate, TimeZone, Timelike, Utc};
+
+/// A completed work session.
+#[derive(Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct Session {
+    pub started_at: DateTime<Utc>,
+    pub ended_at: DateTime<Utc>,
+    /// The app in the foreground for most of the session, if app tracking was on.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub app: Option<String>,
+    /// What the session was spent on, e.g. "writing", if it was tagged.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tag: Option<String>,
+    /// Name of the profile that was active. Unknown for sessions recorded before it was
+    /// stored, and for imported ones.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile: Option<String>,
+    /// How the user felt afterwards, from 1 (drained) to 5 (energised), if asked.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub energy: Option<u8>,
+    /// Seconds east of UTC of the timezone the session was recorded in. Unknown for
+    /// sessions recorded before it was stored, and for imported ones.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub utc_offset: Option<i32>,
+}
+
+/// Seconds east of UTC of the current timezone at `time`.
+fn utc_offset(time: DateTime<Utc>) -> i32 {
+    Local
+        .offset_from_utc_datetime(&time.naive_utc())
+        .local_minus_utc()
+}
+
+/// `time` on the clock of the timezone `utc_offset` seconds east of UTC, or the current
+/// timezone if that's unknown. Keeps a session on the day it happened on after
+/// travelling.
+fn on_clock(time: DateTime<Utc>, utc_offset: Option<i32>) -> DateTime<FixedOffset> {
+    let offset = utc_offset
+        .and_then(FixedOffset::east_opt)
+        .unwrap_or_else(|| *time.with_timezone(&Local).offset());
+    time.with_timezone(&offset)
+}
+
+impl Session {
+    /// `time` on the clock of wherever the session was recorded.
+    fn local(&self, time: DateTime<Utc>) -> DateTime<FixedOffset> {
+        on_clock(time, self.utc_offset)
+    }
+
+    pub fn local_start(&self) -> DateTime<FixedOffset> {
+        self.local(self.started_at)
+    }
+
+    pub fn local_end(&self) -> DateTime<FixedOffset> {
+        self.local(self.ended_at)
+    }
+
+    pub fn duration(&self) -> chrono::Duration {
+        self.ended_at - self.started_at
+    }
+}
+
+/// A work session stopped before it was over. Kept apart from the finished ones so it
+/// doesn't count towards goals or streaks, but the time still shows up in stats.
+#[derive(Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct Abandoned {
+    pub started_at: DateTime<Utc>,
+    pub ended_at: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tag: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile: Option<String>,
+    /// Why it was stopped, if the user said.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    /// Seconds east of UTC of the timezone it was recorded in, like
+    /// [`Session::utc_offset`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub utc_offset: Option<i32>,
+}
+
+impl Abandoned {
+    pub fn local_start(&self) -> DateTime<FixedOffset> {
+        on_clock(self.started_at, self.utc_offset)
+    }
+
+    pub fn duration(&self) -> chrono::Duration {
+        self.ended_at - self.started_at
+    }
+}
+
+/// Which sessions the stats look at.
+#[derive(Clone, Default, PartialEq)]
+pub enum SessionFilter {
+    #[default]
+    All,
+    Profile(String),
+    Tag(String),
+}
+
+impl SessionFilter {
+    fn matches(&self, profile: Option<&String>, tag: Option<&String>) -> bool {
+        match self {
+            SessionFilter::All => true,
+            SessionFilter::Profile(name) => profile == Some(name),
+            SessionFilter::Tag(name) => tag == Some(name),
+        }
+    }
+}
+
+#[derive(Clone, Default, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct History {
+    pub sessions: Vec<Session>,
+    pub abandoned: Vec<Abandoned>,
+}
+
+impl History {
+    pub fn record(
+        &mut self,
+        started_at: DateTime<Utc>,
+        ended_at: DateTime<Utc>,
+        app: Option<String>,
+        tag: Option<String>,
+        profile: Option<String>,
+    ) {
+        self.sessions.push(Session {
+            started_at,
+            ended_at,
+            app,
+            tag,
+            profile,
+            energy: None,
+            utc_offset: Some(utc_offset(started_at)),
+        });
+    }
+
+    /// Notes a work session that was stopped early.
+    pub fn abandon(
+        &mut self,
+        started_at: DateTime<Utc>,
+        ended_at: DateTime<Utc>,
+        tag: Option<String>,
+        profile: Option<String>,
+    ) {
+        self.abandoned.push(Abandoned {
+            started_at,
+            ended_at,
+            tag,
+            profile,
+            reason: None,
+            utc_offset: Some(utc_offset(started_at)),
+        });
+    }
+
+    /// Says why the session stopped early at `started_at` was stopped.
+    pub fn set_abandon_reason(&mut self, started_at: DateTime<Utc>, reason: String) {
+        if let Some(abandoned) = self
+            .abandoned
+            .iter_mut()
+            .find(|a| a.started_at == started_at)
+        {
+            abandoned.reason = Some(reason);
+        }
+    }
+
+    /// The share of work sessions that were stopped early, or `None` before the first.
+    pub fn abandonment_rate(&self) -> Option<f64> {
+        let total = self.sessions.len() + self.abandoned.len();
+        (total > 0).then(|| self.abandoned.len() as f64 / total as f64)
+    }
+
+    /// Only the sessions, finished or stopped early, that `filter` lets through.
+    pub fn filtered(&self, filter: &SessionFilter) -> History {
+        History {
+            sessions: self
+                .sessions
+                .iter()
+                .filter(|s| filter.matches(s.profile.as_ref(), s.tag.as_ref()))
+                .cloned()
+                .collect(),
+            abandoned: self
+                .abandoned
+                .iter()
+                .filter(|a| filter.matches(a.profile.as_ref(), a.tag.as_ref()))
+                .cloned()
+                .collect(),
+        }
+    }
+
+    /// Adds the sessions not already present and keeps the list in chronological order.
+    /// Returns how many were new.
+    pub fn merge(&mut self, sessions: impl IntoIterator<Item = Session>) -> usize {
+        let before = self.sessions.len();
+        let mut index: HashMap<_, usize> = self
+            .sessions
+            .iter()
+            .enumerate()
+            .map(|(i, s)| ((s.started_at, s.ended_at), i))
+            .collect();
+        for session in sessions {
+            match index.get(&(session.started_at, session.ended_at)) {
+                // It may have been rated on the other device after it was first synced.
+                Some(&i) => {
+                    let known = &mut self.sessions[i];
+                    known.energy = known.energy.or(session.energy);
+                    known.profile = known.profile.take().or(session.profile);
+                }
+                None => {
+                    index.insert((session.started_at, session.ended_at), self.sessions.len());
+                    self.sessions.push(session);
+                }
+            }
+        }
+        self.sessions.sort_by_key(|s| s.started_at);
+        self.sessions.len() - before
+    }
+
+    /// Adds the sessions stopped early that aren't already present, matched by start and
+    /// end, and keeps the list in chronological order. Returns how many were new.
+    pub fn merge_abandoned(&mut self, abandoned: impl IntoIterator<Item = Abandoned>) -> usize {
+        let before = self.abandoned.len();
+        let mut index: HashMap<_, usize> = self
+            .abandoned
+            .iter()
+            .enumerate()
+            .map(|(i, a)| ((a.started_at, a.ended_at), i))
+            .collect();
+        for other in abandoned {
+            match index.get(&(other.started_at, other.ended_at)) {
+                // The reason may have been given on the other device.
+                Some(&i) => {
+                    let known = &mut self.abandoned[i];
+                    known.reason = known.reason.take().or(other.reason);
+                }
+                None => {
+                    index.insert((other.started_at, other.ended_at), self.abandoned.len());
+                    self.abandoned.push(other);
+                }
+            }
+        }
+        self.abandoned.sort_by_key(|a| a.started_at);
+        self.abandoned.len() - before
+    }
+
+    /// Drops sessions overlapping a longer one, which happens when the same stretch of
+    /// time was tracked on two devices. Returns how many were dropped.
+    pub fn resolve_overlaps(&mut self) -> usize {
+        self.sessions.sort_by_key(|s| s.started_at);
+        let before = self.sessions.len();
+        let mut kept: Vec<Session> = Vec::with_capacity(before);
+        for session in self.sessions.drain(..) {
+            match kept.last_mut() {
+                Some(last) if session.started_at < last.ended_at => {
+                    if session.duration() > last.duration() {
+                        *last = session;
+                    }
+                }
+                _ => kept.push(session),
+            }
+        }
+        self.sessions = kept;
+        before - self.sessions.len()
+    }
+
+    /// Sessions started on the local day `day`.
+    pub fn count_on(&self, day: NaiveDate) -> usize {
+        self.sessions
+            .iter()
+            .filter(|s| s.local_start().date_naive() == day)
+            .count()
+    }
+
+    /// Sessions started on the local day `day` or later.
+    pub fn count_since(&self, day: NaiveDate) -> usize {
+        self.sessions
+            .iter()
+            .filter(|s| s.local_start().date_naive() >= day)
+            .count()
+    }
+
+    /// Minutes of focus on each of the last `days` local days, oldest first.
+    pub fn focus_minutes_by_day(&self, days: u32) -> Vec<(NaiveDate, i64)> {
+        let today = Local::now().date_naive();
+        (0..days as i64)
+            .rev()
+            .map(|ago| {
+                let day = today - chrono::Duration::days(ago);
+                let minutes = self
+                    .sessions
+                    .iter()
+                    .filter(|s| s.local_start().date_naive() == day)
+                    .map(|s| s.duration().num_minutes())
+                    .sum();
+                (day, minutes)
+            })
+            .collect()
+    }
+
+    /// Sessions started in each local hour of the day.
+    pub fn sessions_by_hour(&self) -> [usize; 24] {
+        let mut counts = [0; 24];
+        for s in &self.sessions {
+            counts[s.local_start().hour() as usize] += 1;
+        }
+        counts
+    }
+
+    /// The share of work sessions started in each local hour of the day that were
+    /// stopped early. `None` for hours without any.
+    pub fn abandonment_by_hour(&self) -> [Option<f64>; 24] {
+        let (mut finished, mut stopped) = ([0usize; 24], [0usize; 24]);
+        for s in &self.sessions {
+            finished[s.local_start().hour() as usize] += 1;
+        }
+        for a in &self.abandoned {
+            stopped[a.local_start().hour() as usize] += 1;
+        }
+        let mut rates = [None; 24];
+        for (hour, rate) in rates.iter_mut().enumerate() {
+            let total = finished[hour] + stopped[hour];
+            *rate = (total > 0).then(|| stopped[hour] as f64 / total as f64);
+        }
+        rates
+    }
+
+    /// Sessions started on each weekday, Monday first.
+    pub fn sessions_by_weekday(&self) -> [usize; 7] {
+        let mut counts = [0; 7];
+        for s in &self.sessions {
+            let weekday = s.local_start().weekday();
+            counts[weekday.num_days_from_monday() as usize] += 1;
+        }
+        counts
+    }
+
+    /// How many sessions each app was the main one in, most first.
+    pub fn sessions_by_app(&self) -> Vec<(String, usize)> {
+        most_common(self.sessions.iter().filter_map(|s| s.app.as_ref()))
+    }
+
+    /// Rates the session that started at `started_at`.
+    pub fn rate(&mut self, started_at: DateTime<Utc>, energy: u8) {
+        if let Some(session) = self
+            .sessions
+            .iter_mut()
+            .find(|s| s.started_at == started_at)
+        {
+            session.energy = Some(energy.clamp(1, 5));
+        }
+    }
+
+    /// The average rating of the rated sessions in each of `N` groups, with `group`
+    /// picking a session's group. `None` for groups with no ratings.
+    fn average_energy<const N: usize>(
+        &self,
+        group: impl Fn(&Session) -> usize,
+    ) -> [Option<f64>; N] {
+        let mut sums = [(0u32, 0u32); N];
+        for s in &self.sessions {
+            if let Some(energy) = s.energy {
+                let (sum, count) = &mut sums[group(s).min(N - 1)];
+                *sum += u32::from(energy);
+                *count += 1;
+            }
+        }
+        sums.map(|(sum, count)| (count > 0).then(|| f64::from(sum) / f64::from(count)))
+    }
+
+    /// Average rating of sessions started at night (0–6), in the morning (6–12), the
+    /// afternoon (12–18) and the evening (18–24), local time.
+    pub fn energy_by_time_of_day(&self) -> [Option<f64>; 4] {
+        self.average_energy(|s| s.local_start().hour() as usize / 6)
+    }
+
+    /// Average rating of sessions under 20 minutes, 20 to 34, 35 to 49 and 50 or more.
+    pub fn energy_by_length(&self) -> [Option<f64>; 4] {
+        self.average_energy(|s| match s.duration().num_minutes() {
+            minutes if minutes < 20 => 0,
+            20..=34 => 1,
+            35..=49 => 2,
+            _ => 3,
+        })
+    }
+
+    /// How many sessions have each tag, most used first.
+    pub fn sessions_by_tag(&self) -> Vec<(String, usize)> {
+        most_common(self.sessions.iter().filter_map(|s| s.tag.as_ref()))
+    }
+
+    /// How many sessions were run with each profile, most used first.
+    pub fn sessions_by_profile(&self) -> Vec<(String, usize)> {
+        most_common(self.sessions.iter().filter_map(|s| s.profile.as_ref()))
+    }
+
+    /// Minutes of focus since the local day `day` for each value `key` gives sessions,
+    /// most first. Sessions without one are left out.
+    pub fn focus_minutes_since_by(
+        &self,
+        day: NaiveDate,
+        key: impl Fn(&Session) -> Option<&String>,
+    ) -> Vec<(String, i64)> {
+        let mut minutes: Vec<(String, i64)> = Vec::new();
+        for s in self
+            .sessions
+            .iter()
+            .filter(|s| s.local_start().date_naive() >= day)
+        {
+            let Some(value) = key(s) else {
+                continue;
+            };
+            let m = s.duration().num_minutes();
+            match minutes.iter_mut().find(|(v, _)| v == value) {
+                Some((_, total)) => *total += m,
+                None => minutes.push((value.clone(), m)),
+            }
+        }
+        minutes.sort_by_key(|&(_, m)| std::cmp::Reverse(m));
+        minutes
+    }
+}
+
+/// Counts each distinct value, most common first. Ties keep the order first seen in.
+fn most_common<'a>(values: impl Iterator<Item = &'a String>) -> Vec<(String, usize)> {
+    let mut counts: Vec<(String, usize)> = Vec::new();
+    for value in values {
+        match counts.iter_mut().find(|(v, _)| v == value) {
+            Some((_, n)) => *n += 1,
+            None => counts.push((value.clone(), 1)),
+        }
+    }
+    counts.sort_by_key(|&(_, n)| std::cmp::Reverse(n));
+    counts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `hour:minute` today, on the local clock, which is the one sessions are recorded
+    /// with.
+    fn at(hour: u32, minute: u32) -> DateTime<Utc> {
+        let time = Local::now()
+            .date_naive()
+            .and_hms_opt(hour, minute, 0)
+            .unwrap();
+        Local
+            .from_local_datetime(&time)
+            .earliest()
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    #[test]
+    fn abandonment_rate_by_hour() {
+        let mut history = History::default();
+        history.record(at(9, 0), at(9, 25), None, None, None);
+        history.record(at(9, 30), at(9, 55), None, None, None);
+        history.abandon(at(9, 40), at(9, 50), None, None);
+        history.record(at(14, 0), at(14, 25), None, None, None);
+        history.abandon(at(20, 0), at(20, 5), None, None);
+
+        let rates = history.abandonment_by_hour();
+        assert_eq!(rates[9], Some(1.0 / 3.0));
+        assert_eq!(rates[14], Some(0.0));
+        assert_eq!(rates[20], Some(1.0));
+        assert_eq!(rates[3], None);
+    }
+
+    #[test]
+    fn abandonment_goes_by_the_clock_it_was_recorded_on() {
+        let mut history = History::default();
+        let start = Utc.with_ymd_and_hms(2026, 3, 2, 0, 30, 0).unwrap();
+        history.abandon(start, start + chrono::Duration::minutes(5), None, None);
+        history.abandoned[0].utc_offset = Some(9 * 3600);
+
+        let rates = history.abandonment_by_hour();
+        assert_eq!(rates[9], Some(1.0));
+        assert_eq!(rates.iter().flatten().count(), 1);
+    }
+
+    #[test]
+    fn merging_skips_known_sessions_and_keeps_their_ratings() {
+        let mut ours = History::default();
+        ours.record(at(9, 0), at(9, 25), None, None, None);
+        ours.record(at(10, 0), at(10, 25), None, None, None);
+        let mut theirs = ours.clone();
+        theirs.sessions[0].energy = Some(3);
+        theirs.record(at(8, 0), at(8, 25), None, None, None);
+
+        assert_eq!(ours.merge(theirs.sessions.clone()), 1);
+        assert_eq!(ours.merge(theirs.sessions), 0);
+        let starts: Vec<_> = ours.sessions.iter().map(|s| s.started_at).collect();
+        assert_eq!(starts, [at(8, 0), at(9, 0), at(10, 0)]);
+        assert_eq!(ours.sessions[1].energy, Some(3));
+    }
+}
@@ -0,0 +1,163 @@
+use std::{io, path::Path};
+
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, TimeZone, Utc};
+
+use crate::history::Session;
+
+/// Column names other apps use for the start of a session, lowercased.
+const START_COLUMNS: &[&str] = &["start", "start time", "started_at", "startdate", "date"];
+/// Column names for the end of a session.
+const END_COLUMNS: &[&str] = &["end", "end time", "ended_at", "enddate"];
+/// Column names for the length of a session in minutes.
+const MINUTES_COLUMNS: &[&str] = &[
+    "minutes",
+    "duration",
+    "duration (min)",
+    "focus time",
+    "focus time(min)",
+    "focus time (min)",
+];
+
+fn invalid(msg: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg.into())
+}
+
+/// Reads the timestamp formats seen in exports. Times without a zone are local.
+fn parse_time(s: &str) -> Option<DateTime<Utc>> {
+    let s = s.trim();
+    if let Ok(t) = DateTime::parse_from_rfc3339(s) {
+        return Some(t.with_timezone(&Utc));
+    }
+    if let Ok(ms) = s.parse::<i64>() {
+        // Unix time in milliseconds, as JSON exports tend to use.
+        return Utc.timestamp_millis_opt(ms).single();
+    }
+    let naive = [
+        "%Y-%m-%d %H:%M:%S",
+        "%Y-%m-%d %H:%M",
+        "%Y/%m/%d %H:%M:%S",
+        "%Y/%m/%d %H:%M",
+        "%m/%d/%Y %H:%M:%S",
+        "%m/%d/%Y %H:%M",
+        "%d.%m.%Y %H:%M",
+    ]
+    .iter()
+    .find_map(|fmt| NaiveDateTime::parse_from_str(s, fmt).ok())
+    .or_else(|| {
+        // A bare date: put the session at noon so it lands on the right day.
+        ["%Y-%m-%d", "%Y/%m/%d", "%m/%d/%Y", "%d.%m.%Y"]
+            .iter()
+            .find_map(|fmt| NaiveDate::parse_from_str(s, fmt).ok())
+            .and_then(|d| d.and_hms_opt(12, 0, 0))
+    })?;
+    Local
+        .from_local_datetime(&naive)
+        .earliest()
+        .map(|t| t.with_timezone(&Utc))
+}
+
+/// Builds a session from whichever of start, end and length a record has.
+fn session(start: Option<&str>, end: Option<&str>, minutes: Option<&str>) -> Option<Session> {
+    let start = start.and_then(parse_time);
+    let end = end.and_then(parse_time);
+    let length = minutes
+        .and_then(|m| m.trim().parse::<f64>().ok())
+        .map(|m| chrono::Duration::seconds((m * 60.0) as i64));
+    let (started_at, ended_at) = match (start, end, length) {
+        (Some(start), Some(end), _) => (start, end),
+        (Some(start), None, Some(length)) => (start, start + length),
+        (None, Some(end), Some(length)) => (end - length, end),
+        _ => return None,
+    };
+    (ended_at > started_at).then_some(Session {
+        started_at,
+        ended_at,
+        app: None,
+        tag: None,
+        profile: None,
+        energy: None,
+        utc_offset: None,
+    })
+}
+
+fn column(headers: &[String], names: &[&str]) -> Option<usize> {
+    headers.iter().position(|h| names.contains(&h.as_str()))
+}
+
+fn from_csv(contents: &str) -> io::Result<Vec<Session>> {
+    let mut reader = csv::ReaderBuilder::new()
+        .flexible(true)
+        .from_reader(contents.as_bytes());
+    let headers: Vec<String> = reader
+        .headers()
+        .map_err(|e| invalid(e.to_string()))?
+        .iter()
+        .map(|h| h.trim().to_lowercase())
+        .collect();
+    let start = column(&headers, START_COLUMNS);
+    let end = column(&headers, END_COLUMNS);
+    let minutes = column(&headers, MINUTES_COLUMNS);
+    if start.is_none() && end.is_none() {
+        return Err(invalid("no start or end time column found"));
+    }
+    let mut sessions = Vec::new();
+    for record in reader.records() {
+        let record = record.map_err(|e| invalid(e.to_string()))?;
+        let field = |i: Option<usize>| i.and_then(|i| record.get(i));
+        sessions.extend(session(field(start), field(end), field(minutes)));
+    }
+    Ok(sessions)
+}
+
+fn from_json(contents: &str) -> io::Result<Vec<Session>> {
+    let value: serde_json::Value = serde_json::from_str(contents)?;
+    // Either a bare array of records or an object holding one.
+    let records = match &value {
+        serde_json::Value::Array(records) => records,
+        serde_json::Value::Object(map) => map
+            .values()
+            .find_map(|v| v.as_array())
+            .ok_or_else(|| invalid("no list of sessions found"))?,
+        _ => return Err(invalid("no list of sessions found")),
+    };
+    let text = |v: &serde_json::Value| match v {
+        serde_json::Value::String(s) => Some(s.clone()),
+        serde_json::Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    };
+    let sessions = records
+        .iter()
+        .filter_map(|record| {
+            let record = record.as_object()?;
+            let field = |names: &[&str]| {
+                record
+                    .iter()
+                    .find(|(k, _)| names.contains(&k.to_lowercase().as_str()))
+                    .and_then(|(_, v)| text(v))
+            };
+            session(
+                field(START_COLUMNS).as_deref(),
+                field(END_COLUMNS).as_deref(),
+                field(MINUTES_COLUMNS).as_deref(),
+            )
+        })
+        .collect();
+    Ok(sessions)
+}
+
+/// Reads the sessions from another pomodoro app's export, e.g. from Pomofocus or Focus
+/// To-Do. Columns are matched by name, so most CSV or JSON exports with a start time
+/// and either an end time or a length work.
+pub fn read(path: &Path) -> io::Result<Vec<Session>> {
+    let contents = std::fs::read_to_string(path)?;
+    let is_json = path
+        .extension()
+        .map_or(false, |ext| ext.eq_ignore_ascii_case("json"));
+    let mut sessions = if is_json {
+        from_json(&contents)?
+    } else {
+        from_csv(&contents)?
+    };
+    sessions.sort_by_key(|s| s.started_at);
+    Ok(sessions)
+}
@@ -0,0 +1,62 @@
+use std::{process::Command, sync::mpsc, time::Duration};
+
+use zbus::blocking::Connection;
+
+/// How often the screensaver is nudged when there's no D-Bus service to ask.
+const RESET_INTERVAL: Duration = Duration::from_secs(30);
+
+const SERVICE: &str = "org.freedesktop.ScreenSaver";
+const PATH: &str = "/org/freedesktop/ScreenSaver";
+
+fn inhibit() -> zbus::Result<(Connection, u32)> {
+    let conn = Connection::session()?;
+    let cookie = conn
+        .call_method(
+            Some(SERVICE),
+            PATH,
+            Some(SERVICE),
+            "Inhibit",
+            &("epomo", "Focus session"),
+        )?
+        .body::<u32>()?;
+    Ok((conn, cookie))
+}
+
+/// Keeps the screen from blanking until dropped.
+///
+/// Asks the desktop's `org.freedesktop.ScreenSaver` service, which GNOME, KDE and most
+/// other desktops provide on both Wayland and X11. Wayland's idle-inhibit protocol
+/// would need the window's surface, which eframe doesn't expose, so without that
+/// service this falls back to resetting the X11 screensaver every so often.
+pub struct IdleInhibitor {
+    // Dropping this wakes the thread up, which then lifts the inhibition.
+    _stop: mpsc::Sender<()>,
+}
+
+impl IdleInhibitor {
+    pub fn start() -> Self {
+        let (stop, stopped) = mpsc::channel::<()>();
+        std::thread::spawn(move || match inhibit() {
+            Ok((conn, cookie)) => {
+                let _ = stopped.recv();
+                if let Err(e) =
+                    conn.call_method(Some(SERVICE), PATH, Some(SERVICE), "UnInhibit", &cookie)
+                {
+                    tracing::warn!("could not lift the screensaver inhibition: {e}");
+                }
+            }
+            Err(e) => {
+                tracing::info!("no screensaver service ({e}), using xdg-screensaver");
+                while let Err(mpsc::RecvTimeoutError::Timeout) =
+                    stopped.recv_timeout(RESET_INTERVAL)
+                {
+                    if let Err(e) = Command::new("xdg-screensaver").arg("reset").status() {
+                        tracing::warn!("could not run xdg-screensaver: {e}");
+                        break;
+                    }
+                }
+            }
+        });
+        Self { _stop: stop }
+    }
+}
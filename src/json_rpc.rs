@@ -0,0 +1,139 @@
+use std::{
+    io::{self, BufRead, Write},
+    sync::mpsc,
+    time::Duration,
+};
+
+use chrono::{DateTime, Utc};
+
+use crate::{engine::Engine, events::TimerEvent, settings::Settings, timer::PomodoroMode};
+
+/// One line read from stdin, e.g. `{"command": "configure", "work": 50}`.
+#[derive(serde::Deserialize)]
+#[serde(tag = "command", rename_all = "snake_case")]
+enum Command {
+    Start,
+    Pause,
+    Resume,
+    Skip,
+    Stop,
+    /// Asks for a [`Output::Status`] line.
+    Status,
+    /// Changes the active profile's lengths, in minutes. Left out fields stay as they
+    /// are. Takes effect from the next phase.
+    Configure {
+        work: Option<i64>,
+        short_break: Option<i64>,
+        long_break: Option<i64>,
+        sessions_per_long_break: Option<usize>,
+    },
+}
+
+/// One line written to stdout.
+#[derive(serde::Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Output<'a> {
+    Event {
+        event: &'a TimerEvent,
+    },
+    Status {
+        mode: PomodoroMode,
+        running: bool,
+        paused: bool,
+        ends_at: Option<DateTime<Utc>>,
+        session_count: usize,
+    },
+    Error {
+        message: String,
+    },
+}
+
+fn emit(output: &Output<'_>) {
+    let line = serde_json::to_string(output).expect("output always serializes");
+    let mut stdout = io::stdout().lock();
+    // Nobody is listening any more if this fails, and the read loop finds out soon.
+    let _ = writeln!(stdout, "{line}");
+    let _ = stdout.flush();
+}
+
+fn status(engine: &Engine) -> Output<'static> {
+    let timer = &engine.timer;
+    Output::Status {
+        mode: timer.mode,
+        running: timer.is_running(),
+        paused: timer.is_paused(),
+        ends_at: timer.ends_at,
+        session_count: timer.session_count,
+    }
+}
+
+/// Runs the timer without a window, taking one JSON command per line on stdin and
+/// writing every state change to stdout as a JSON line. Returns when stdin closes.
+pub fn run_json_rpc() -> io::Result<()> {
+    let mut engine = Engine::new(Settings::default());
+    engine.on_event(|event| emit(&Output::Event { event }));
+
+    // Reading stdin blocks, so it happens on a thread of its own and the loop below
+    // stays free to tick the timer.
+    let (tx, lines) = mpsc::channel();
+    std::thread::spawn(move || {
+        for line in io::stdin().lock().lines() {
+            if tx.send(line).is_err() {
+                break;
+            }
+        }
+    });
+
+    loop {
+        let line = match lines.recv_timeout(Duration::from_secs(1)) {
+            Ok(line) => line?,
+            Err(mpsc::RecvTimeoutError::Timeout) => {
+                engine.tick();
+                continue;
+            }
+            Err(mpsc::RecvTimeoutError::Disconnected) => return Ok(()),
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+        let command = match serde_json::from_str(&line) {
+            Ok(command) => command,
+            Err(e) => {
+                emit(&Output::Error {
+                    message: format!("could not read command: {e}"),
+                });
+                continue;
+            }
+        };
+        match command {
+            Command::Start => engine.start(),
+            Command::Pause => engine.pause(),
+            Command::Resume => engine.resume(),
+            Command::Skip => engine.skip(),
+            Command::Stop => engine.stop(),
+            Command::Status => emit(&status(&engine)),
+            Command::Configure {
+                work,
+                short_break,
+                long_break,
+                sessions_per_long_break,
+            } => {
+                let profile = engine.settings.profile_mut();
+                if let Some(minutes) = work {
+                    profile.interval_period = minutes.max(1);
+                }
+                if let Some(minutes) = short_break {
+                    profile.short_break_period = minutes.max(1);
+                }
+                if let Some(minutes) = long_break {
+                    profile.long_break_period = minutes.max(1);
+                }
+                if let Some(sessions) = sessions_per_long_break {
+                    profile.sessions_per_long_break = sessions.max(1);
+                }
+                emit(&status(&engine));
+            }
+        }
+        engine.tick();
+    }
+}
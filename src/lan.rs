@@ -0,0 +1,155 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    io::{self, Write},
+    net::{IpAddr, SocketAddr, TcpListener, TcpStream},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use mdns_sd::{ServiceDaemon, ServiceEvent, ServiceInfo};
+
+use crate::room::{RoomMessage, SharedTimer};
+
+const SERVICE_TYPE: &str = "_epomo._tcp.local.";
+
+fn other(e: impl std::error::Error + Send + Sync + 'static) -> io::Error {
+    io::Error::new(io::ErrorKind::Other, e)
+}
+
+/// Advertises this instance on the local network and pushes the timer to everyone who
+/// joins, one JSON line per change.
+pub struct LanHost {
+    mdns: ServiceDaemon,
+    fullname: String,
+    clients: Arc<Mutex<Vec<TcpStream>>>,
+    last: Arc<Mutex<Option<String>>>,
+    stop: Arc<AtomicBool>,
+}
+
+impl LanHost {
+    pub fn start(name: &str, device_id: &str) -> io::Result<Self> {
+        let listener = TcpListener::bind(("0.0.0.0", 0))?;
+        listener.set_nonblocking(true)?;
+        let port = listener.local_addr()?.port();
+
+        let mdns = ServiceDaemon::new().map_err(other)?;
+        let host_name = format!("epomo-{device_id}.local.");
+        let info = ServiceInfo::new(
+            SERVICE_TYPE,
+            name,
+            &host_name,
+            "",
+            port,
+            None::<HashMap<String, String>>,
+        )
+        .map_err(other)?
+        .enable_addr_auto();
+        let fullname = info.get_fullname().to_owned();
+        mdns.register(info).map_err(other)?;
+
+        let clients: Arc<Mutex<Vec<TcpStream>>> = Arc::default();
+        let last: Arc<Mutex<Option<String>>> = Arc::default();
+        let stop = Arc::new(AtomicBool::new(false));
+        {
+            let (clients, last, stop) = (clients.clone(), last.clone(), stop.clone());
+            std::thread::spawn(move || {
+                while !stop.load(Ordering::Relaxed) {
+                    match listener.accept() {
+                        Ok((mut stream, _)) => {
+                            let _ = stream.set_nonblocking(false);
+                            let _ = stream.set_write_timeout(Some(Duration::from_millis(200)));
+                            // Late joiners get the current state straight away.
+                            if let Some(line) = &*last.lock().unwrap() {
+                                let _ = stream.write_all(line.as_bytes());
+                            }
+                            clients.lock().unwrap().push(stream);
+                        }
+                        Err(_) => std::thread::sleep(Duration::from_millis(200)),
+                    }
+                }
+            });
+        }
+
+        Ok(Self {
+            mdns,
+            fullname,
+            clients,
+            last,
+            stop,
+        })
+    }
+
+    /// Sends the timer to all participants if it changed since the last call.
+    pub fn broadcast(&self, timer: &SharedTimer) {
+        let line = RoomMessage::Timer(timer.clone()).to_line();
+        let mut last = self.last.lock().unwrap();
+        if last.as_deref() == Some(line.as_str()) {
+            return;
+        }
+        self.clients
+            .lock()
+            .unwrap()
+            .retain_mut(|stream| stream.write_all(line.as_bytes()).is_ok());
+        *last = Some(line);
+    }
+
+    pub fn participant_count(&self) -> usize {
+        self.clients.lock().unwrap().len()
+    }
+}
+
+impl Drop for LanHost {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        let _ = self.mdns.unregister(&self.fullname);
+        let _ = self.mdns.shutdown();
+    }
+}
+
+/// Keeps track of the shared timers announced on the local network.
+pub struct LanBrowser {
+    mdns: ServiceDaemon,
+    events: mdns_sd::Receiver<ServiceEvent>,
+    /// Host names by the address to join them at.
+    pub hosts: BTreeMap<String, SocketAddr>,
+}
+
+impl LanBrowser {
+    pub fn start() -> io::Result<Self> {
+        let mdns = ServiceDaemon::new().map_err(other)?;
+        let events = mdns.browse(SERVICE_TYPE).map_err(other)?;
+        Ok(Self {
+            mdns,
+            events,
+            hosts: BTreeMap::new(),
+        })
+    }
+
+    pub fn poll(&mut self) {
+        let suffix = format!(".{SERVICE_TYPE}");
+        while let Ok(event) = self.events.try_recv() {
+            match event {
+                ServiceEvent::ServiceResolved(info) => {
+                    if let Some(ip) = info.get_addresses().iter().next() {
+                        let name = info.get_fullname().trim_end_matches(&suffix).to_owned();
+                        let addr = SocketAddr::new(IpAddr::V4(*ip), info.get_port());
+                        self.hosts.insert(name, addr);
+                    }
+                }
+                ServiceEvent::ServiceRemoved(_, fullname) => {
+                    self.hosts.remove(fullname.trim_end_matches(&suffix));
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+impl Drop for LanBrowser {
+    fn drop(&mut self) {
+        let _ = self.mdns.shutdown();
+    }
+}
@@ -1,4 +1,103 @@
+//! A tiny pomodoro app, and the pomodoro engine it runs on.
+//!
+//! The engine can be used without the app: [`Engine`] keeps a [`timer::Timer`] going
+//! through work phases and breaks as set up by [`settings::Settings`], and reports each
+//! change as an [`events::TimerEvent`] over a channel or to a callback. All of these
+//! save and load with serde.
+//!
+//! ```no_run
+//! let mut engine = epomo::Engine::default();
+//! engine.settings.profile_mut().interval_period = 50;
+//! engine.on_event(|event| println!("{event}"));
+//! engine.start();
+//! loop {
+//!     engine.tick();
+//!     std::thread::sleep(std::time::Duration::from_secs(1));
+//! }
+//! ```
+//!
+//! To show a timer in another egui app, see [`widget::EpomoWidget`]. The desktop app
+//! itself is behind the default `gui` feature; turn default features off to leave out
+//! its windowing, tray and notification dependencies.
+
 #![warn(clippy::all, rust_2018_idioms)]
 
+mod accent;
+#[cfg(feature = "gui")]
+mod activity;
+#[cfg(feature = "gui")]
 mod app;
+#[cfg(feature = "gui")]
+mod app_usage;
+#[cfg(feature = "gui")]
+mod autostart;
+#[cfg(feature = "gui")]
+mod backup;
+#[cfg(feature = "gui")]
+mod blocker;
+#[cfg(feature = "gui")]
+mod chart;
+mod cli;
+pub mod clock;
+mod engine;
+#[cfg(feature = "gui")]
+mod event_log;
+pub mod events;
+#[cfg(feature = "gui")]
+mod gcal;
+#[cfg(feature = "gui")]
+mod history;
+#[cfg(feature = "gui")]
+mod history_import;
+#[cfg(all(feature = "gui", target_os = "linux"))]
+mod idle_inhibit;
+mod json_rpc;
+#[cfg(feature = "gui")]
+mod lan;
+#[cfg(feature = "gui")]
+mod media_key;
+#[cfg(feature = "gui")]
+mod monitors;
+#[cfg(feature = "gui")]
+mod notion;
+#[cfg(feature = "gui")]
+mod ntfy;
+#[cfg(feature = "gui")]
+mod obsidian;
+#[cfg(feature = "gui")]
+mod paths;
+#[cfg(feature = "gui")]
+mod remote_sync;
+#[cfg(feature = "gui")]
+mod report;
+#[cfg(feature = "gui")]
+mod retry;
+#[cfg(feature = "gui")]
+mod room;
+#[cfg(feature = "gui")]
+mod screen_lock;
+pub mod settings;
+#[cfg(all(feature = "gui", feature = "sqlite"))]
+mod store;
+#[cfg(feature = "gui")]
+mod sync;
+#[cfg(feature = "gui")]
+mod tasks;
+#[cfg(feature = "gui")]
+mod theme;
+pub mod timer;
+#[cfg(feature = "gui")]
+mod tray;
+#[cfg(feature = "gui")]
+mod update_check;
+pub mod widget;
+#[cfg(feature = "gui")]
 pub use app::EpomoApp;
+pub use cli::{Args, USAGE};
+pub use clock::ClockFormat;
+pub use engine::Engine;
+pub use json_rpc::run_json_rpc;
+#[cfg(feature = "gui")]
+pub use paths::set_data_dir;
+pub use settings::ReportFormat;
+pub use timer::set_time_scale;
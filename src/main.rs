@@ -4,16 +4,65 @@
 // When compiling natively:
 #[cfg(not(target_arch = "wasm32"))]
 fn main() -> eframe::Result<()> {
-    // Log to stdout (if you run with `RUST_LOG=debug`).
-
     use egui::Vec2;
-    tracing_subscriber::fmt::init();
+    use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
+
+    let args = match epomo::Args::from_env() {
+        Ok(args) if !args.help => args,
+        Ok(_) => {
+            print!("{}", epomo::USAGE);
+            return Ok(());
+        }
+        Err(e) => {
+            eprint!("epomo: {e}\n\n{}", epomo::USAGE);
+            std::process::exit(2);
+        }
+    };
+
+    // Log to stdout (if you run with `RUST_LOG=debug`), and to a file if asked to. The
+    // Windows release build has no console, so the file is the only way to get logs
+    // there. In JSON-RPC mode stdout is for events only, so logs go to stderr.
+    let (file_layer, _guard) = match &args.log_file {
+        Some(path) => {
+            let dir = path.parent().filter(|d| !d.as_os_str().is_empty());
+            let name = path.file_name().unwrap_or_else(|| "epomo.log".as_ref());
+            let appender = tracing_appender::rolling::daily(dir.unwrap_or(".".as_ref()), name);
+            let (writer, guard) = tracing_appender::non_blocking(appender);
+            let layer = tracing_subscriber::fmt::layer()
+                .with_ansi(false)
+                .with_writer(writer);
+            (Some(layer), Some(guard))
+        }
+        None => (None, None),
+    };
+    tracing_subscriber::registry()
+        .with(EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info")))
+        .with((!args.json_rpc).then(tracing_subscriber::fmt::layer))
+        .with(
+            args.json_rpc
+                .then(|| tracing_subscriber::fmt::layer().with_writer(std::io::stderr)),
+        )
+        .with(file_layer)
+        .init();
+
+    epomo::set_time_scale(args.time_scale);
+    if let Some(dir) = args.data_dir {
+        epomo::set_data_dir(dir);
+    }
+    if args.json_rpc {
+        if let Err(e) = epomo::run_json_rpc() {
+            eprintln!("epomo: {e}");
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
 
     eframe::run_native(
         "epomo",
         eframe::NativeOptions {
-            initial_window_size: Some(Vec2::new(200.0, 225.0)),
-            resizable: false,
+            initial_window_size: Some(Vec2::new(240.0, 320.0)),
+            min_window_size: Some(Vec2::new(200.0, 240.0)),
+            transparent: true, // lets the break overlay dim the screen behind it
             ..Default::default()
         },
         Box::new(|cc| Box::new(epomo::EpomoApp::new(cc))),
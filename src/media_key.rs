@@ -0,0 +1,51 @@
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+
+use global_hotkey::{
+    hotkey::{Code, HotKey},
+    GlobalHotKeyEvent, GlobalHotKeyManager,
+};
+
+/// Listens for the keyboard's play/pause media key system-wide, so it works while
+/// another app has focus. Other apps don't see the key while this is registered.
+pub struct MediaKey {
+    manager: GlobalHotKeyManager,
+    hotkey: HotKey,
+    pressed: Arc<AtomicBool>,
+}
+
+impl MediaKey {
+    /// Must be called on the main thread. Wakes `ctx` up whenever the key is pressed.
+    pub fn register(ctx: &egui::Context) -> Result<Self, String> {
+        let manager = GlobalHotKeyManager::new().map_err(|e| e.to_string())?;
+        let hotkey = HotKey::new(None, Code::MediaPlayPause);
+        manager.register(hotkey).map_err(|e| e.to_string())?;
+        let pressed = Arc::new(AtomicBool::new(false));
+        let (flag, ctx, id) = (pressed.clone(), ctx.clone(), hotkey.id());
+        GlobalHotKeyEvent::set_event_handler(Some(move |event: GlobalHotKeyEvent| {
+            if event.id == id {
+                flag.store(true, Ordering::Relaxed);
+                ctx.request_repaint();
+            }
+        }));
+        Ok(Self {
+            manager,
+            hotkey,
+            pressed,
+        })
+    }
+
+    /// Whether the key was pressed since the last call.
+    pub fn take_press(&self) -> bool {
+        self.pressed.swap(false, Ordering::Relaxed)
+    }
+}
+
+impl Drop for MediaKey {
+    fn drop(&mut self) {
+        GlobalHotKeyEvent::set_event_handler(None::<fn(GlobalHotKeyEvent)>);
+        let _ = self.manager.unregister(self.hotkey);
+    }
+}
@@ -0,0 +1,71 @@
+use egui::{Pos2, Rect, Vec2};
+
+/// One monitor's place on the desktop, in physical pixels.
+pub struct Monitor {
+    pub rect: Rect,
+    pub primary: bool,
+}
+
+/// Every connected monitor. eframe only tells us about the one the window is on.
+pub fn all() -> Vec<Monitor> {
+    match display_info::DisplayInfo::all() {
+        Ok(displays) => displays
+            .into_iter()
+            .map(|d| Monitor {
+                rect: Rect::from_min_size(
+                    Pos2::new(d.x as f32, d.y as f32),
+                    Vec2::new(d.width as f32, d.height as f32),
+                ),
+                primary: d.is_primary,
+            })
+            .collect(),
+        Err(e) => {
+            tracing::warn!("could not list the monitors: {e}");
+            Vec::new()
+        }
+    }
+}
+
+/// The smallest rectangle covering all of `monitors`.
+pub fn desktop(monitors: &[Monitor]) -> Option<Rect> {
+    monitors.iter().map(|m| m.rect).reduce(|a, b| a.union(b))
+}
+
+/// `rect` in egui's points instead of pixels.
+pub fn in_points(rect: Rect, pixels_per_point: f32) -> Rect {
+    Rect::from_min_size(
+        (rect.min.to_vec2() / pixels_per_point).to_pos2(),
+        rect.size() / pixels_per_point,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn monitor(x: f32, y: f32, width: f32, height: f32) -> Monitor {
+        Monitor {
+            rect: Rect::from_min_size(Pos2::new(x, y), Vec2::new(width, height)),
+            primary: false,
+        }
+    }
+
+    #[test]
+    fn desktop_spans_monitors_left_of_and_above_the_primary() {
+        let monitors = [
+            monitor(0.0, 0.0, 1920.0, 1080.0),
+            monitor(-1280.0, -200.0, 1280.0, 1024.0),
+        ];
+        let span = desktop(&monitors).unwrap();
+        assert_eq!(span.min, Pos2::new(-1280.0, -200.0));
+        assert_eq!(span.max, Pos2::new(1920.0, 1080.0));
+        assert!(desktop(&[]).is_none());
+    }
+
+    #[test]
+    fn points_follow_the_scale() {
+        let rect = in_points(monitor(1920.0, 0.0, 2560.0, 1440.0).rect, 2.0);
+        assert_eq!(rect.min, Pos2::new(960.0, 0.0));
+        assert_eq!(rect.size(), Vec2::new(1280.0, 720.0));
+    }
+}
@@ -0,0 +1,77 @@
+use chrono::{DateTime, Utc};
+
+use crate::{
+    retry::{Failure, Worker},
+    timer::PomodoroMode,
+};
+
+const PAGES_URL: &str = "https://api.notion.com/v1/pages";
+const NOTION_VERSION: &str = "2022-06-28";
+
+/// The integration token and the database finished phases go into. The database needs
+/// these properties: Name (title), Mode (select), Minutes (number), Date (date), Tag
+/// (select) and Notes (text).
+#[derive(Clone, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct NotionConfig {
+    pub token: String,
+    pub database_id: String,
+}
+
+impl NotionConfig {
+    pub fn is_configured(&self) -> bool {
+        !self.token.trim().is_empty() && !self.database_id.trim().is_empty()
+    }
+}
+
+/// One finished phase, as a row of the database.
+pub struct Row {
+    pub mode: PomodoroMode,
+    pub started_at: DateTime<Utc>,
+    pub ended_at: DateTime<Utc>,
+    pub tag: Option<String>,
+    pub notes: Option<String>,
+}
+
+impl Row {
+    fn to_page(&self, database_id: &str) -> serde_json::Value {
+        let mode = self.mode.to_string();
+        let mut properties = serde_json::json!({
+            "Name": { "title": [{ "text": { "content": mode } }] },
+            "Mode": { "select": { "name": mode } },
+            "Minutes": { "number": (self.ended_at - self.started_at).num_minutes() },
+            "Date": { "date": {
+                "start": self.started_at.to_rfc3339(),
+                "end": self.ended_at.to_rfc3339(),
+            } },
+        });
+        if let Some(tag) = &self.tag {
+            // Select options can't contain commas.
+            properties["Tag"] = serde_json::json!({ "select": { "name": tag.replace(',', " ") } });
+        }
+        if let Some(notes) = &self.notes {
+            properties["Notes"] =
+                serde_json::json!({ "rich_text": [{ "text": { "content": notes } }] });
+        }
+        serde_json::json!({
+            "parent": { "database_id": database_id.trim() },
+            "properties": properties,
+        })
+    }
+}
+
+fn upload(config: &NotionConfig, row: &Row) -> Result<(), Failure> {
+    ureq::post(PAGES_URL)
+        .set("Authorization", &format!("Bearer {}", config.token.trim()))
+        .set("Notion-Version", NOTION_VERSION)
+        .send_json(row.to_page(&config.database_id))
+        .map(drop)
+        .map_err(|e| Failure::from_ureq("Notion", e))
+}
+
+/// Adds finished phases to a Notion database, retrying while Notion can't be reached.
+pub type NotionUploader = Worker<NotionConfig, Row>;
+
+pub fn uploader(config: NotionConfig) -> NotionUploader {
+    Worker::start("Notion upload", config, upload)
+}
@@ -0,0 +1,59 @@
+use crate::{
+    retry::{Failure, Worker},
+    timer::PomodoroMode,
+};
+
+/// Where phase changes are pushed to: an ntfy topic such as `https://ntfy.sh/my-topic`,
+/// or any other URL that takes a plain-text POST.
+#[derive(Clone, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct NtfyConfig {
+    pub url: String,
+    /// An access token, for protected topics.
+    pub token: String,
+    /// Only push when a work phase starts, not when a break does.
+    pub work_only: bool,
+}
+
+impl NtfyConfig {
+    pub fn is_configured(&self) -> bool {
+        !self.url.trim().is_empty()
+    }
+}
+
+/// One phase change, as a push notification.
+pub struct Message {
+    pub next: PomodoroMode,
+    pub body: String,
+}
+
+fn send(config: &NtfyConfig, message: &Message) -> Result<(), Failure> {
+    if config.work_only && message.next != PomodoroMode::Work {
+        return Ok(());
+    }
+    let (title, tags) = match message.next {
+        PomodoroMode::Work => ("Back to work", "tomato"),
+        PomodoroMode::ShortBreak | PomodoroMode::LongBreak => ("Break time", "coffee"),
+    };
+    let mut request = ureq::post(config.url.trim())
+        .set("Title", &format!("epomo: {title}"))
+        .set("Tags", tags);
+    // Work starting is what the phone should make noise for.
+    if message.next == PomodoroMode::Work {
+        request = request.set("Priority", "high");
+    }
+    if !config.token.trim().is_empty() {
+        request = request.set("Authorization", &format!("Bearer {}", config.token.trim()));
+    }
+    request
+        .send_string(&message.body)
+        .map(drop)
+        .map_err(|e| Failure::from_ureq("ntfy", e))
+}
+
+/// Pushes phase changes to a phone, retrying while the server can't be reached.
+pub type NtfyPusher = Worker<NtfyConfig, Message>;
+
+pub fn pusher(config: NtfyConfig) -> NtfyPusher {
+    Worker::start("ntfy push", config, send)
+}
@@ -0,0 +1,63 @@
+use std::{
+    fs::OpenOptions,
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+use chrono::format::{Item, StrftimeItems};
+
+use crate::{
+    clock::{format_clock, ClockFormat},
+    history::Session,
+};
+
+/// The daily note for the day `session` started on, within `vault`. `template` is a
+/// path relative to the vault with chrono date placeholders, e.g. `Daily/%Y-%m-%d.md`.
+fn note_path(vault: &Path, template: &str, session: &Session) -> io::Result<PathBuf> {
+    // Formatting a bad placeholder panics, so they're caught here first.
+    if StrftimeItems::new(template).any(|item| matches!(item, Item::Error)) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{template:?} isn't a valid note name"),
+        ));
+    }
+    Ok(vault.join(session.local_start().format(template).to_string()))
+}
+
+/// Appends `session` to its daily note as a bullet like
+/// `- 09:00–09:25 🍅 #thesis Write the intro`, making the note if it doesn't exist yet.
+/// Returns the note's path.
+pub fn append_session(
+    vault: &Path,
+    template: &str,
+    session: &Session,
+    task: Option<&str>,
+    clock: ClockFormat,
+) -> io::Result<PathBuf> {
+    let path = note_path(vault, template, session)?;
+    let mut line = format!(
+        "- {}–{} 🍅",
+        format_clock(&session.local_start(), clock),
+        format_clock(&session.local_end(), clock)
+    );
+    if let Some(tag) = &session.tag {
+        // Obsidian tags can't have spaces.
+        line.push_str(&format!(" #{}", tag.replace(' ', "-")));
+    }
+    if let Some(task) = task {
+        line.push_str(&format!(" {task}"));
+    }
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    // Start on a line of its own if the note doesn't end with a newline.
+    let needs_newline = std::fs::read(&path)
+        .map(|contents| contents.last().map_or(false, |&b| b != b'\n'))
+        .unwrap_or(false);
+    let mut file = OpenOptions::new().create(true).append(true).open(&path)?;
+    if needs_newline {
+        writeln!(file)?;
+    }
+    writeln!(file, "{line}")?;
+    Ok(path)
+}
@@ -0,0 +1,217 @@
+use std::{
+    io,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+/// A file with this name next to the executable turns on portable mode.
+const PORTABLE_MARKER: &str = "epomo.portable";
+/// The app state, when it's kept in a custom data directory rather than by eframe.
+const STATE_FILE: &str = "state.json";
+
+static DATA_DIR_OVERRIDE: Mutex<Option<PathBuf>> = Mutex::new(None);
+
+/// Keeps all of epomo's files in `dir` instead of the per-user data directory.
+pub fn set_data_dir(dir: PathBuf) {
+    if let Ok(mut data_dir) = DATA_DIR_OVERRIDE.lock() {
+        *data_dir = Some(dir);
+    }
+}
+
+/// The directory given with `--data-dir`, or in portable mode a `data` directory next to
+/// the executable. `None` when epomo uses the usual per-user directory.
+pub fn custom_data_dir() -> Option<PathBuf> {
+    if let Some(dir) = DATA_DIR_OVERRIDE.lock().ok().and_then(|dir| dir.clone()) {
+        return Some(dir);
+    }
+    let exe = std::env::current_exe().ok()?;
+    let exe_dir = exe.parent()?;
+    exe_dir
+        .join(PORTABLE_MARKER)
+        .exists()
+        .then(|| exe_dir.join("data"))
+}
+
+/// Where epomo keeps its files. Unless there's a custom data directory, this is the
+/// same directory eframe saves the app state in.
+pub fn data_dir() -> Option<PathBuf> {
+    custom_data_dir().or_else(|| {
+        directories_next::ProjectDirs::from("", "", "epomo").map(|dirs| dirs.data_dir().to_owned())
+    })
+}
+
+/// Where settings meant to be edited by hand, like the theme, are looked for. With a
+/// custom data directory, that's where they are too.
+pub fn config_dir() -> Option<PathBuf> {
+    custom_data_dir().or_else(|| {
+        directories_next::ProjectDirs::from("", "", "epomo")
+            .map(|dirs| dirs.config_dir().to_owned())
+    })
+}
+
+/// Where the app state goes when the data directory can't be written to, e.g. because
+/// it's read-only or the disk is full. It's in the user's own cache directory, so other
+/// users can neither read it nor leave a state there for epomo to pick up.
+pub fn fallback_dir() -> Option<PathBuf> {
+    directories_next::ProjectDirs::from("", "", "epomo").map(|dirs| dirs.cache_dir().to_owned())
+}
+
+/// Creates `dir` if needed, readable only by the user on Unix.
+fn create_private_dir(dir: &Path) -> io::Result<()> {
+    let mut builder = std::fs::DirBuilder::new();
+    builder.recursive(true);
+    #[cfg(unix)]
+    std::os::unix::fs::DirBuilderExt::mode(&mut builder, 0o700);
+    builder.create(dir)
+}
+
+/// Whether a file can be created in `dir`.
+fn is_writable(dir: &Path) -> bool {
+    tempfile::NamedTempFile::new_in(dir).is_ok()
+}
+
+fn read_state<T: serde::de::DeserializeOwned>(path: &Path) -> Option<T> {
+    let contents = std::fs::read_to_string(path).ok()?;
+    match serde_json::from_str(&contents) {
+        Ok(state) => Some(state),
+        Err(e) => {
+            tracing::warn!("could not read the saved state in {}: {e}", path.display());
+            None
+        }
+    }
+}
+
+/// Reads the app state saved by [`save_state`], if there is one. The fallback directory
+/// is only looked at while the data directory can't be written to or has no readable
+/// state, which is when [`save_state`] would have used it. The `secret_fields` it saved
+/// there without are taken from the data directory's state.
+pub fn load_state(secret_fields: &[&str]) -> Option<serde_json::Value> {
+    let fallback = || fallback_dir().and_then(|dir| read_state(&dir.join(STATE_FILE)));
+    match data_dir() {
+        Some(dir) => {
+            let state = read_state(&dir.join(STATE_FILE));
+            if state.is_some() && is_writable(&dir) {
+                state
+            } else {
+                with_secrets(fallback(), state, secret_fields)
+            }
+        }
+        None => fallback(),
+    }
+}
+
+/// `fallback` with the `secret_fields` put back from `state`, so they aren't lost the
+/// next time it's saved to the data directory. Without a `fallback`, just `state`.
+fn with_secrets(
+    fallback: Option<serde_json::Value>,
+    state: Option<serde_json::Value>,
+    secret_fields: &[&str],
+) -> Option<serde_json::Value> {
+    let Some(mut fallback) = fallback else {
+        return state;
+    };
+    let secrets = state.as_ref().and_then(|state| state.as_object());
+    if let (Some(fields), Some(secrets)) = (fallback.as_object_mut(), secrets) {
+        for field in secret_fields {
+            if let Some(secret) = secrets.get(*field) {
+                fields.insert((*field).to_owned(), secret.clone());
+            }
+        }
+    }
+    Some(fallback)
+}
+
+/// Takes the top-level `secret_fields` out of `state`.
+fn strip_secrets(state: &mut serde_json::Value, secret_fields: &[&str]) {
+    if let Some(fields) = state.as_object_mut() {
+        for field in secret_fields {
+            fields.remove(*field);
+        }
+    }
+}
+
+/// Saves `state` in `dir`, replacing the previous one only once the new one is fully
+/// written. The file is only readable by the user.
+fn write_state(dir: &Path, state: &serde_json::Value) -> io::Result<()> {
+    create_private_dir(dir)?;
+    let mut file = tempfile::NamedTempFile::new_in(dir)?;
+    serde_json::to_writer(&mut file, state)?;
+    file.as_file().sync_all()?;
+    file.persist(dir.join(STATE_FILE)).map_err(|e| e.error)?;
+    Ok(())
+}
+
+/// Where [`save_state`] managed to save.
+pub enum Saved {
+    DataDir,
+    /// The data directory couldn't be written to because of `error`, so the state went
+    /// to `dir` instead.
+    Fallback {
+        dir: PathBuf,
+        error: io::Error,
+    },
+}
+
+/// Saves the app state in the data directory, or if that fails, in the fallback
+/// directory without the top-level `secret_fields`, so tokens and passphrases only ever
+/// end up in the data directory. Fails only when neither can be written to.
+pub fn save_state<T: serde::Serialize>(state: &T, secret_fields: &[&str]) -> io::Result<Saved> {
+    let mut state = serde_json::to_value(state)?;
+    let error = match data_dir() {
+        Some(dir) => match write_state(&dir, &state) {
+            Ok(()) => {
+                // Anything saved there while this was failing is out of date now.
+                if let Some(fallback) = fallback_dir() {
+                    let _ = std::fs::remove_file(fallback.join(STATE_FILE));
+                }
+                return Ok(Saved::DataDir);
+            }
+            Err(e) => {
+                tracing::warn!("could not save to {}: {e}", dir.display());
+                e
+            }
+        },
+        None => io::Error::new(io::ErrorKind::NotFound, "there's no data directory"),
+    };
+    let Some(dir) = fallback_dir() else {
+        return Err(error);
+    };
+    strip_secrets(&mut state, secret_fields);
+    write_state(&dir, &state)?;
+    Ok(Saved::Fallback { dir, error })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn secrets_survive_a_trip_through_the_fallback() {
+        let (data, fallback) = (tempfile::tempdir().unwrap(), tempfile::tempdir().unwrap());
+        let secrets = ["remote"];
+        let state = serde_json::json!({ "remote": { "passphrase": "hunter2" }, "tag": "old" });
+        write_state(data.path(), &state).unwrap();
+
+        let mut newer = serde_json::json!({ "remote": { "passphrase": "hunter2" }, "tag": "new" });
+        strip_secrets(&mut newer, &secrets);
+        write_state(fallback.path(), &newer).unwrap();
+        assert!(
+            read_state::<serde_json::Value>(&fallback.path().join(STATE_FILE))
+                .unwrap()
+                .get("remote")
+                .is_none()
+        );
+
+        let loaded = with_secrets(
+            read_state(&fallback.path().join(STATE_FILE)),
+            read_state(&data.path().join(STATE_FILE)),
+            &secrets,
+        )
+        .unwrap();
+        write_state(data.path(), &loaded).unwrap();
+        assert_eq!(
+            read_state::<serde_json::Value>(&data.path().join(STATE_FILE)).unwrap(),
+            serde_json::json!({ "remote": { "passphrase": "hunter2" }, "tag": "new" })
+        );
+    }
+}
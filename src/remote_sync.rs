@@ -0,0 +1,210 @@
+use std::{
+    io::{self, Read},
+    sync::mpsc,
+};
+
+use base64::Engine as _;
+use chacha20poly1305::{
+    aead::{rand_core::RngCore, Aead, AeadCore, KeyInit, OsRng},
+    XChaCha20Poly1305, XNonce,
+};
+use chrono::{DateTime, Utc};
+
+use crate::{history::History, settings::Settings};
+
+const MAGIC: &[u8] = b"EPOMO1";
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 24;
+/// How many times an upload is retried when another device wrote in between.
+const MAX_ATTEMPTS: usize = 3;
+
+/// Where and how to reach the WebDAV folder holding the encrypted sync file.
+#[derive(Clone, Default, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct RemoteConfig {
+    pub url: String,
+    pub username: String,
+    pub password: String,
+    /// Never leaves this machine; the server only ever sees ciphertext.
+    pub passphrase: String,
+}
+
+impl RemoteConfig {
+    pub fn is_configured(&self) -> bool {
+        !self.url.is_empty() && !self.passphrase.is_empty()
+    }
+
+    fn file_url(&self) -> String {
+        format!("{}/epomo.bin", self.url.trim_end_matches('/'))
+    }
+
+    fn authorize(&self, request: ureq::Request) -> ureq::Request {
+        if self.username.is_empty() {
+            return request;
+        }
+        let credentials = base64::engine::general_purpose::STANDARD
+            .encode(format!("{}:{}", self.username, self.password));
+        request.set("Authorization", &format!("Basic {credentials}"))
+    }
+}
+
+#[derive(Default, serde::Deserialize, serde::Serialize)]
+struct Payload {
+    history: History,
+    settings: Option<(DateTime<Utc>, Settings)>,
+}
+
+/// The reconciled state after a round trip with the server.
+pub struct Outcome {
+    pub history: History,
+    pub settings: Option<(DateTime<Utc>, Settings)>,
+    pub dropped_overlaps: usize,
+}
+
+fn other(e: impl std::error::Error + Send + Sync + 'static) -> io::Error {
+    io::Error::new(io::ErrorKind::Other, e)
+}
+
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+fn derive_key(passphrase: &str, salt: &[u8]) -> io::Result<chacha20poly1305::Key> {
+    let mut key = chacha20poly1305::Key::default();
+    argon2::Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|_| invalid("could not derive a key from the passphrase"))?;
+    Ok(key)
+}
+
+fn encrypt(passphrase: &str, plaintext: &[u8]) -> io::Result<Vec<u8>> {
+    let mut salt = [0; SALT_LEN];
+    OsRng.fill_bytes(&mut salt);
+    let cipher = XChaCha20Poly1305::new(&derive_key(passphrase, &salt)?);
+    let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let ciphertext = cipher
+        .encrypt(&nonce, plaintext)
+        .map_err(|_| invalid("encryption failed"))?;
+    Ok([MAGIC, &salt[..], nonce.as_slice(), &ciphertext[..]].concat())
+}
+
+fn decrypt(passphrase: &str, blob: &[u8]) -> io::Result<Vec<u8>> {
+    let header = MAGIC.len() + SALT_LEN + NONCE_LEN;
+    if blob.len() < header || !blob.starts_with(MAGIC) {
+        return Err(invalid("the sync file is not an epomo sync file"));
+    }
+    let salt = &blob[MAGIC.len()..MAGIC.len() + SALT_LEN];
+    let nonce = XNonce::from_slice(&blob[MAGIC.len() + SALT_LEN..header]);
+    XChaCha20Poly1305::new(&derive_key(passphrase, salt)?)
+        .decrypt(nonce, &blob[header..])
+        .map_err(|_| invalid("wrong passphrase or corrupted sync file"))
+}
+
+/// Downloads the current sync file along with its ETag, if there is one yet.
+fn fetch(config: &RemoteConfig) -> io::Result<Option<(Payload, Option<String>)>> {
+    let response = match config.authorize(ureq::get(&config.file_url())).call() {
+        Ok(response) => response,
+        Err(ureq::Error::Status(404, _)) => return Ok(None),
+        Err(e) => return Err(other(e)),
+    };
+    let etag = response.header("ETag").map(str::to_owned);
+    let mut blob = Vec::new();
+    response.into_reader().read_to_end(&mut blob)?;
+    let payload = serde_json::from_slice(&decrypt(&config.passphrase, &blob)?)?;
+    Ok(Some((payload, etag)))
+}
+
+/// Uploads the payload unless someone else replaced the file since we fetched it.
+/// Returns `Ok(false)` on such a conflict.
+fn upload(config: &RemoteConfig, payload: &Payload, etag: Option<&str>) -> io::Result<bool> {
+    let blob = encrypt(&config.passphrase, &serde_json::to_vec(payload)?)?;
+    let request = config.authorize(ureq::put(&config.file_url()));
+    let request = match etag {
+        Some(etag) => request.set("If-Match", etag),
+        None => request.set("If-None-Match", "*"),
+    };
+    match request.send_bytes(&blob) {
+        Ok(_) => Ok(true),
+        Err(ureq::Error::Status(412, _)) => Ok(false),
+        Err(e) => Err(other(e)),
+    }
+}
+
+fn run(
+    config: &RemoteConfig,
+    local: History,
+    settings: (DateTime<Utc>, Settings),
+) -> io::Result<Outcome> {
+    for _ in 0..MAX_ATTEMPTS {
+        let (remote, etag) = match fetch(config)? {
+            Some((payload, etag)) => (payload, etag),
+            None => (Payload::default(), None),
+        };
+
+        let mut history = local.clone();
+        history.merge(remote.history.sessions);
+        history.merge_abandoned(remote.history.abandoned);
+        let dropped_overlaps = history.resolve_overlaps();
+        let settings = match remote.settings {
+            Some(remote) if remote.0 > settings.0 => remote,
+            _ => settings.clone(),
+        };
+
+        let payload = Payload {
+            history,
+            settings: Some(settings),
+        };
+        if upload(config, &payload, etag.as_deref())? {
+            return Ok(Outcome {
+                history: payload.history,
+                settings: payload.settings,
+                dropped_overlaps,
+            });
+        }
+    }
+    Err(io::Error::new(
+        io::ErrorKind::WouldBlock,
+        "the sync file kept changing, try again later",
+    ))
+}
+
+/// Reconciles with the server on a worker thread.
+///
+/// `settings` carries the time they were last changed so the newest edit wins.
+pub fn spawn(
+    config: RemoteConfig,
+    history: History,
+    settings: (DateTime<Utc>, Settings),
+) -> mpsc::Receiver<io::Result<Outcome>> {
+    let (tx, rx) = mpsc::channel();
+    std::thread::spawn(move || {
+        let _ = tx.send(run(&config, history, settings));
+    });
+    rx
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_the_right_passphrase_opens_a_sync_file() {
+        let blob = encrypt("correct horse", b"{\"sessions\":[]}").unwrap();
+        assert!(blob.starts_with(MAGIC));
+        assert_eq!(
+            decrypt("correct horse", &blob).unwrap(),
+            b"{\"sessions\":[]}"
+        );
+        assert!(decrypt("battery staple", &blob).is_err());
+        // A fresh salt and nonce each time, so the same data never looks the same.
+        assert_ne!(
+            encrypt("correct horse", b"{\"sessions\":[]}").unwrap(),
+            blob
+        );
+
+        let mut tampered = blob;
+        *tampered.last_mut().unwrap() ^= 1;
+        assert!(decrypt("correct horse", &tampered).is_err());
+        assert!(decrypt("correct horse", b"EPOMO1").is_err());
+    }
+}
@@ -0,0 +1,173 @@
+use std::{
+    fmt::Write as _,
+    io,
+    path::{Path, PathBuf},
+};
+
+use chrono::NaiveDate;
+
+use crate::{
+    clock::{format_clock, ClockFormat},
+    history::History,
+    settings::ReportFormat,
+    tasks::TaskList,
+};
+
+#[derive(serde::Serialize)]
+struct ReportSession {
+    start: String,
+    end: String,
+    /// Times in the user's clock format, for the Markdown version.
+    #[serde(skip)]
+    start_clock: String,
+    #[serde(skip)]
+    end_clock: String,
+    minutes: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tag: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    app: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    energy: Option<u8>,
+}
+
+#[derive(serde::Serialize)]
+struct ReportAbandoned {
+    start: String,
+    #[serde(skip)]
+    start_clock: String,
+    minutes: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reason: Option<String>,
+}
+
+#[derive(serde::Serialize)]
+struct ReportTask {
+    title: String,
+    completed: u32,
+    estimate: u32,
+    done: bool,
+}
+
+/// A summary of one day's work.
+#[derive(serde::Serialize)]
+pub struct DailyReport {
+    date: NaiveDate,
+    focus_minutes: i64,
+    sessions: Vec<ReportSession>,
+    /// Work sessions stopped before they were over.
+    abandoned: Vec<ReportAbandoned>,
+    /// The plan as it stands when the report is written.
+    tasks: Vec<ReportTask>,
+}
+
+impl DailyReport {
+    pub fn new(date: NaiveDate, history: &History, tasks: &TaskList, clock: ClockFormat) -> Self {
+        let sessions: Vec<ReportSession> = history
+            .sessions
+            .iter()
+            .filter(|s| s.local_start().date_naive() == date)
+            .map(|s| ReportSession {
+                start: s.local_start().format("%H:%M").to_string(),
+                end: s.local_end().format("%H:%M").to_string(),
+                start_clock: format_clock(&s.local_start(), clock),
+                end_clock: format_clock(&s.local_end(), clock),
+                minutes: s.duration().num_minutes(),
+                tag: s.tag.clone(),
+                app: s.app.clone(),
+                energy: s.energy,
+            })
+            .collect();
+        let abandoned = history
+            .abandoned
+            .iter()
+            .map(|a| (a, a.local_start()))
+            .filter(|(_, start)| start.date_naive() == date)
+            .map(|(a, start)| ReportAbandoned {
+                start: start.format("%H:%M").to_string(),
+                start_clock: format_clock(&start, clock),
+                minutes: a.duration().num_minutes(),
+                reason: a.reason.clone(),
+            })
+            .collect();
+        Self {
+            date,
+            focus_minutes: sessions.iter().map(|s| s.minutes).sum(),
+            sessions,
+            abandoned,
+            tasks: tasks
+                .tasks
+                .iter()
+                .map(|t| ReportTask {
+                    title: t.title.clone(),
+                    completed: t.completed,
+                    estimate: t.estimate,
+                    done: t.done,
+                })
+                .collect(),
+        }
+    }
+
+    fn to_markdown(&self) -> String {
+        let mut out = format!("# epomo report for {}\n\n", self.date.format("%A %Y-%m-%d"));
+        let _ = writeln!(
+            out,
+            "{} sessions, {}h {}m of focus\n",
+            self.sessions.len(),
+            self.focus_minutes / 60,
+            self.focus_minutes % 60
+        );
+        if !self.sessions.is_empty() {
+            out.push_str("## Sessions\n\n");
+            for s in &self.sessions {
+                let mut details = format!("{} min", s.minutes);
+                if let Some(tag) = &s.tag {
+                    let _ = write!(details, ", #{tag}");
+                }
+                if let Some(app) = &s.app {
+                    let _ = write!(details, ", mostly {app}");
+                }
+                if let Some(energy) = s.energy {
+                    let _ = write!(details, ", energy {energy}/5");
+                }
+                let _ = writeln!(out, "- {}–{} ({details})", s.start_clock, s.end_clock);
+            }
+            out.push('\n');
+        }
+        if !self.abandoned.is_empty() {
+            out.push_str("## Stopped early\n\n");
+            for a in &self.abandoned {
+                let _ = write!(out, "- {} ({} min", a.start_clock, a.minutes);
+                if let Some(reason) = &a.reason {
+                    let _ = write!(out, ", {reason}");
+                }
+                out.push_str(")\n");
+            }
+            out.push('\n');
+        }
+        if !self.tasks.is_empty() {
+            out.push_str("## Plan\n\n");
+            for t in &self.tasks {
+                let check = if t.done { 'x' } else { ' ' };
+                let _ = writeln!(
+                    out,
+                    "- [{check}] {} ({}/{})",
+                    t.title, t.completed, t.estimate
+                );
+            }
+        }
+        out
+    }
+
+    /// Writes the report into `dir`, named after its date. Returns the file's path.
+    pub fn write(&self, dir: &Path, format: ReportFormat) -> io::Result<PathBuf> {
+        let (ext, contents) = match format {
+            ReportFormat::Markdown => ("md", self.to_markdown()),
+            ReportFormat::Json => ("json", serde_json::to_string_pretty(self)?),
+        };
+        std::fs::create_dir_all(dir)?;
+        let path = dir.join(format!("epomo-{}.{ext}", self.date.format("%Y-%m-%d")));
+        std::fs::write(&path, contents)?;
+        Ok(path)
+    }
+}
@@ -0,0 +1,149 @@
+use std::{collections::VecDeque, sync::mpsc, time::Duration};
+
+/// The first wait before trying again. It doubles on each failure in a row.
+const FIRST_RETRY: Duration = Duration::from_secs(5);
+const MAX_RETRY: Duration = Duration::from_secs(10 * 60);
+
+pub enum Failure {
+    /// Worth trying again later: the network, rate limits or the service itself.
+    Transient(String),
+    /// Trying again won't help, e.g. a bad token.
+    Permanent(String),
+}
+
+impl Failure {
+    /// Sorts a failed request by whether it's worth retrying.
+    pub fn from_ureq(service: &str, e: ureq::Error) -> Self {
+        match e {
+            ureq::Error::Status(code, response) => {
+                let message = format!(
+                    "{service} said {code}: {}",
+                    response.into_string().unwrap_or_default()
+                );
+                if code == 429 || code >= 500 {
+                    Failure::Transient(message)
+                } else {
+                    Failure::Permanent(message)
+                }
+            }
+            e => Failure::Transient(e.to_string()),
+        }
+    }
+}
+
+/// Hands each job sent to the returned channel to `send` on a background thread, in
+/// order, retrying with growing waits while it fails in ways worth retrying. Problems
+/// come back on the other channel for showing to the user. The thread ends once the
+/// sender is dropped; jobs still waiting then are lost.
+pub fn spawn<T: Send + 'static>(
+    name: &'static str,
+    mut send: impl FnMut(&T) -> Result<(), Failure> + Send + 'static,
+) -> (mpsc::Sender<T>, mpsc::Receiver<String>) {
+    let (jobs, received) = mpsc::channel::<T>();
+    let (report, errors) = mpsc::channel();
+    std::thread::spawn(move || {
+        let mut queue = VecDeque::new();
+        let mut wait = FIRST_RETRY;
+        loop {
+            if queue.is_empty() {
+                match received.recv() {
+                    Ok(job) => queue.push_back(job),
+                    Err(_) => return,
+                }
+            }
+            queue.extend(received.try_iter());
+            let Some(job) = queue.front() else {
+                continue;
+            };
+            match send(job) {
+                Ok(()) => {
+                    queue.pop_front();
+                    wait = FIRST_RETRY;
+                }
+                Err(Failure::Permanent(e)) => {
+                    queue.pop_front();
+                    let _ = report.send(e);
+                }
+                Err(Failure::Transient(e)) => {
+                    tracing::info!("{name} failed, retrying in {wait:?}: {e}");
+                    if wait == FIRST_RETRY {
+                        let _ = report.send(format!("{e}. Retrying in the background."));
+                    }
+                    match received.recv_timeout(wait) {
+                        Ok(job) => queue.push_back(job),
+                        Err(mpsc::RecvTimeoutError::Timeout) => {}
+                        Err(mpsc::RecvTimeoutError::Disconnected) => {
+                            tracing::warn!("{name}: gave up on {} waiting jobs", queue.len());
+                            return;
+                        }
+                    }
+                    wait = (wait * 2).min(MAX_RETRY);
+                }
+            }
+        }
+    });
+    (jobs, errors)
+}
+
+/// A [`spawn`]ed thread and the configuration its jobs are sent with, so it can be
+/// started over when that changes.
+pub struct Worker<C, T> {
+    config: C,
+    jobs: mpsc::Sender<T>,
+    errors: mpsc::Receiver<String>,
+}
+
+impl<C: Clone + Send + 'static, T: Send + 'static> Worker<C, T> {
+    /// Starts handing each pushed job to `send` along with `config`.
+    pub fn start(
+        name: &'static str,
+        config: C,
+        mut send: impl FnMut(&C, &T) -> Result<(), Failure> + Send + 'static,
+    ) -> Self {
+        let sending = config.clone();
+        let (jobs, errors) = spawn(name, move |job| send(&sending, job));
+        Self {
+            config,
+            jobs,
+            errors,
+        }
+    }
+
+    /// What this was started with.
+    pub fn config(&self) -> &C {
+        &self.config
+    }
+
+    pub fn push(&self, job: T) {
+        let _ = self.jobs.send(job);
+    }
+
+    /// Problems since the last call, for showing to the user.
+    pub fn take_errors(&self) -> Vec<String> {
+        self.errors.try_iter().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn worker_sends_jobs_in_order_and_reports_what_failed() {
+        let (seen, sent) = mpsc::channel();
+        let worker = Worker::start("test", 10, move |offset: &i32, job: &i32| {
+            if *job < 0 {
+                return Err(Failure::Permanent(format!("bad job {job}")));
+            }
+            seen.send(offset + job).unwrap();
+            Ok(())
+        });
+        for job in [1, -1, 2] {
+            worker.push(job);
+        }
+        let received: Vec<i32> = sent.iter().take(2).collect();
+        assert_eq!(received, [11, 12]);
+        assert_eq!(worker.take_errors(), ["bad job -1"]);
+        assert_eq!(*worker.config(), 10);
+    }
+}
@@ -0,0 +1,210 @@
+use std::{
+    io::{self, BufRead, BufReader, Write},
+    net::{Shutdown, TcpStream, ToSocketAddrs},
+    sync::{mpsc, Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use chrono::{DateTime, Utc};
+
+use crate::timer::PomodoroMode;
+
+/// The part of a timer everyone in a shared session sees.
+#[derive(Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct SharedTimer {
+    pub mode: PomodoroMode,
+    pub ends_at: Option<DateTime<Utc>>,
+    pub session_count: usize,
+}
+
+/// The first line sent to a relay, saying which room to open or enter.
+#[derive(serde::Deserialize, serde::Serialize)]
+pub enum Hello {
+    Host { code: String, name: String },
+    Join { code: String, name: String },
+}
+
+/// Lines sent to the participants of a shared timer, by a LAN host or a relay.
+#[derive(serde::Deserialize, serde::Serialize)]
+pub enum RoomMessage {
+    Timer(SharedTimer),
+    Participants(Vec<String>),
+}
+
+impl RoomMessage {
+    pub fn to_line(&self) -> String {
+        let mut line = serde_json::to_string(self).unwrap_or_default();
+        line.push('\n');
+        line
+    }
+}
+
+/// How this machine shows up to others sharing a timer.
+pub fn display_name() -> String {
+    let user = std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .unwrap_or_else(|_| "epomo".to_owned());
+    format!("{user}'s timer")
+}
+
+/// How often an empty line is sent to a relay when there's nothing else to send, so it
+/// doesn't drop the connection as idle.
+const KEEPALIVE: Duration = Duration::from_secs(30);
+
+/// A short room code that is easy to read out loud.
+pub fn new_code() -> String {
+    const ALPHABET: &[u8] = b"ABCDEFGHJKLMNPQRSTUVWXYZ23456789";
+    (0..6)
+        .map(|_| ALPHABET[fastrand::usize(..ALPHABET.len())] as char)
+        .collect()
+}
+
+fn connect(addr: impl ToSocketAddrs, hello: Option<&Hello>) -> io::Result<TcpStream> {
+    let addr = addr
+        .to_socket_addrs()?
+        .next()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "could not resolve address"))?;
+    let mut stream = TcpStream::connect_timeout(&addr, Duration::from_secs(3))?;
+    stream.set_write_timeout(Some(Duration::from_secs(1)))?;
+    if let Some(hello) = hello {
+        let mut line = serde_json::to_string(hello)?;
+        line.push('\n');
+        stream.write_all(line.as_bytes())?;
+    }
+    Ok(stream)
+}
+
+/// Reads room messages on a worker thread, keeping the participant list up to date and
+/// forwarding timer updates.
+fn spawn_reader(
+    stream: &TcpStream,
+    participants: Arc<Mutex<Vec<String>>>,
+) -> io::Result<mpsc::Receiver<SharedTimer>> {
+    let reader = BufReader::new(stream.try_clone()?);
+    let (tx, rx) = mpsc::channel();
+    std::thread::spawn(move || {
+        for line in reader.lines() {
+            let Ok(line) = line else { break };
+            match serde_json::from_str(&line) {
+                Ok(RoomMessage::Timer(timer)) => {
+                    if tx.send(timer).is_err() {
+                        break;
+                    }
+                }
+                Ok(RoomMessage::Participants(names)) => *participants.lock().unwrap() = names,
+                Err(_) => {}
+            }
+        }
+    });
+    Ok(rx)
+}
+
+/// Hosts a room on a relay so people outside the local network can follow the timer.
+pub struct RoomHost {
+    pub code: String,
+    stream: TcpStream,
+    last: Option<String>,
+    last_sent: Instant,
+    participants: Arc<Mutex<Vec<String>>>,
+    // Only kept so the reader thread stays alive; hosts don't receive timers.
+    _updates: mpsc::Receiver<SharedTimer>,
+}
+
+impl RoomHost {
+    pub fn start(relay: &str, name: &str) -> io::Result<Self> {
+        let code = new_code();
+        let hello = Hello::Host {
+            code: code.clone(),
+            name: name.to_owned(),
+        };
+        let stream = connect(relay, Some(&hello))?;
+        let participants = Arc::default();
+        let updates = spawn_reader(&stream, Arc::clone(&participants))?;
+        Ok(Self {
+            code,
+            stream,
+            last: None,
+            last_sent: Instant::now(),
+            participants,
+            _updates: updates,
+        })
+    }
+
+    /// Sends the timer to the relay if it changed since the last call, or else a
+    /// keepalive once in a while.
+    pub fn broadcast(&mut self, timer: &SharedTimer) -> io::Result<()> {
+        let line = RoomMessage::Timer(timer.clone()).to_line();
+        if self.last.as_ref() != Some(&line) {
+            self.stream.write_all(line.as_bytes())?;
+            self.last = Some(line);
+            self.last_sent = Instant::now();
+        } else if self.last_sent.elapsed() >= KEEPALIVE {
+            self.stream.write_all(b"\n")?;
+            self.last_sent = Instant::now();
+        }
+        Ok(())
+    }
+
+    pub fn participants(&self) -> Vec<String> {
+        self.participants.lock().unwrap().clone()
+    }
+}
+
+impl Drop for RoomHost {
+    fn drop(&mut self) {
+        let _ = self.stream.shutdown(Shutdown::Both);
+    }
+}
+
+/// A connection to someone else's shared timer, directly or through a relay.
+pub struct Follower {
+    stream: TcpStream,
+    updates: mpsc::Receiver<SharedTimer>,
+    participants: Arc<Mutex<Vec<String>>>,
+}
+
+impl Follower {
+    pub fn connect(addr: impl ToSocketAddrs, hello: Option<&Hello>) -> io::Result<Self> {
+        let stream = connect(addr, hello)?;
+        let participants = Arc::default();
+        let updates = spawn_reader(&stream, Arc::clone(&participants))?;
+        // Through a relay, nothing else is ever sent, so keep the connection alive until
+        // it's shut down.
+        if hello.is_some() {
+            let mut writer = stream.try_clone()?;
+            std::thread::spawn(move || loop {
+                std::thread::sleep(KEEPALIVE);
+                if writer.write_all(b"\n").is_err() {
+                    break;
+                }
+            });
+        }
+        Ok(Self {
+            stream,
+            updates,
+            participants,
+        })
+    }
+
+    /// The newest state from the host, or `Err` once the host has gone away.
+    pub fn poll(&self) -> Result<Option<SharedTimer>, mpsc::TryRecvError> {
+        let mut latest = None;
+        loop {
+            match self.updates.try_recv() {
+                Ok(timer) => latest = Some(timer),
+                Err(mpsc::TryRecvError::Empty) => return Ok(latest),
+                Err(e) => return latest.map(Some).ok_or(e),
+            }
+        }
+    }
+
+    pub fn participants(&self) -> Vec<String> {
+        self.participants.lock().unwrap().clone()
+    }
+}
+
+impl Drop for Follower {
+    fn drop(&mut self) {
+        let _ = self.stream.shutdown(Shutdown::Both);
+    }
+}
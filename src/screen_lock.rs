@@ -0,0 +1,34 @@
+use std::{io, process::Command};
+
+/// Runs the first of `commands` that can be started.
+fn run_first(commands: &[(&str, &[&str])]) -> io::Result<()> {
+    let mut last_error = io::Error::new(io::ErrorKind::NotFound, "no way to lock the screen");
+    for (program, args) in commands {
+        match Command::new(program).args(*args).status() {
+            Ok(status) if status.success() => return Ok(()),
+            Ok(status) => {
+                last_error = io::Error::new(
+                    io::ErrorKind::Other,
+                    format!("{program} failed with {status}"),
+                )
+            }
+            Err(e) => last_error = e,
+        }
+    }
+    Err(last_error)
+}
+
+/// Locks the screen, or on macOS puts the display to sleep, which locks it when a
+/// password is required after sleep.
+pub fn lock() -> io::Result<()> {
+    if cfg!(target_os = "windows") {
+        run_first(&[("rundll32.exe", &["user32.dll,LockWorkStation"])])
+    } else if cfg!(target_os = "macos") {
+        run_first(&[("pmset", &["displaysleepnow"])])
+    } else {
+        run_first(&[
+            ("loginctl", &["lock-session"]),
+            ("xdg-screensaver", &["lock"]),
+        ])
+    }
+}
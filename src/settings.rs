@@ -0,0 +1,381 @@
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
+
+use egui::Color32;
+
+use crate::{clock::ClockFormat, timer::PomodoroMode};
+
+/// A named set of durations, colors and sounds, e.g. "Deep work" or "Study".
+#[derive(Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct Profile {
+    pub name: String,
+    pub interval_period: i64,
+    pub long_break_period: i64,
+    pub short_break_period: i64,
+    /// Work sessions in a cycle; the last one is followed by a long break.
+    pub sessions_per_long_break: usize,
+    pub work_color: [u8; 3],
+    pub short_break_color: [u8; 3],
+    pub long_break_color: [u8; 3],
+    /// Sound the phase change notifications play, by the name the OS knows it under,
+    /// e.g. `complete` on Linux or `Glass` on macOS. Empty keeps the system's own.
+    pub sound: String,
+}
+
+impl Default for Profile {
+    fn default() -> Self {
+        Self {
+            name: "Default".to_owned(),
+            interval_period: 25,
+            long_break_period: 15,
+            short_break_period: 5,
+            sessions_per_long_break: 4,
+            work_color: [58, 191, 240],
+            short_break_color: [240, 231, 58],
+            long_break_color: [240, 140, 58],
+            sound: String::new(),
+        }
+    }
+}
+
+impl Profile {
+    /// Length of a phase in minutes.
+    pub fn period(&self, mode: PomodoroMode) -> i64 {
+        match mode {
+            PomodoroMode::LongBreak => self.long_break_period,
+            PomodoroMode::ShortBreak => self.short_break_period,
+            PomodoroMode::Work => self.interval_period,
+        }
+    }
+
+    pub fn color(&self, mode: PomodoroMode) -> Color32 {
+        let [r, g, b] = match mode {
+            PomodoroMode::LongBreak => self.long_break_color,
+            PomodoroMode::ShortBreak => self.short_break_color,
+            PomodoroMode::Work => self.work_color,
+        };
+        Color32::from_rgb(r, g, b)
+    }
+}
+
+/// The text of each notification. `{mode}`, `{duration}`, `{sessions_today}` and
+/// `{task}` are filled in when one is shown, and `{label}` in the countdown one.
+#[derive(Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct NotifTemplates {
+    pub work: String,
+    pub short_break: String,
+    pub long_break: String,
+    pub countdown: String,
+}
+
+impl Default for NotifTemplates {
+    fn default() -> Self {
+        Self {
+            work: PomodoroMode::Work.get_notif_str().to_owned(),
+            short_break: PomodoroMode::ShortBreak.get_notif_str().to_owned(),
+            long_break: PomodoroMode::LongBreak.get_notif_str().to_owned(),
+            countdown: "{label} is done".to_owned(),
+        }
+    }
+}
+
+impl NotifTemplates {
+    /// The template for the start of `mode`.
+    pub fn for_mode(&self, mode: PomodoroMode) -> &str {
+        match mode {
+            PomodoroMode::LongBreak => &self.long_break,
+            PomodoroMode::ShortBreak => &self.short_break,
+            PomodoroMode::Work => &self.work,
+        }
+    }
+
+    /// Replaces each `{name}` in `template` with its value. Unknown names are left as
+    /// they are.
+    pub fn fill(template: &str, values: &[(&str, &str)]) -> String {
+        values
+            .iter()
+            .fold(template.to_owned(), |text, (name, value)| {
+                text.replace(&format!("{{{name}}}"), value)
+            })
+    }
+}
+
+/// Tags sessions by what they were spent in: each look at the foreground app whose
+/// name or window title contains `pattern`, ignoring case, counts towards `tag`.
+#[derive(Clone, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct TagRule {
+    pub pattern: String,
+    pub tag: String,
+}
+
+impl TagRule {
+    pub fn matches(&self, app: &str, title: &str) -> bool {
+        let pattern = self.pattern.trim().to_lowercase();
+        !pattern.is_empty()
+            && !self.tag.trim().is_empty()
+            && (app.to_lowercase().contains(&pattern) || title.to_lowercase().contains(&pattern))
+    }
+}
+
+/// Which screens the break overlay covers.
+#[derive(Clone, Copy, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+pub enum OverlayScreen {
+    /// The one the window is on.
+    #[default]
+    Current,
+    Primary,
+    /// Every monitor. A window can only be full screen on one, so the window is made as
+    /// big as the whole desktop instead.
+    All,
+}
+
+/// How the daily report is written.
+#[derive(Clone, Copy, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+pub enum ReportFormat {
+    #[default]
+    Markdown,
+    Json,
+}
+
+/// What skipping a long break does to the cycle.
+#[derive(Clone, Copy, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+pub enum LongBreakSkip {
+    /// The cycle is done anyway, and the next long break comes after the usual number
+    /// of work sessions.
+    #[default]
+    EndCycle,
+    /// The long break is owed, and comes after the next work session instead of a short
+    /// one.
+    CarryOver,
+}
+
+/// Everything the user can configure, kept apart from the running timer state so it
+/// can be exported to and imported from a TOML file as a whole.
+#[derive(Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)] // settings files from older versions may miss fields
+pub struct Settings {
+    pub profiles: Vec<Profile>,
+    pub active_profile: usize,
+    /// Profile name to switch to on each weekday, starting from Monday. Empty keeps
+    /// whatever profile is active.
+    pub weekday_profiles: [String; 7],
+    pub show_notifs: bool,
+    /// Look for a newer release on GitHub once a day.
+    pub check_updates: bool,
+    /// Start and pause the timer with the play/pause media key.
+    pub media_key: bool,
+    /// Show the minutes left in the system tray.
+    pub tray_icon: bool,
+    pub notif_templates: NotifTemplates,
+    pub long_break_skip: LongBreakSkip,
+    /// Make each work phase up to this many minutes longer or shorter, at random, so
+    /// they aren't all the same.
+    pub jitter_minutes: i64,
+    /// Seconds between one phase running out and the next one starting, up to a minute.
+    pub grace_secs: u32,
+    /// Most work sessions to run in a day, or 0 for no limit.
+    pub daily_max_sessions: u32,
+    /// Most minutes of focus in a day, or 0 for no limit.
+    pub daily_max_focus_minutes: i64,
+    /// Once the daily limit is reached, don't start work again when a break ends.
+    pub hold_at_daily_limit: bool,
+    /// Seconds between redraws while the window is in the background, or 0 to keep
+    /// ticking every second.
+    pub background_repaint_secs: u64,
+    /// Zoom on top of the display's own scale factor.
+    pub ui_scale: f32,
+    /// Tint the window background with the current mode's color.
+    pub tint_panel: bool,
+    /// No fades, confetti or other animation.
+    pub reduce_motion: bool,
+    /// Show the countdown in whole minutes, so there's less to watch.
+    pub hide_seconds: bool,
+    /// Use the OS accent color for work phases and highlights instead of the profile's
+    /// work color.
+    pub follow_accent: bool,
+    pub clock_format: ClockFormat,
+    pub dim_breaks: bool,
+    pub dim_opacity: f32,
+    pub dim_screen: OverlayScreen,
+    /// Let notifications and other windows come up over the overlay and take clicks.
+    pub dim_click_through: bool,
+    /// Lock the screen as a long break starts, after a short warning.
+    pub lock_on_long_break: bool,
+    pub block_sites: bool,
+    /// Keep the screen from blanking during work phases. Linux only.
+    pub inhibit_idle: bool,
+    pub blocked_sites: String,
+    /// Things to do on a break, one per line. Each break shows the next one.
+    pub break_suggestions: String,
+    /// `host:port` of the relay used for online shared timers.
+    pub relay_addr: String,
+    /// Folder to write a summary of each day into, if any.
+    pub report_dir: Option<PathBuf>,
+    pub report_format: ReportFormat,
+    /// Local hour from which the day's report is written. A day missed entirely gets
+    /// its report on the next launch.
+    pub report_hour: u32,
+    /// Obsidian vault to log each finished session into, if any.
+    pub obsidian_vault: Option<PathBuf>,
+    /// The daily note's path within the vault, with chrono date placeholders.
+    pub obsidian_note: String,
+    /// Note the foreground app during work sessions. Off unless the user opts in.
+    pub track_apps: bool,
+    /// Applied to the foreground app while it's tracked.
+    pub tag_rules: Vec<TagRule>,
+    /// Tag untagged sessions by the rules when they end, rather than only suggesting a
+    /// tag while they run.
+    pub auto_tag: bool,
+    /// Ask how each work session went, from 1 to 5, for the stats.
+    pub ask_energy: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            profiles: vec![Profile::default()],
+            active_profile: 0,
+            weekday_profiles: Default::default(),
+            show_notifs: true,
+            check_updates: false,
+            media_key: false,
+            tray_icon: false,
+            notif_templates: NotifTemplates::default(),
+            long_break_skip: LongBreakSkip::default(),
+            jitter_minutes: 0,
+            grace_secs: 0,
+            daily_max_sessions: 0,
+            daily_max_focus_minutes: 0,
+            hold_at_daily_limit: false,
+            ui_scale: 1.0,
+            background_repaint_secs: 0,
+            tint_panel: false,
+            reduce_motion: false,
+            hide_seconds: false,
+            follow_accent: false,
+            clock_format: ClockFormat::default(),
+            dim_breaks: false,
+            dim_opacity: 0.85,
+            dim_screen: OverlayScreen::default(),
+            dim_click_through: false,
+            lock_on_long_break: false,
+            block_sites: false,
+            inhibit_idle: false,
+            blocked_sites: String::new(),
+            break_suggestions: [
+                "Stretch your neck and shoulders",
+                "Refill your water",
+                "Look at something far away",
+                "Stand up and walk around",
+                "Take a few deep breaths",
+                "Open a window",
+            ]
+            .join("\n"),
+            relay_addr: String::new(),
+            report_dir: None,
+            report_format: ReportFormat::default(),
+            report_hour: 22,
+            obsidian_vault: None,
+            // Obsidian's own default: a note named after the date in the vault's root.
+            obsidian_note: "%Y-%m-%d.md".to_owned(),
+            track_apps: false,
+            tag_rules: Vec::new(),
+            auto_tag: false,
+            ask_energy: false,
+        }
+    }
+}
+
+impl Settings {
+    pub fn profile(&self) -> &Profile {
+        &self.profiles[self.active_profile.min(self.profiles.len() - 1)]
+    }
+
+    /// The color for `mode`, taking the OS accent color into account.
+    pub fn color(&self, mode: PomodoroMode) -> Color32 {
+        let accent = self
+            .follow_accent
+            .then(crate::accent::system_accent)
+            .flatten();
+        match accent {
+            Some([r, g, b]) if mode == PomodoroMode::Work => Color32::from_rgb(r, g, b),
+            _ => self.profile().color(mode),
+        }
+    }
+
+    pub fn profile_mut(&mut self) -> &mut Profile {
+        let i = self.active_profile.min(self.profiles.len() - 1);
+        &mut self.profiles[i]
+    }
+
+    /// Repairs hand-edited or truncated settings so there is always a profile to use.
+    pub fn ensure_profile(&mut self) {
+        if self.profiles.is_empty() {
+            self.profiles.push(Profile::default());
+        }
+        self.active_profile = self.active_profile.min(self.profiles.len() - 1);
+    }
+
+    /// Switches to the profile scheduled for `weekday`, if there is one.
+    pub fn apply_schedule(&mut self, weekday: chrono::Weekday) {
+        let name = &self.weekday_profiles[weekday.num_days_from_monday() as usize];
+        if let Some(i) = self
+            .profiles
+            .iter()
+            .position(|p| !name.is_empty() && p.name == *name)
+        {
+            self.active_profile = i;
+        }
+    }
+
+    /// The break suggestion for the `n`th break, going round the list.
+    pub fn break_suggestion(&self, n: usize) -> Option<&str> {
+        let suggestions: Vec<&str> = self
+            .break_suggestions
+            .lines()
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .collect();
+        suggestions.get(n % suggestions.len().max(1)).copied()
+    }
+
+    pub fn export(&self, path: &Path) -> io::Result<()> {
+        let contents = toml::to_string_pretty(self)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        std::fs::write(path, contents)
+    }
+
+    /// Settings from another device, merged field by field with these: fields changed
+    /// here since `base`, the settings last synced, stay as they are and the rest are
+    /// taken from `theirs`. Without a `base`, `theirs` wins outright.
+    pub fn merged(&self, base: Option<&Settings>, theirs: Settings) -> Settings {
+        let Some(base) = base else {
+            return theirs;
+        };
+        let to_value = |s: &Settings| serde_json::to_value(s).unwrap_or_default();
+        let (ours, base, mut merged) = (to_value(self), to_value(base), to_value(&theirs));
+        if let (Some(ours), Some(base), Some(merged)) =
+            (ours.as_object(), base.as_object(), merged.as_object_mut())
+        {
+            for (field, value) in ours {
+                if base.get(field) != Some(value) {
+                    merged.insert(field.clone(), value.clone());
+                }
+            }
+        }
+        serde_json::from_value(merged).unwrap_or(theirs)
+    }
+
+    pub fn import(path: &Path) -> io::Result<Self> {
+        let contents = std::fs::read_to_string(path)?;
+        let mut settings: Self =
+            toml::from_str(&contents).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        settings.ensure_profile();
+        Ok(settings)
+    }
+}
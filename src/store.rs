@@ -0,0 +1,327 @@
+use std::{
+    collections::{HashMap, HashSet},
+    path::Path,
+};
+
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection};
+
+use crate::{
+    history::{Abandoned, History, Session},
+    settings::Settings,
+    tasks::{Task, TaskList},
+};
+
+/// Schema changes, applied in order. `PRAGMA user_version` records how many have run.
+const MIGRATIONS: &[&str] = &[
+    "
+    CREATE TABLE sessions (
+        started_at TEXT NOT NULL,
+        ended_at TEXT NOT NULL,
+        UNIQUE (started_at, ended_at)
+    );
+    CREATE TABLE tasks (
+        position INTEGER PRIMARY KEY,
+        title TEXT NOT NULL,
+        estimate INTEGER NOT NULL,
+        completed INTEGER NOT NULL,
+        done INTEGER NOT NULL
+    );
+    CREATE TABLE settings (
+        id INTEGER PRIMARY KEY CHECK (id = 0),
+        json TEXT NOT NULL
+    );
+",
+    "ALTER TABLE sessions ADD COLUMN app TEXT;",
+    "ALTER TABLE sessions ADD COLUMN utc_offset INTEGER;",
+    "ALTER TABLE sessions ADD COLUMN tag TEXT;",
+    "ALTER TABLE sessions ADD COLUMN energy INTEGER;",
+    "
+    CREATE TABLE abandoned (
+        started_at TEXT NOT NULL UNIQUE,
+        ended_at TEXT NOT NULL,
+        tag TEXT,
+        reason TEXT
+    );
+",
+    "
+    ALTER TABLE sessions ADD COLUMN profile TEXT;
+    ALTER TABLE abandoned ADD COLUMN profile TEXT;
+",
+    "ALTER TABLE abandoned ADD COLUMN utc_offset INTEGER;",
+];
+
+/// Keeps history, tasks and settings in a SQLite database instead of the app state
+/// blob, so saving doesn't mean rewriting years of sessions every time.
+pub struct SqliteStore {
+    conn: Connection,
+    saved: Saved,
+}
+
+/// Sessions as last written, so a save only touches the rows that changed since.
+#[derive(Default)]
+struct Saved {
+    sessions: HashMap<(DateTime<Utc>, DateTime<Utc>), Session>,
+    abandoned: HashMap<DateTime<Utc>, Abandoned>,
+}
+
+impl Saved {
+    /// Notes `history` as what the database now holds.
+    fn remember(&mut self, history: &History) {
+        self.sessions = history
+            .sessions
+            .iter()
+            .map(|s| ((s.started_at, s.ended_at), s.clone()))
+            .collect();
+        self.abandoned = history
+            .abandoned
+            .iter()
+            .map(|a| (a.started_at, a.clone()))
+            .collect();
+    }
+}
+
+impl SqliteStore {
+    pub fn open(path: &Path) -> rusqlite::Result<Self> {
+        let mut conn = Connection::open(path)?;
+        let version: usize = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+        for (i, migration) in MIGRATIONS.iter().enumerate().skip(version) {
+            let tx = conn.transaction()?;
+            tx.execute_batch(migration)?;
+            tx.pragma_update(None, "user_version", i + 1)?;
+            tx.commit()?;
+        }
+        Ok(Self {
+            conn,
+            saved: Saved::default(),
+        })
+    }
+
+    pub fn is_empty(&self) -> rusqlite::Result<bool> {
+        self.conn
+            .query_row("SELECT NOT EXISTS (SELECT 1 FROM settings)", [], |row| {
+                row.get(0)
+            })
+    }
+
+    pub fn load(&mut self) -> rusqlite::Result<(Settings, History, TaskList)> {
+        let settings = self
+            .conn
+            .query_row("SELECT json FROM settings", [], |row| {
+                row.get::<_, String>(0)
+            })
+            .map(|json| serde_json::from_str(&json).unwrap_or_default())
+            .unwrap_or_default();
+
+        let mut stmt = self.conn.prepare(
+            "SELECT started_at, ended_at, app, utc_offset, tag, energy, profile FROM sessions ORDER BY started_at",
+        )?;
+        let sessions = stmt
+            .query_map([], |row| {
+                Ok(Session {
+                    started_at: row.get(0)?,
+                    ended_at: row.get(1)?,
+                    app: row.get(2)?,
+                    utc_offset: row.get(3)?,
+                    tag: row.get(4)?,
+                    energy: row.get(5)?,
+                    profile: row.get(6)?,
+                })
+            })?
+            .collect::<rusqlite::Result<_>>()?;
+
+        let mut stmt = self.conn.prepare(
+            "SELECT started_at, ended_at, tag, reason, profile, utc_offset FROM abandoned ORDER BY started_at",
+        )?;
+        let abandoned = stmt
+            .query_map([], |row| {
+                Ok(Abandoned {
+                    started_at: row.get(0)?,
+                    ended_at: row.get(1)?,
+                    tag: row.get(2)?,
+                    reason: row.get(3)?,
+                    profile: row.get(4)?,
+                    utc_offset: row.get(5)?,
+                })
+            })?
+            .collect::<rusqlite::Result<_>>()?;
+
+        let mut stmt = self
+            .conn
+            .prepare("SELECT title, estimate, completed, done FROM tasks ORDER BY position")?;
+        let tasks = stmt
+            .query_map([], |row| {
+                Ok(Task {
+                    title: row.get(0)?,
+                    estimate: row.get(1)?,
+                    completed: row.get(2)?,
+                    done: row.get(3)?,
+                })
+            })?
+            .collect::<rusqlite::Result<_>>()?;
+
+        let history = History {
+            sessions,
+            abandoned,
+        };
+        self.saved.remember(&history);
+        Ok((settings, history, TaskList { tasks }))
+    }
+
+    pub fn save(
+        &mut self,
+        settings: &Settings,
+        history: &History,
+        tasks: &TaskList,
+    ) -> rusqlite::Result<()> {
+        let json = serde_json::to_string(settings)
+            .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
+        let tx = self.conn.transaction()?;
+        tx.execute(
+            "INSERT OR REPLACE INTO settings (id, json) VALUES (0, ?1)",
+            params![json],
+        )?;
+
+        // Only sessions that are new or changed since the last save are written, and
+        // ones no longer in the history (dropped by syncing) are deleted.
+        let mut upsert = tx.prepare_cached(
+            "INSERT INTO sessions (started_at, ended_at, app, utc_offset, tag, energy, profile) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+             ON CONFLICT (started_at, ended_at) DO UPDATE SET app = excluded.app,
+                 utc_offset = excluded.utc_offset, tag = excluded.tag,
+                 energy = excluded.energy, profile = excluded.profile",
+        )?;
+        let mut gone: HashSet<_> = self.saved.sessions.keys().copied().collect();
+        for session in &history.sessions {
+            let key = (session.started_at, session.ended_at);
+            gone.remove(&key);
+            if self.saved.sessions.get(&key) != Some(session) {
+                upsert.execute(params![
+                    session.started_at,
+                    session.ended_at,
+                    session.app,
+                    session.utc_offset,
+                    session.tag,
+                    session.energy,
+                    session.profile
+                ])?;
+            }
+        }
+        drop(upsert);
+        let mut delete =
+            tx.prepare_cached("DELETE FROM sessions WHERE started_at = ?1 AND ended_at = ?2")?;
+        for (started_at, ended_at) in gone {
+            delete.execute(params![started_at, ended_at])?;
+        }
+        drop(delete);
+
+        let mut upsert = tx.prepare_cached(
+            "INSERT INTO abandoned (started_at, ended_at, tag, reason, profile, utc_offset) VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+             ON CONFLICT (started_at) DO UPDATE SET ended_at = excluded.ended_at,
+                 tag = excluded.tag, reason = excluded.reason, profile = excluded.profile,
+                 utc_offset = excluded.utc_offset",
+        )?;
+        let mut gone: HashSet<_> = self.saved.abandoned.keys().copied().collect();
+        for abandoned in &history.abandoned {
+            gone.remove(&abandoned.started_at);
+            if self.saved.abandoned.get(&abandoned.started_at) != Some(abandoned) {
+                upsert.execute(params![
+                    abandoned.started_at,
+                    abandoned.ended_at,
+                    abandoned.tag,
+                    abandoned.reason,
+                    abandoned.profile,
+                    abandoned.utc_offset
+                ])?;
+            }
+        }
+        drop(upsert);
+        let mut delete = tx.prepare_cached("DELETE FROM abandoned WHERE started_at = ?1")?;
+        for started_at in gone {
+            delete.execute(params![started_at])?;
+        }
+        drop(delete);
+
+        tx.execute("DELETE FROM tasks", [])?;
+        let mut insert = tx.prepare_cached(
+            "INSERT INTO tasks (position, title, estimate, completed, done) VALUES (?1, ?2, ?3, ?4, ?5)",
+        )?;
+        for (i, task) in tasks.tasks.iter().enumerate() {
+            insert.execute(params![
+                i,
+                task.title,
+                task.estimate,
+                task.completed,
+                task.done
+            ])?;
+        }
+        drop(insert);
+        tx.commit()?;
+        self.saved.remember(history);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, TimeZone};
+
+    use super::*;
+
+    #[test]
+    fn migrations_bring_an_old_database_up_to_date() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("epomo.db");
+        // A database from before any column was added, holding one session.
+        let conn = Connection::open(&path).unwrap();
+        conn.execute_batch(MIGRATIONS[0]).unwrap();
+        conn.pragma_update(None, "user_version", 1).unwrap();
+        conn.execute(
+            "INSERT INTO sessions (started_at, ended_at) VALUES (?1, ?2)",
+            params![
+                Utc.with_ymd_and_hms(2023, 3, 1, 9, 0, 0).unwrap(),
+                Utc.with_ymd_and_hms(2023, 3, 1, 9, 25, 0).unwrap()
+            ],
+        )
+        .unwrap();
+        drop(conn);
+
+        let mut store = SqliteStore::open(&path).unwrap();
+        let version: usize = store
+            .conn
+            .query_row("PRAGMA user_version", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(version, MIGRATIONS.len());
+        let (_, history, _) = store.load().unwrap();
+        assert_eq!(history.sessions.len(), 1);
+        assert_eq!(history.sessions[0].profile, None);
+        // Opening it again doesn't run anything twice.
+        drop(store);
+        SqliteStore::open(&path).unwrap();
+    }
+
+    #[test]
+    fn saving_writes_edits_and_drops_removed_sessions() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("epomo.db");
+        let start = Utc.with_ymd_and_hms(2026, 3, 2, 9, 0, 0).unwrap();
+        let mut history = History::default();
+        for i in 0..3 {
+            let started = start + Duration::hours(i);
+            history.record(started, started + Duration::minutes(25), None, None, None);
+        }
+        let mut store = SqliteStore::open(&path).unwrap();
+        store
+            .save(&Settings::default(), &history, &TaskList::default())
+            .unwrap();
+
+        history.sessions[0].tag = Some("writing".to_owned());
+        history.sessions[0].energy = Some(4);
+        history.sessions.remove(1);
+        store
+            .save(&Settings::default(), &history, &TaskList::default())
+            .unwrap();
+
+        let (_, loaded, _) = SqliteStore::open(&path).unwrap().load().unwrap();
+        assert!(loaded.sessions == history.sessions);
+    }
+}
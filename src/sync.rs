@@ -0,0 +1,231 @@
+use std::{
+    collections::HashSet,
+    fs::OpenOptions,
+    io::{self, BufRead, Write},
+    path::{Path, PathBuf},
+    sync::mpsc,
+};
+
+use chrono::{DateTime, Utc};
+
+use crate::{
+    history::{Abandoned, History, Session},
+    settings::Settings,
+};
+
+/// A folder shared between machines by Dropbox, Syncthing and the like.
+///
+/// Every device only ever appends to its own `history-<device>.jsonl`,
+/// `abandoned-<device>.jsonl` and `settings-<device>.jsonl`, so the sync tool never
+/// sees two machines editing the same file. Reading merges the files of all devices.
+pub struct SyncDir {
+    root: PathBuf,
+    device: String,
+}
+
+#[derive(serde::Deserialize, serde::Serialize)]
+struct SettingsEntry {
+    saved_at: DateTime<Utc>,
+    settings: Settings,
+}
+
+/// What a sync on a worker thread came back with.
+pub struct Outcome {
+    /// The history it was given, with the other devices' sessions merged in.
+    pub history: History,
+    /// The newest settings saved since the last sync, if any.
+    pub settings: Option<(DateTime<Utc>, Settings)>,
+}
+
+/// Tells apart the versions of a session before and after it was rated.
+fn session_key(session: &Session) -> (DateTime<Utc>, DateTime<Utc>, bool) {
+    (
+        session.started_at,
+        session.ended_at,
+        session.energy.is_some(),
+    )
+}
+
+/// Tells apart the versions of a session stopped early, before and after a reason was
+/// given.
+fn abandoned_key(abandoned: &Abandoned) -> (DateTime<Utc>, DateTime<Utc>, bool) {
+    (
+        abandoned.started_at,
+        abandoned.ended_at,
+        abandoned.reason.is_some(),
+    )
+}
+
+/// Reads every complete JSON line in the files of all devices with the given prefix.
+/// Lines that don't parse are skipped, since a file may be caught mid-sync.
+fn read_all<T: serde::de::DeserializeOwned>(root: &Path, prefix: &str) -> io::Result<Vec<T>> {
+    let mut items = Vec::new();
+    for entry in std::fs::read_dir(root)? {
+        let path = entry?.path();
+        let is_ours = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .map_or(false, |n| n.starts_with(prefix) && n.ends_with(".jsonl"));
+        if !is_ours {
+            continue;
+        }
+        let file = std::fs::File::open(&path)?;
+        for line in io::BufReader::new(file).lines() {
+            if let Ok(item) = serde_json::from_str(&line?) {
+                items.push(item);
+            }
+        }
+    }
+    Ok(items)
+}
+
+impl SyncDir {
+    pub fn new(root: PathBuf, device: String) -> Self {
+        Self { root, device }
+    }
+
+    fn append<T: serde::Serialize>(&self, prefix: &str, items: &[T]) -> io::Result<()> {
+        if items.is_empty() {
+            return Ok(());
+        }
+        let path = self.root.join(format!("{prefix}-{}.jsonl", self.device));
+        let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+        let mut buf = Vec::new();
+        for item in items {
+            serde_json::to_writer(&mut buf, item)?;
+            buf.push(b'\n');
+        }
+        file.write_all(&buf)
+    }
+
+    /// Publishes the current settings if they changed since `last`.
+    pub fn push_settings(&self, settings: &Settings, last: Option<&Settings>) -> io::Result<()> {
+        if last == Some(settings) {
+            return Ok(());
+        }
+        self.append(
+            "settings",
+            &[SettingsEntry {
+                saved_at: Utc::now(),
+                settings: settings.clone(),
+            }],
+        )
+    }
+
+    /// Appends local sessions the folder doesn't know about yet and merges in the sessions
+    /// of other devices. Returns the newest settings saved after `settings_seen`, if any.
+    pub fn sync(
+        &self,
+        history: &mut History,
+        settings_seen: Option<DateTime<Utc>>,
+    ) -> io::Result<Option<(DateTime<Utc>, Settings)>> {
+        std::fs::create_dir_all(&self.root)?;
+
+        let remote: Vec<Session> = read_all(&self.root, "history-")?;
+        let known: HashSet<_> = remote.iter().map(session_key).collect();
+        let missing: Vec<Session> = history
+            .sessions
+            .iter()
+            .filter(|s| !known.contains(&session_key(s)))
+            .cloned()
+            .collect();
+        self.append("history", &missing)?;
+        history.merge(remote);
+
+        let remote: Vec<Abandoned> = read_all(&self.root, "abandoned-")?;
+        let known: HashSet<_> = remote.iter().map(abandoned_key).collect();
+        let missing: Vec<Abandoned> = history
+            .abandoned
+            .iter()
+            .filter(|a| !known.contains(&abandoned_key(a)))
+            .cloned()
+            .collect();
+        self.append("abandoned", &missing)?;
+        history.merge_abandoned(remote);
+
+        Ok(read_all::<SettingsEntry>(&self.root, "settings-")?
+            .into_iter()
+            .filter(|e| settings_seen.map_or(true, |seen| e.saved_at > seen))
+            .max_by_key(|e| e.saved_at)
+            .map(|e| (e.saved_at, e.settings)))
+    }
+
+    /// Publishes `settings` and syncs `history` like [`SyncDir::push_settings`] and
+    /// [`SyncDir::sync`], on a worker thread.
+    pub fn spawn(
+        self,
+        mut history: History,
+        settings: Settings,
+        last: Option<Settings>,
+        settings_seen: Option<DateTime<Utc>>,
+    ) -> mpsc::Receiver<io::Result<Outcome>> {
+        let (tx, rx) = mpsc::channel();
+        std::thread::spawn(move || {
+            let res = self
+                .push_settings(&settings, last.as_ref())
+                .and_then(|()| self.sync(&mut history, settings_seen))
+                .map(|settings| Outcome { history, settings });
+            let _ = tx.send(res);
+        });
+        rx
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+
+    use super::*;
+
+    #[test]
+    fn ratings_given_after_syncing_reach_every_device() {
+        let root = tempfile::tempdir().unwrap();
+        let start = Utc::now() - Duration::hours(2);
+        let mut a = History::default();
+        a.record(start, start + Duration::minutes(25), None, None, None);
+        let mut b = History::default();
+        let dir_a = SyncDir::new(root.path().to_owned(), "a".to_owned());
+        let dir_b = SyncDir::new(root.path().to_owned(), "b".to_owned());
+        dir_a.sync(&mut a, None).unwrap();
+        dir_b.sync(&mut b, None).unwrap();
+        assert_eq!(b.sessions[0].energy, None);
+
+        a.rate(start, 4);
+        for _ in 0..2 {
+            dir_a.sync(&mut a, None).unwrap();
+            dir_b.sync(&mut b, None).unwrap();
+        }
+        for history in [&a, &b] {
+            assert_eq!(history.sessions.len(), 1);
+            assert_eq!(history.sessions[0].energy, Some(4));
+        }
+    }
+
+    #[test]
+    fn sessions_stopped_early_reach_every_device_once() {
+        let root = tempfile::tempdir().unwrap();
+        let start = Utc::now() - Duration::hours(3);
+        let (mut a, mut b) = (History::default(), History::default());
+        a.abandon(start, start + Duration::minutes(10), None, None);
+        b.abandon(
+            start + Duration::hours(1),
+            start + Duration::hours(1) + Duration::minutes(5),
+            None,
+            None,
+        );
+        let dir_a = SyncDir::new(root.path().to_owned(), "a".to_owned());
+        let dir_b = SyncDir::new(root.path().to_owned(), "b".to_owned());
+        dir_a.sync(&mut a, None).unwrap();
+        dir_b.sync(&mut b, None).unwrap();
+        // A reason given later on one device reaches the other too.
+        a.set_abandon_reason(start, "meeting".to_owned());
+        for _ in 0..2 {
+            dir_a.sync(&mut a, None).unwrap();
+            dir_b.sync(&mut b, None).unwrap();
+        }
+        for history in [&a, &b] {
+            assert_eq!(history.abandoned.len(), 2);
+            assert_eq!(history.abandoned[0].reason.as_deref(), Some("meeting"));
+        }
+    }
+}
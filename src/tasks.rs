@@ -0,0 +1,79 @@
+use chrono::{DateTime, Utc};
+
+use crate::{
+    settings::Settings,
+    timer::{get_mode, phase_length, PomodoroMode, Timer},
+};
+
+#[derive(Clone, serde::Deserialize, serde::Serialize)]
+pub struct Task {
+    pub title: String,
+    /// Estimated pomodoros.
+    pub estimate: u32,
+    /// Work sessions spent on it so far.
+    pub completed: u32,
+    pub done: bool,
+}
+
+/// The day's plan, worked through from the top.
+#[derive(Clone, Default, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct TaskList {
+    pub tasks: Vec<Task>,
+}
+
+impl TaskList {
+    pub fn add(&mut self, title: String, estimate: u32) {
+        self.tasks.push(Task {
+            title,
+            estimate,
+            completed: 0,
+            done: false,
+        });
+    }
+
+    /// The task being worked on: the first one not marked done.
+    pub fn current_mut(&mut self) -> Option<&mut Task> {
+        self.tasks.iter_mut().find(|t| !t.done)
+    }
+
+    /// Credits a finished work session to the current task.
+    pub fn credit_session(&mut self) {
+        if let Some(task) = self.current_mut() {
+            task.completed += 1;
+        }
+    }
+
+    pub fn remaining_pomodoros(&self) -> u32 {
+        self.tasks
+            .iter()
+            .filter(|t| !t.done)
+            .map(|t| t.estimate.saturating_sub(t.completed))
+            .sum()
+    }
+}
+
+/// When `remaining` more work sessions will be done if the cycle runs on from where the
+/// timer is now, breaks included.
+pub fn projected_finish(timer: &Timer, settings: &Settings, mut remaining: u32) -> DateTime<Utc> {
+    let profile = settings.profile();
+    let mut at = Utc::now();
+    let (mut mode, mut count) = (timer.mode, timer.session_count);
+    if let Some(end) = timer.ends_at {
+        at = at.max(end);
+        if mode == PomodoroMode::Work {
+            remaining = remaining.saturating_sub(1);
+            count += 1;
+        }
+        mode = get_mode(mode, count, profile.sessions_per_long_break);
+    }
+    while remaining > 0 {
+        at += phase_length(profile.period(mode));
+        if mode == PomodoroMode::Work {
+            remaining -= 1;
+            count += 1;
+        }
+        mode = get_mode(mode, count, profile.sessions_per_long_break);
+    }
+    at
+}
@@ -0,0 +1,299 @@
+use std::{
+    io,
+    path::{Path, PathBuf},
+    time::{Duration, Instant, SystemTime},
+};
+
+use egui::{Color32, FontData, FontDefinitions, FontFamily, Rounding, Vec2};
+
+use crate::paths;
+
+/// The names a theme file can have in the config directory, in the order they're looked
+/// for.
+const FILES: [&str; 2] = ["theme.toml", "theme.json"];
+/// How often the theme file is checked for changes.
+const CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// A color written as `#rrggbb` or `#rrggbbaa`.
+#[derive(Clone, Copy, PartialEq)]
+pub struct Hex(pub Color32);
+
+impl<'de> serde::Deserialize<'de> for Hex {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        let digits = text.trim().trim_start_matches('#');
+        let channel = |i: usize| {
+            digits
+                .get(i..i + 2)
+                .and_then(|c| u8::from_str_radix(c, 16).ok())
+        };
+        let color = match digits.len() {
+            6 => channel(0)
+                .zip(channel(2))
+                .zip(channel(4))
+                .map(|((r, g), b)| Color32::from_rgb(r, g, b)),
+            8 => channel(0)
+                .zip(channel(2))
+                .zip(channel(4))
+                .zip(channel(6))
+                .map(|(((r, g), b), a)| Color32::from_rgba_unmultiplied(r, g, b, a)),
+            _ => None,
+        };
+        color.map(Hex).ok_or_else(|| {
+            serde::de::Error::custom(format!("{text:?} isn't a #rrggbb or #rrggbbaa color"))
+        })
+    }
+}
+
+#[derive(Clone, Default, PartialEq, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Colors {
+    /// Behind windows and panels.
+    pub background: Option<Hex>,
+    pub panel: Option<Hex>,
+    pub text: Option<Hex>,
+    /// Selections and the keyboard focus ring.
+    pub accent: Option<Hex>,
+    pub link: Option<Hex>,
+    /// Buttons and other widgets at rest, and under the pointer.
+    pub widget: Option<Hex>,
+    pub widget_hovered: Option<Hex>,
+    pub border: Option<Hex>,
+}
+
+#[derive(Clone, Default, PartialEq, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Fonts {
+    /// A TrueType or OpenType file for all text but the monospaced, relative to the
+    /// theme file.
+    pub file: Option<PathBuf>,
+    /// Body text size in points. Headings and small text are scaled to match.
+    pub size: Option<f32>,
+}
+
+#[derive(Clone, Default, PartialEq, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Spacing {
+    /// Horizontal and vertical gap between widgets.
+    pub item: Option<[f32; 2]>,
+    pub button_padding: Option<[f32; 2]>,
+    pub window_margin: Option<f32>,
+    /// Corner radius of widgets and windows.
+    pub rounding: Option<f32>,
+}
+
+/// A look for the app, read from `theme.toml` or `theme.json` in the config directory.
+/// Anything left out keeps egui's default.
+#[derive(Clone, Default, PartialEq, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Theme {
+    /// Start from egui's light look instead of the dark one.
+    pub light: bool,
+    pub colors: Colors,
+    pub fonts: Fonts,
+    pub spacing: Spacing,
+}
+
+impl Theme {
+    fn read(path: &Path) -> io::Result<Self> {
+        let contents = std::fs::read_to_string(path)?;
+        let invalid = |e: String| io::Error::new(io::ErrorKind::InvalidData, e);
+        if path.extension().map_or(false, |ext| ext == "json") {
+            serde_json::from_str(&contents).map_err(|e| invalid(e.to_string()))
+        } else {
+            toml::from_str(&contents).map_err(|e| invalid(e.to_string()))
+        }
+    }
+
+    pub fn accent(&self) -> Option<Color32> {
+        self.colors.accent.map(|Hex(c)| c)
+    }
+
+    pub fn style(&self) -> egui::Style {
+        let mut style = egui::Style::default();
+        let visuals = &mut style.visuals;
+        if self.light {
+            *visuals = egui::Visuals::light();
+        }
+        let Colors {
+            background,
+            panel,
+            text,
+            accent,
+            link,
+            widget,
+            widget_hovered,
+            border,
+        } = self.colors.clone();
+        if let Some(Hex(c)) = background {
+            visuals.window_fill = c;
+            visuals.extreme_bg_color = c;
+        }
+        if let Some(Hex(c)) = panel {
+            visuals.panel_fill = c;
+            visuals.widgets.noninteractive.bg_fill = c;
+        }
+        if let Some(Hex(c)) = text {
+            visuals.override_text_color = Some(c);
+        }
+        if let Some(Hex(c)) = accent {
+            visuals.selection.bg_fill = c;
+            visuals.selection.stroke.color = c;
+        }
+        if let Some(Hex(c)) = link {
+            visuals.hyperlink_color = c;
+        }
+        if let Some(Hex(c)) = widget {
+            visuals.widgets.inactive.bg_fill = c;
+            visuals.widgets.inactive.weak_bg_fill = c;
+        }
+        if let Some(Hex(c)) = widget_hovered {
+            visuals.widgets.hovered.bg_fill = c;
+            visuals.widgets.hovered.weak_bg_fill = c;
+        }
+        if let Some(Hex(c)) = border {
+            visuals.window_stroke.color = c;
+            visuals.widgets.noninteractive.bg_stroke.color = c;
+        }
+        if let Some(radius) = self.spacing.rounding {
+            let rounding = Rounding::same(radius);
+            visuals.window_rounding = rounding;
+            for widget in [
+                &mut visuals.widgets.noninteractive,
+                &mut visuals.widgets.inactive,
+                &mut visuals.widgets.hovered,
+                &mut visuals.widgets.active,
+                &mut visuals.widgets.open,
+            ] {
+                widget.rounding = rounding;
+            }
+        }
+
+        let spacing = &mut style.spacing;
+        if let Some([x, y]) = self.spacing.item {
+            spacing.item_spacing = Vec2::new(x, y);
+        }
+        if let Some([x, y]) = self.spacing.button_padding {
+            spacing.button_padding = Vec2::new(x, y);
+        }
+        if let Some(margin) = self.spacing.window_margin {
+            spacing.window_margin = egui::style::Margin::same(margin);
+        }
+        if let Some(size) = self.fonts.size {
+            let scale = size / egui::FontId::default().size;
+            for font in style.text_styles.values_mut() {
+                font.size *= scale;
+            }
+        }
+        style
+    }
+
+    /// egui's fonts with the theme's font file, relative to `dir`, put first.
+    fn fonts(&self, dir: &Path) -> io::Result<FontDefinitions> {
+        let mut fonts = FontDefinitions::default();
+        if let Some(file) = &self.fonts.file {
+            let path = dir.join(file);
+            let data = std::fs::read(&path).map_err(|e| {
+                io::Error::new(e.kind(), format!("could not read {}: {e}", path.display()))
+            })?;
+            fonts
+                .font_data
+                .insert("theme".to_owned(), FontData::from_owned(data));
+            if let Some(family) = fonts.families.get_mut(&FontFamily::Proportional) {
+                family.insert(0, "theme".to_owned());
+            }
+        }
+        Ok(fonts)
+    }
+}
+
+/// What became of the theme file since it was last looked at.
+pub enum Change {
+    Loaded(Box<Theme>, FontDefinitions),
+    /// There's no theme file anymore, so egui's own look is back.
+    Removed,
+    Failed(String),
+}
+
+/// Watches the theme file, so changes to it show up as soon as it's saved.
+#[derive(Default)]
+pub struct ThemeFile {
+    watching: bool,
+    checked: Option<Instant>,
+    /// Where the file was and when it was last changed, the last time it was read.
+    seen: Option<(PathBuf, SystemTime)>,
+    theme: Option<Theme>,
+}
+
+impl ThemeFile {
+    /// Where a new theme file would be picked up: an existing one, or else `theme.toml`.
+    pub fn path() -> Option<PathBuf> {
+        let dir = paths::config_dir()?;
+        let path = FILES
+            .into_iter()
+            .map(|name| dir.join(name))
+            .find(|path| path.exists())
+            .unwrap_or_else(|| dir.join(FILES[0]));
+        Some(path)
+    }
+
+    /// The theme in use, if there is one.
+    pub fn theme(&self) -> Option<&Theme> {
+        self.theme.as_ref()
+    }
+
+    /// Looks at the file again once a second and says what changed, if anything. The
+    /// first call starts watching the file, so `ctx` repaints when it changes even while
+    /// nothing else is going on.
+    pub fn poll(&mut self, ctx: &egui::Context) -> Option<Change> {
+        if !self.watching {
+            self.watching = true;
+            watch(ctx.clone());
+        }
+        if self.checked.map_or(false, |t| t.elapsed() < CHECK_INTERVAL) {
+            return None;
+        }
+        self.checked = Some(Instant::now());
+        let path = Self::path()?;
+        let Ok(modified) = path.metadata().and_then(|m| m.modified()) else {
+            self.seen.take()?;
+            self.theme = None;
+            return Some(Change::Removed);
+        };
+        let seen = Some((path.clone(), modified));
+        if self.seen == seen {
+            return None;
+        }
+        // Remembered even when reading fails, so a broken file is only reported once.
+        self.seen = seen;
+        let dir = path.parent().unwrap_or(Path::new("."));
+        let loaded = Theme::read(&path).and_then(|theme| {
+            let fonts = theme.fonts(dir)?;
+            Ok((theme, fonts))
+        });
+        Some(match loaded {
+            Ok((theme, fonts)) => {
+                self.theme = Some(theme.clone());
+                Change::Loaded(Box::new(theme), fonts)
+            }
+            Err(e) => Change::Failed(format!("Could not load {}: {e}", path.display())),
+        })
+    }
+}
+
+/// Asks `ctx` for a repaint whenever the theme file appears, changes or goes away, for
+/// as long as the app runs.
+fn watch(ctx: egui::Context) {
+    std::thread::spawn(move || {
+        let mut seen = None;
+        loop {
+            let modified =
+                ThemeFile::path().and_then(|path| path.metadata().and_then(|m| m.modified()).ok());
+            if modified != seen {
+                seen = modified;
+                ctx.request_repaint();
+            }
+            std::thread::sleep(CHECK_INTERVAL);
+        }
+    });
+}
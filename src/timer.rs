@@ -0,0 +1,629 @@
+use std::{
+    fmt::Display,
+    sync::atomic::{AtomicU32, Ordering},
+    time::Instant,
+};
+
+use chrono::{DateTime, Datelike, Local, NaiveDate, NaiveTime, TimeZone, Utc};
+
+use crate::{
+    events::TimerEvent,
+    settings::{LongBreakSkip, Settings},
+};
+
+#[derive(
+    Copy, Clone, Debug, Default, PartialEq, Eq, Hash, serde::Deserialize, serde::Serialize,
+)]
+pub enum PomodoroMode {
+    LongBreak,
+    ShortBreak,
+    #[default]
+    Work,
+}
+
+impl PomodoroMode {
+    pub fn get_notif_str(&self) -> &'static str {
+        match *self {
+            PomodoroMode::LongBreak => "Time for a long break!",
+            PomodoroMode::ShortBreak => "Time for a short break!",
+            PomodoroMode::Work => "Time to work!",
+        }
+    }
+}
+
+impl Display for PomodoroMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match *self {
+            PomodoroMode::LongBreak => write!(f, "Long break"),
+            PomodoroMode::ShortBreak => write!(f, "Short break"),
+            PomodoroMode::Work => write!(f, "Work"),
+        }
+    }
+}
+
+/// The most a work phase can be made longer or shorter by at random.
+pub const MAX_JITTER_MINUTES: i64 = 10;
+
+/// The longest wait allowed between one phase and the next.
+pub const MAX_GRACE_SECS: u32 = 60;
+
+/// How many times faster than real time phases run. Only ever changed for development.
+static TIME_SCALE: AtomicU32 = AtomicU32::new(1);
+
+/// Makes every phase run `scale` times faster, e.g. 60 to get through a 25 minute phase
+/// in 25 seconds.
+pub fn set_time_scale(scale: u32) {
+    TIME_SCALE.store(scale.max(1), Ordering::Relaxed);
+}
+
+/// How long a phase of `minutes` really takes, given the time scale.
+pub fn phase_length(minutes: i64) -> chrono::Duration {
+    chrono::Duration::minutes(minutes) / TIME_SCALE.load(Ordering::Relaxed) as i32
+}
+
+/// The phase after `cur_mode`, with `session_count` work sessions done and a long break
+/// after every `per_long_break` of them.
+pub fn get_mode(
+    cur_mode: PomodoroMode,
+    session_count: usize,
+    per_long_break: usize,
+) -> PomodoroMode {
+    match cur_mode {
+        PomodoroMode::Work => {
+            if session_count % per_long_break.max(1) == 0 {
+                PomodoroMode::LongBreak
+            } else {
+                PomodoroMode::ShortBreak
+            }
+        }
+        _ => PomodoroMode::Work,
+    }
+}
+
+/// Work done on one local day, for the daily limit.
+#[derive(Clone, Copy, Debug, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct DailyTally {
+    pub day: Option<NaiveDate>,
+    pub sessions: u32,
+    pub focus_secs: i64,
+}
+
+impl DailyTally {
+    /// Counts a work phase, starting over if it ended on a new day.
+    fn add(&mut self, started_at: DateTime<Utc>, ended_at: DateTime<Utc>) {
+        let day = ended_at.with_timezone(&Local).date_naive();
+        if self.day != Some(day) {
+            *self = DailyTally {
+                day: Some(day),
+                ..Default::default()
+            };
+        }
+        self.sessions += 1;
+        self.focus_secs += (ended_at - started_at).num_seconds().max(0);
+    }
+
+    /// Whether the work counted for `day` is up to either of `settings`' daily limits.
+    pub fn reached(&self, settings: &Settings, day: NaiveDate) -> bool {
+        if self.day != Some(day) {
+            return false;
+        }
+        let sessions = settings.daily_max_sessions;
+        let minutes = settings.daily_max_focus_minutes;
+        (sessions > 0 && self.sessions >= sessions)
+            || (minutes > 0 && self.focus_secs >= minutes * 60)
+    }
+}
+
+/// Saves a duration as whole milliseconds.
+mod millis {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer>(
+        duration: &Option<chrono::Duration>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        duration.map(|d| d.num_milliseconds()).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<chrono::Duration>, D::Error> {
+        Ok(Option::<i64>::deserialize(deserializer)?.map(chrono::Duration::milliseconds))
+    }
+}
+
+/// One timer instance. The app always has a pomodoro timer that cycles through work and
+/// breaks; any others are plain countdowns like "laundry in 40 min".
+#[derive(serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct Timer {
+    pub label: String,
+    /// Length of a plain countdown. Pomodoro timers take theirs from the active profile.
+    pub minutes: i64,
+    /// Pomodoro timers move on to the next phase when one ends, countdowns just stop.
+    pub cycles: bool,
+    pub session_count: usize,
+
+    // The live state below is saved too, so a phase survives a crash or restart.
+    pub mode: PomodoroMode,
+    /// Wall-clock end of the current phase, for display and for sharing with others.
+    pub ends_at: Option<DateTime<Utc>>,
+    /// When the current phase actually ends. This doesn't move when the system clock is
+    /// adjusted, so NTP corrections or manual changes can't cut a phase short or stretch
+    /// it.
+    #[serde(skip)]
+    deadline: Option<Instant>,
+    pub phase_started_at: Option<DateTime<Utc>>,
+    /// Set when the current phase runs up to a clock time rather than for a duration.
+    pub target: Option<DateTime<Utc>>,
+    /// A long break was skipped with [`LongBreakSkip::CarryOver`], so the next work
+    /// phase is followed by one.
+    pub long_break_owed: bool,
+    /// Work done today, counted as phases end.
+    pub today: DailyTally,
+    /// What was left of the phase when it was paused.
+    #[serde(with = "millis")]
+    paused_left: Option<chrono::Duration>,
+    /// Events not yet handed out by [`Timer::take_events`].
+    #[serde(skip)]
+    events: Vec<TimerEvent>,
+}
+
+impl Default for Timer {
+    fn default() -> Self {
+        Self::pomodoro()
+    }
+}
+
+impl Timer {
+    pub fn pomodoro() -> Self {
+        Self {
+            label: "Pomodoro".to_owned(),
+            minutes: 0,
+            cycles: true,
+            session_count: 0,
+            mode: PomodoroMode::Work, // Begin with work
+            ends_at: None,
+            deadline: None,
+            phase_started_at: None,
+            target: None,
+            long_break_owed: false,
+            today: DailyTally::default(),
+            paused_left: None,
+            events: Vec::new(),
+        }
+    }
+
+    pub fn countdown(label: String, minutes: i64) -> Self {
+        Self {
+            label,
+            minutes,
+            cycles: false,
+            ..Self::pomodoro()
+        }
+    }
+
+    /// Length of the current phase in minutes.
+    pub fn period(&self, settings: &Settings) -> i64 {
+        if self.cycles {
+            settings.profile().period(self.mode)
+        } else {
+            self.minutes
+        }
+    }
+
+    /// How long the current phase takes.
+    pub fn length(&self, settings: &Settings) -> chrono::Duration {
+        phase_length(self.period(settings))
+    }
+
+    /// How long a phase starting now takes. That's [`Timer::length`], give or take a few
+    /// random minutes for work phases when jitter is on.
+    fn draw_length(&self, settings: &Settings) -> chrono::Duration {
+        let jitter = settings.jitter_minutes.clamp(0, MAX_JITTER_MINUTES);
+        if !self.cycles || self.mode != PomodoroMode::Work || jitter == 0 {
+            return self.length(settings);
+        }
+        let minutes = self.period(settings) + fastrand::i64(-jitter..=jitter);
+        phase_length(minutes.max(1))
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.ends_at.is_some()
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused_left.is_some()
+    }
+
+    /// Time left of the current phase. During the grace period before it starts, that's
+    /// all of it.
+    pub fn time_left(&self) -> Option<chrono::Duration> {
+        if let Some(left) = self.paused_left {
+            return Some(left);
+        }
+        let left = match self.deadline {
+            Some(deadline) => {
+                chrono::Duration::from_std(deadline.saturating_duration_since(Instant::now())).ok()
+            }
+            None => self.ends_at.map(|end| end - Utc::now()),
+        }?;
+        Some(left - self.grace_left().unwrap_or_else(chrono::Duration::zero))
+    }
+
+    /// How long until the current phase starts, while it's held back by the grace period
+    /// after the one before.
+    pub fn grace_left(&self) -> Option<chrono::Duration> {
+        let start = self.phase_started_at.filter(|_| self.is_running())?;
+        let left = start - Utc::now();
+        (left > chrono::Duration::zero()).then_some(left)
+    }
+
+    /// When the next long break starts and when it ends, closing the cycle, if the
+    /// phases run on from where they are now with the configured lengths. During a long
+    /// break, that's the one running. `None` while stopped.
+    pub fn long_break_forecast(
+        &self,
+        settings: &Settings,
+    ) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+        let left = self.time_left().filter(|_| self.cycles)?;
+        let now = Utc::now();
+        let mut at = now + self.grace_left().unwrap_or_else(chrono::Duration::zero) + left;
+        if self.mode == PomodoroMode::LongBreak {
+            return Some((self.phase_started_at.unwrap_or(now).min(now), at));
+        }
+        let profile = settings.profile();
+        let grace = chrono::Duration::seconds(settings.grace_secs.min(MAX_GRACE_SECS).into());
+        let (mut mode, mut count) = (self.mode, self.session_count);
+        loop {
+            // `at` is the end of `mode`.
+            if mode == PomodoroMode::Work {
+                count += 1;
+            }
+            mode = match mode {
+                PomodoroMode::Work if self.long_break_owed => PomodoroMode::LongBreak,
+                _ => get_mode(mode, count, profile.sessions_per_long_break),
+            };
+            at += grace;
+            let end = at + phase_length(profile.period(mode));
+            if mode == PomodoroMode::LongBreak {
+                return Some((at, end));
+            }
+            at = end;
+        }
+    }
+
+    /// Hands out the events queued since the last call.
+    pub fn take_events(&mut self) -> Vec<TimerEvent> {
+        std::mem::take(&mut self.events)
+    }
+
+    /// Picks up a saved timer where it left off. A phase still running goes on until its
+    /// end on the wall clock. One that ended while the app was closed ends at the time
+    /// it really ended, but the time the app was closed isn't walked through: the next
+    /// phase starts now.
+    pub fn restore(&mut self, settings: &mut Settings) {
+        let now = Utc::now();
+        match self.ends_at {
+            Some(end) if end > now => self.set_ends_at(Some(end)),
+            Some(end) => self.end_phase(settings, end, now, false),
+            None => {}
+        }
+    }
+
+    /// Sets the end of the phase from a wall-clock time, e.g. one received from the host
+    /// of a shared timer.
+    pub fn set_ends_at(&mut self, ends_at: Option<DateTime<Utc>>) {
+        self.ends_at = ends_at;
+        self.deadline =
+            ends_at.map(|end| Instant::now() + (end - Utc::now()).to_std().unwrap_or_default());
+    }
+
+    /// Takes on the state of a shared timer someone else is hosting, queueing the same
+    /// events as if the phases had changed here.
+    pub fn follow(
+        &mut self,
+        mode: PomodoroMode,
+        ends_at: Option<DateTime<Utc>>,
+        session_count: usize,
+    ) {
+        let now = Utc::now();
+        if mode != self.mode && self.is_running() {
+            self.events.push(TimerEvent::PhaseEnded {
+                mode: self.mode,
+                next: mode,
+                started_at: self.phase_started_at.unwrap_or(now),
+                ended_at: now,
+            });
+        }
+        let started = ends_at.is_some() && (mode != self.mode || !self.is_running());
+        if started {
+            self.phase_started_at = Some(now);
+        }
+        self.mode = mode;
+        self.set_ends_at(ends_at);
+        self.session_count = session_count;
+        if let (true, Some(ends_at)) = (started, ends_at) {
+            self.events.push(TimerEvent::PhaseStarted { mode, ends_at });
+        }
+    }
+
+    /// Begins a phase of `length` from now.
+    fn run_for(&mut self, length: chrono::Duration) {
+        self.run_from(Utc::now(), length);
+    }
+
+    /// Begins a phase of `length` that started at `start`, which may be in the past.
+    fn run_from(&mut self, start: DateTime<Utc>, length: chrono::Duration) {
+        let ends_at = start + length;
+        self.ends_at = Some(ends_at);
+        self.deadline = Some(Instant::now() + (ends_at - Utc::now()).to_std().unwrap_or_default());
+        self.phase_started_at = Some(start);
+        if self.cycles {
+            self.events.push(TimerEvent::PhaseStarted {
+                mode: self.mode,
+                ends_at,
+            });
+        }
+    }
+
+    pub fn start(&mut self, settings: &mut Settings) {
+        if self.cycles {
+            settings.apply_schedule(chrono::Local::now().weekday());
+        }
+        self.run_for(self.draw_length(settings));
+    }
+
+    /// Starts a work phase that ends exactly at `target` instead of after the usual
+    /// interval, e.g. to work right up to a meeting.
+    pub fn start_until(&mut self, target: DateTime<Utc>) {
+        self.mode = PomodoroMode::Work;
+        self.run_for(target - Utc::now());
+        self.target = Some(target);
+    }
+
+    pub fn pause(&mut self) {
+        let Some(left) = self.time_left().filter(|_| !self.is_paused()) else {
+            return;
+        };
+        self.paused_left = Some(left);
+        self.ends_at = None;
+        self.deadline = None;
+        self.events.push(TimerEvent::Paused {
+            mode: self.mode,
+            at: Utc::now(),
+        });
+    }
+
+    /// Picks up a paused phase where it left off. The phase keeps its original start, so
+    /// the pause counts towards it.
+    pub fn resume(&mut self) {
+        let Some(left) = self.paused_left.take() else {
+            return;
+        };
+        let now = Utc::now();
+        // Paused before it had even started, so it starts now.
+        if self.phase_started_at.map_or(false, |start| start > now) {
+            self.phase_started_at = Some(now);
+        }
+        let ends_at = now + left;
+        self.ends_at = Some(ends_at);
+        self.deadline = Some(Instant::now() + left.to_std().unwrap_or_default());
+        self.events.push(TimerEvent::Resumed {
+            mode: self.mode,
+            ends_at,
+        });
+    }
+
+    /// Makes the current phase, running or paused, longer by `by`.
+    pub fn extend(&mut self, by: chrono::Duration) {
+        if let Some(left) = self.paused_left {
+            self.paused_left = Some(left + by);
+        } else if let Some(end) = self.ends_at {
+            self.ends_at = Some(end + by);
+            self.deadline = self.deadline.map(|d| d + by.to_std().unwrap_or_default());
+        }
+    }
+
+    /// Moves the timer `by` into the future, as if that much time had gone by, so the
+    /// current phase ends that much sooner. Paused timers stay put. Lets tests and
+    /// simulations drive a timer without waiting for it.
+    pub fn fast_forward(&mut self, by: chrono::Duration) {
+        let Some(end) = self.ends_at else {
+            return;
+        };
+        self.ends_at = Some(end - by);
+        self.phase_started_at = self.phase_started_at.map(|t| t - by);
+        self.target = self.target.map(|t| t - by);
+        // Falls back to the wall clock if the monotonic one can't go back that far.
+        self.deadline = self
+            .deadline
+            .and_then(|d| d.checked_sub(by.to_std().unwrap_or_default()));
+    }
+
+    /// Cuts the current phase short and starts the next one. A skipped work phase doesn't
+    /// count as a session, so it's always followed by a short break.
+    pub fn skip(&mut self, settings: &mut Settings) {
+        if !self.cycles || !(self.is_running() || self.is_paused()) {
+            return;
+        }
+        let from = self.mode;
+        let next = match from {
+            PomodoroMode::Work => PomodoroMode::ShortBreak,
+            _ => PomodoroMode::Work,
+        };
+        self.paused_left = None;
+        self.target = None;
+        self.events.push(TimerEvent::Skipped {
+            mode: from,
+            next,
+            at: Utc::now(),
+        });
+        if from == PomodoroMode::LongBreak {
+            match settings.long_break_skip {
+                LongBreakSkip::EndCycle => settings.apply_schedule(chrono::Local::now().weekday()),
+                LongBreakSkip::CarryOver => self.long_break_owed = true,
+            }
+        }
+        self.mode = next;
+        self.run_for(self.draw_length(settings));
+    }
+
+    pub fn stop(&mut self) {
+        if self.cycles && (self.is_running() || self.is_paused()) {
+            self.events.push(TimerEvent::Stopped {
+                mode: self.mode,
+                started_at: self.phase_started_at,
+                at: Utc::now(),
+            });
+        }
+        self.ends_at = None;
+        self.deadline = None;
+        self.phase_started_at = None;
+        self.target = None;
+        self.paused_left = None;
+        if self.cycles {
+            self.session_count = 0;
+            self.long_break_owed = false;
+        }
+    }
+
+    /// Moves on to the next phase once the current one is over, or stops a countdown.
+    /// Returns whether anything changed; the details are queued as events.
+    ///
+    /// If nothing called this for a while, e.g. because the window wasn't being drawn,
+    /// several phases may have gone by. Each of them is walked through in turn, as if
+    /// it had been ticked on time, and [`TimerEvent::CaughtUp`] sums them up.
+    pub fn tick(&mut self, settings: &mut Settings) -> bool {
+        let Some(end) = self.ends_at else {
+            return false;
+        };
+        let now = Utc::now();
+        // How long ago the phase ran out, if it has.
+        let overdue = match self.deadline {
+            Some(deadline) => Instant::now().checked_duration_since(deadline).map(|d| {
+                chrono::Duration::from_std(d).unwrap_or_else(|_| chrono::Duration::zero())
+            }),
+            None => (end < now).then(|| now - end),
+        };
+        let Some(overdue) = overdue else {
+            return false;
+        };
+        // Measured on the monotonic clock when there is one, so this is right even if
+        // the system clock was changed during the phase.
+        self.end_phase(settings, now - overdue, now, true);
+        true
+    }
+
+    /// Ends the current phase at `end`. With `catch_up`, also ends every later phase
+    /// that would have been over by `now`, each starting after the grace period;
+    /// otherwise the next phase starts now.
+    fn end_phase(
+        &mut self,
+        settings: &mut Settings,
+        mut end: DateTime<Utc>,
+        now: DateTime<Utc>,
+        catch_up: bool,
+    ) {
+        let mut started_at = self.phase_started_at.unwrap_or(end - self.length(settings));
+
+        self.target = None;
+        if !self.cycles {
+            self.ends_at = None;
+            self.deadline = None;
+            self.phase_started_at = None;
+            self.events.push(TimerEvent::CountdownFinished {
+                label: self.label.clone(),
+                at: end,
+            });
+            return;
+        }
+
+        let grace = chrono::Duration::seconds(settings.grace_secs.min(MAX_GRACE_SECS).into());
+        let (mut phases, mut sessions) = (0, 0);
+        loop {
+            let from = self.mode;
+            let day = end.with_timezone(&Local).date_naive();
+            let mut limit_reached = false;
+            if from == PomodoroMode::Work {
+                self.session_count += 1;
+                sessions += 1;
+                let before = self.today.reached(settings, day);
+                self.today.add(started_at, end);
+                limit_reached = !before && self.today.reached(settings, day);
+            }
+            if from == PomodoroMode::LongBreak {
+                // A new cycle begins.
+                settings.apply_schedule(chrono::Local::now().weekday());
+            }
+            self.mode = get_mode(
+                from,
+                self.session_count,
+                settings.profile().sessions_per_long_break,
+            );
+            if from == PomodoroMode::Work && self.long_break_owed {
+                self.mode = PomodoroMode::LongBreak;
+            }
+            if self.mode == PomodoroMode::LongBreak {
+                self.long_break_owed = false;
+            }
+            self.events.push(TimerEvent::PhaseEnded {
+                mode: from,
+                next: self.mode,
+                started_at,
+                ended_at: end,
+            });
+            if limit_reached {
+                self.events.push(TimerEvent::DailyLimitReached {
+                    sessions: self.today.sessions,
+                    focus_minutes: self.today.focus_secs / 60,
+                });
+            }
+            phases += 1;
+            if from != PomodoroMode::Work
+                && self.mode == PomodoroMode::Work
+                && settings.hold_at_daily_limit
+                && self.today.reached(settings, day)
+            {
+                self.ends_at = None;
+                self.deadline = None;
+                self.phase_started_at = None;
+                self.paused_left = None;
+                self.events.push(TimerEvent::HeldForTheDay { at: end });
+                break;
+            }
+            let length = self.draw_length(settings);
+            if !catch_up {
+                self.run_for(length);
+                break;
+            }
+            self.run_from(end + grace, length);
+            if end + grace + length > now || length <= chrono::Duration::zero() {
+                break;
+            }
+            started_at = end + grace;
+            end = end + grace + length;
+        }
+        if phases > 1 {
+            self.events.push(TimerEvent::CaughtUp {
+                phases,
+                sessions,
+                mode: self.mode,
+            });
+        }
+    }
+}
+
+/// The next time the local clock shows `time`, today or tomorrow.
+pub fn next_occurrence(time: NaiveTime) -> DateTime<Utc> {
+    let now = Local::now();
+    let mut date = now.date_naive();
+    if now.time() >= time {
+        date = date.succ_opt().unwrap_or(date);
+    }
+    Local
+        .from_local_datetime(&date.and_time(time))
+        .earliest()
+        .map_or_else(|| now.with_timezone(&Utc), |t| t.with_timezone(&Utc))
+}
@@ -0,0 +1,96 @@
+use egui::Color32;
+use tray_icon::{icon::Icon, TrayIcon, TrayIconBuilder};
+
+use crate::chart;
+
+/// The icon's side in pixels. Trays scale it to fit.
+const SIZE: u32 = 32;
+
+fn icon(minutes: Option<i64>, color: Color32) -> Option<Icon> {
+    Icon::from_rgba(chart::badge(minutes, color, SIZE).into_raw(), SIZE, SIZE)
+        .map_err(|e| tracing::warn!("could not make the tray icon: {e}"))
+        .ok()
+}
+
+fn build(minutes: Option<i64>, color: Color32, tooltip: &str) -> Result<TrayIcon, String> {
+    let mut builder = TrayIconBuilder::new().with_tooltip(tooltip);
+    if let Some(icon) = icon(minutes, color) {
+        builder = builder.with_icon(icon);
+    }
+    builder.build().map_err(|e| e.to_string())
+}
+
+fn update(tray: &mut TrayIcon, minutes: Option<i64>, color: Color32, tooltip: &str) {
+    if let Err(e) = tray.set_icon(icon(minutes, color)) {
+        tracing::warn!("could not update the tray icon: {e}");
+    }
+    let _ = tray.set_tooltip(Some(tooltip));
+}
+
+/// An icon in the system tray that shows the minutes left, so the timer can be read at
+/// a glance without opening anything.
+///
+/// GTK runs the tray on Linux, and winit doesn't run GTK's loop, so there the icon lives
+/// on a thread of its own. Elsewhere it must be made on the main thread.
+pub struct Tray {
+    #[cfg(target_os = "linux")]
+    updates: std::sync::mpsc::Sender<(Option<i64>, Color32, String)>,
+    #[cfg(not(target_os = "linux"))]
+    icon: TrayIcon,
+}
+
+impl Tray {
+    #[cfg(not(target_os = "linux"))]
+    pub fn new() -> Result<Self, String> {
+        Ok(Self {
+            icon: build(None, Color32::GRAY, "epomo")?,
+        })
+    }
+
+    #[cfg(target_os = "linux")]
+    pub fn new() -> Result<Self, String> {
+        use std::{sync::mpsc, time::Duration};
+
+        let (updates, received) = mpsc::channel::<(Option<i64>, Color32, String)>();
+        let (ready, started) = mpsc::channel();
+        std::thread::spawn(move || {
+            let tray = gtk::init()
+                .map_err(|e| e.to_string())
+                .and_then(|()| build(None, Color32::GRAY, "epomo"));
+            let mut tray = match tray {
+                Ok(tray) => {
+                    let _ = ready.send(Ok(()));
+                    tray
+                }
+                Err(e) => {
+                    let _ = ready.send(Err(e));
+                    return;
+                }
+            };
+            // Runs until the `Tray` is dropped, which drops the sender.
+            loop {
+                while gtk::events_pending() {
+                    gtk::main_iteration();
+                }
+                match received.recv_timeout(Duration::from_millis(100)) {
+                    Ok((minutes, color, tooltip)) => update(&mut tray, minutes, color, &tooltip),
+                    Err(mpsc::RecvTimeoutError::Timeout) => {}
+                    Err(mpsc::RecvTimeoutError::Disconnected) => break,
+                }
+            }
+        });
+        started
+            .recv()
+            .map_err(|_| "the tray thread stopped".to_owned())??;
+        Ok(Self { updates })
+    }
+
+    /// Shows `minutes` left on a disc of `color`, or no minutes when the timer is
+    /// stopped.
+    pub fn show(&mut self, minutes: Option<i64>, color: Color32, tooltip: &str) {
+        #[cfg(target_os = "linux")]
+        let _ = self.updates.send((minutes, color, tooltip.to_owned()));
+        #[cfg(not(target_os = "linux"))]
+        update(&mut self.icon, minutes, color, tooltip);
+    }
+}
@@ -0,0 +1,49 @@
+use std::io;
+
+const LATEST_RELEASE_URL: &str = "https://api.github.com/repos/tatupesonen/epomo/releases/latest";
+
+/// A published release.
+#[derive(Clone, serde::Deserialize, serde::Serialize)]
+pub struct Release {
+    /// The version number without the leading `v`, e.g. `0.3.0`.
+    pub version: String,
+    /// The release's page on GitHub.
+    pub url: String,
+}
+
+impl Release {
+    /// Whether this release is newer than the running build.
+    pub fn is_newer(&self) -> bool {
+        parse_version(&self.version) > parse_version(env!("CARGO_PKG_VERSION"))
+    }
+}
+
+/// `1.2.3` as numbers, ignoring any pre-release suffix.
+fn parse_version(version: &str) -> Vec<u64> {
+    version
+        .split(['-', '+'])
+        .next()
+        .unwrap_or_default()
+        .split('.')
+        .map(|part| part.parse().unwrap_or(0))
+        .collect()
+}
+
+#[derive(serde::Deserialize)]
+struct GithubRelease {
+    tag_name: String,
+    html_url: String,
+}
+
+/// Asks GitHub for the latest release. Blocks, so call this off the UI thread.
+pub fn latest() -> io::Result<Release> {
+    let release: GithubRelease = ureq::get(LATEST_RELEASE_URL)
+        .set("User-Agent", concat!("epomo/", env!("CARGO_PKG_VERSION")))
+        .call()
+        .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?
+        .into_json()?;
+    Ok(Release {
+        version: release.tag_name.trim_start_matches('v').to_owned(),
+        url: release.html_url,
+    })
+}
@@ -0,0 +1,88 @@
+use egui::{Button, Response, RichText, Ui, Widget};
+
+use crate::{clock::format_time, engine::Engine};
+
+/// The countdown and its Start/Pause/Skip/Stop buttons for an [`Engine`], laid out like
+/// epomo's own timer tab.
+///
+/// The widget ticks the engine itself, so all the host app has to do is show it every
+/// frame. Phase changes reach the engine's subscribers as usual.
+///
+/// ```no_run
+/// let mut engine = epomo::Engine::default();
+/// let events = engine.subscribe();
+/// # let ctx = egui::Context::default();
+/// # egui::CentralPanel::default().show(&ctx, |ui| {
+/// ui.add(epomo::widget::EpomoWidget::new(&mut engine));
+/// # });
+/// for event in events.try_iter() {
+///     println!("{event}");
+/// }
+/// ```
+pub struct EpomoWidget<'a> {
+    engine: &'a mut Engine,
+    text_size: f32,
+}
+
+impl<'a> EpomoWidget<'a> {
+    pub fn new(engine: &'a mut Engine) -> Self {
+        Self {
+            engine,
+            text_size: 32.0,
+        }
+    }
+
+    /// The countdown's font size. Defaults to 32.
+    pub fn text_size(mut self, size: f32) -> Self {
+        self.text_size = size;
+        self
+    }
+}
+
+impl Widget for EpomoWidget<'_> {
+    fn ui(self, ui: &mut Ui) -> Response {
+        let Self { engine, text_size } = self;
+        engine.tick();
+
+        ui.vertical(|ui| {
+            let color = engine.settings.color(engine.timer.mode);
+            let timer = &engine.timer;
+            let left = timer
+                .time_left()
+                .unwrap_or_else(|| timer.length(&engine.settings));
+            ui.label(RichText::new(timer.mode.to_string()).color(color));
+            ui.label(
+                RichText::new(format_time(left))
+                    .size(text_size)
+                    .color(color),
+            );
+            ui.horizontal(|ui| {
+                let timer = &engine.timer;
+                let active = timer.is_running() || timer.is_paused();
+                if timer.is_paused() {
+                    if ui.button("Resume").clicked() {
+                        engine.resume();
+                    }
+                } else if timer.is_running() {
+                    if ui.button("Pause").clicked() {
+                        engine.pause();
+                    }
+                } else if ui.button("Start").clicked() {
+                    engine.start();
+                }
+                if ui.add_enabled(active, Button::new("Skip")).clicked() {
+                    engine.skip();
+                }
+                if ui.add_enabled(active, Button::new("Stop")).clicked() {
+                    engine.stop();
+                }
+            });
+            if engine.timer.is_running() {
+                // Once a second is enough for a display in whole seconds.
+                ui.ctx()
+                    .request_repaint_after(std::time::Duration::from_secs(1));
+            }
+        })
+        .response
+    }
+}
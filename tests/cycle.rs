@@ -0,0 +1,365 @@
+use chrono::Duration;
+use epomo::{
+    events::TimerEvent,
+    settings::{LongBreakSkip, Settings},
+    timer::{PomodoroMode, Timer},
+    Engine,
+};
+use proptest::prelude::*;
+
+fn settings(work: i64, short: i64, long: i64) -> Settings {
+    let mut settings = Settings::default();
+    let profile = settings.profile_mut();
+    profile.interval_period = work;
+    profile.short_break_period = short;
+    profile.long_break_period = long;
+    settings
+}
+
+/// Moves the timer to the end of its phase, as if the time had gone by.
+fn run_out(timer: &mut Timer, settings: &mut Settings) -> bool {
+    if let Some(left) = timer.time_left() {
+        timer.fast_forward(left);
+    }
+    timer.tick(settings)
+}
+
+fn describe(events: &[TimerEvent]) -> String {
+    let lines: Vec<String> = events
+        .iter()
+        .map(|event| match event {
+            TimerEvent::PhaseStarted { mode, .. } => format!("started {mode}"),
+            TimerEvent::PhaseEnded { mode, next, .. } => format!("ended {mode}, next {next}"),
+            TimerEvent::Paused { mode, .. } => format!("paused {mode}"),
+            TimerEvent::Resumed { mode, .. } => format!("resumed {mode}"),
+            TimerEvent::Skipped { mode, next, .. } => format!("skipped {mode}, next {next}"),
+            TimerEvent::Stopped { mode, .. } => format!("stopped {mode}"),
+            TimerEvent::CountdownFinished { label, .. } => format!("{label} done"),
+            TimerEvent::CaughtUp { phases, .. } => format!("caught up on {phases} phases"),
+            TimerEvent::DailyLimitReached { sessions, .. } => {
+                format!("daily limit reached after {sessions} sessions")
+            }
+            TimerEvent::HeldForTheDay { .. } => "held for the day".to_owned(),
+        })
+        .collect();
+    lines.join("\n")
+}
+
+#[derive(Clone, Debug)]
+enum Op {
+    RunOut,
+    Skip,
+    Pause,
+    Resume,
+}
+
+fn op() -> impl Strategy<Value = Op> {
+    prop_oneof![
+        3 => Just(Op::RunOut),
+        1 => Just(Op::Skip),
+        1 => Just(Op::Pause),
+        1 => Just(Op::Resume),
+    ]
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(32))]
+
+    #[test]
+    fn long_break_after_every_nth_session(
+        work in 1i64..=120,
+        short in 1i64..=30,
+        long in 1i64..=120,
+        per_long_break in 1usize..=8,
+        phases in 1usize..24,
+    ) {
+        let mut settings = settings(work, short, long);
+        settings.profile_mut().sessions_per_long_break = per_long_break;
+        let mut timer = Timer::pomodoro();
+        timer.start(&mut settings);
+        for _ in 0..phases {
+            prop_assert!(run_out(&mut timer, &mut settings));
+            for event in timer.take_events() {
+                if let TimerEvent::PhaseEnded { mode: PomodoroMode::Work, next, .. } = event {
+                    let long_break = timer.session_count % per_long_break == 0;
+                    prop_assert_eq!(next == PomodoroMode::LongBreak, long_break);
+                    prop_assert_eq!(next == PomodoroMode::ShortBreak, !long_break);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn session_count_never_goes_down(ops in prop::collection::vec(op(), 1..32)) {
+        let mut settings = settings(25, 5, 15);
+        let mut timer = Timer::pomodoro();
+        timer.start(&mut settings);
+        for op in ops {
+            let before = timer.session_count;
+            match op {
+                Op::RunOut => {
+                    run_out(&mut timer, &mut settings);
+                }
+                Op::Skip => timer.skip(&mut settings),
+                Op::Pause => timer.pause(),
+                Op::Resume => timer.resume(),
+            }
+            prop_assert!(timer.session_count >= before);
+        }
+    }
+
+    #[test]
+    fn no_phase_skipped_after_a_gap(gap in 0i64..600, warmup in 0usize..8) {
+        let mut settings = settings(25, 5, 15);
+        let mut timer = Timer::pomodoro();
+        timer.start(&mut settings);
+        for _ in 0..warmup {
+            run_out(&mut timer, &mut settings);
+        }
+        timer.take_events();
+
+        let mut mode = timer.mode;
+        let left = timer.time_left().unwrap();
+        timer.fast_forward(left + Duration::minutes(gap));
+        prop_assert!(timer.tick(&mut settings));
+        // However many phases the gap covered, they must follow on from each other.
+        let mut ended = 0;
+        let mut caught_up = None;
+        for event in timer.take_events() {
+            match event {
+                TimerEvent::PhaseEnded { mode: from, next, .. } => {
+                    prop_assert!(from == mode);
+                    mode = next;
+                    ended += 1;
+                }
+                TimerEvent::PhaseStarted { mode: started, .. } => prop_assert!(started == mode),
+                TimerEvent::CaughtUp { phases, .. } => caught_up = Some(phases),
+                _ => {}
+            }
+        }
+        prop_assert!(timer.mode == mode);
+        prop_assert_eq!(caught_up, (ended > 1).then_some(ended));
+        // The phase running now is the one the gap ended in.
+        prop_assert!(timer.time_left().unwrap() > Duration::zero());
+    }
+}
+
+#[test]
+fn full_cycle() {
+    let mut settings = settings(25, 5, 15);
+    let mut timer = Timer::pomodoro();
+    timer.start(&mut settings);
+    for _ in 0..8 {
+        assert!(run_out(&mut timer, &mut settings));
+    }
+    timer.stop();
+    insta::assert_snapshot!(describe(&timer.take_events()), @r###"
+    started Work
+    ended Work, next Short break
+    started Short break
+    ended Short break, next Work
+    started Work
+    ended Work, next Short break
+    started Short break
+    ended Short break, next Work
+    started Work
+    ended Work, next Short break
+    started Short break
+    ended Short break, next Work
+    started Work
+    ended Work, next Long break
+    started Long break
+    ended Long break, next Work
+    started Work
+    stopped Work
+    "###);
+}
+
+#[test]
+fn pause_skip_and_stop() {
+    let mut settings = settings(25, 5, 15);
+    let mut timer = Timer::pomodoro();
+    timer.start(&mut settings);
+    timer.pause();
+    // A paused phase doesn't run out.
+    assert!(!run_out(&mut timer, &mut settings));
+    timer.resume();
+    timer.skip(&mut settings);
+    timer.skip(&mut settings);
+    timer.stop();
+    insta::assert_snapshot!(describe(&timer.take_events()), @r###"
+    started Work
+    paused Work
+    resumed Work
+    skipped Work, next Short break
+    started Short break
+    skipped Short break, next Work
+    started Work
+    stopped Work
+    "###);
+    assert_eq!(timer.session_count, 0);
+}
+
+#[test]
+fn grace_period_holds_back_the_next_phase() {
+    let mut settings = settings(25, 5, 15);
+    settings.grace_secs = 10;
+    let mut timer = Timer::pomodoro();
+    timer.start(&mut settings);
+    assert!(run_out(&mut timer, &mut settings));
+    assert_eq!(timer.mode, PomodoroMode::ShortBreak);
+    // The break doesn't start counting down until the grace period is over.
+    let grace = timer.grace_left().unwrap();
+    assert!(grace > Duration::seconds(9) && grace <= Duration::seconds(10));
+    assert!(timer.time_left().unwrap() > Duration::minutes(5) - Duration::seconds(1));
+    timer.fast_forward(grace);
+    assert!(timer.grace_left().is_none());
+    assert!(!timer.tick(&mut settings));
+    assert!(timer.time_left().unwrap() <= Duration::minutes(5));
+}
+
+#[test]
+fn skipped_long_break_ends_the_cycle_or_carries_over() {
+    for (policy, after_next_session) in [
+        (LongBreakSkip::EndCycle, PomodoroMode::ShortBreak),
+        (LongBreakSkip::CarryOver, PomodoroMode::LongBreak),
+    ] {
+        let mut settings = settings(25, 5, 15);
+        settings.profile_mut().sessions_per_long_break = 2;
+        settings.long_break_skip = policy;
+        let mut timer = Timer::pomodoro();
+        timer.start(&mut settings);
+        for _ in 0..3 {
+            assert!(run_out(&mut timer, &mut settings));
+        }
+        assert_eq!(timer.mode, PomodoroMode::LongBreak);
+        timer.skip(&mut settings);
+        assert!(run_out(&mut timer, &mut settings));
+        assert_eq!(timer.mode, after_next_session);
+        // Either way, the cycle goes on as usual from there.
+        assert!(run_out(&mut timer, &mut settings));
+        assert!(run_out(&mut timer, &mut settings));
+        assert_eq!(timer.mode, PomodoroMode::LongBreak);
+    }
+}
+
+#[test]
+fn daily_limit_warns_and_can_hold_work_back() {
+    for hold in [false, true] {
+        let mut settings = settings(25, 5, 15);
+        settings.daily_max_sessions = 2;
+        settings.hold_at_daily_limit = hold;
+        let mut timer = Timer::pomodoro();
+        timer.start(&mut settings);
+        for _ in 0..2 {
+            assert!(run_out(&mut timer, &mut settings));
+        }
+        timer.take_events();
+        // The second work session reaches the limit.
+        assert!(run_out(&mut timer, &mut settings));
+        assert_eq!(
+            describe(&timer.take_events()),
+            "ended Work, next Short break\ndaily limit reached after 2 sessions\nstarted Short break"
+        );
+        assert!(run_out(&mut timer, &mut settings));
+        assert_eq!(timer.mode, PomodoroMode::Work);
+        assert_eq!(timer.is_running(), !hold);
+        let then = if hold {
+            "held for the day"
+        } else {
+            "started Work"
+        };
+        assert_eq!(
+            describe(&timer.take_events()),
+            format!("ended Short break, next Work\n{then}")
+        );
+    }
+}
+
+#[test]
+fn jittered_work_phases_are_recorded_as_long_as_they_ran() {
+    let mut settings = settings(25, 5, 15);
+    settings.jitter_minutes = 5;
+    for _ in 0..20 {
+        let mut timer = Timer::pomodoro();
+        timer.start(&mut settings);
+        let length = timer.time_left().unwrap();
+        assert!(length > Duration::minutes(19) && length <= Duration::minutes(30));
+        assert!(run_out(&mut timer, &mut settings));
+        let recorded = timer
+            .take_events()
+            .into_iter()
+            .find_map(|event| match event {
+                TimerEvent::PhaseEnded {
+                    started_at,
+                    ended_at,
+                    ..
+                } => Some(ended_at - started_at),
+                _ => None,
+            })
+            .unwrap();
+        assert!((recorded - length).num_seconds().abs() <= 1);
+        // Breaks keep their length.
+        let short_break = timer.time_left().unwrap();
+        assert!(short_break > Duration::minutes(5) - Duration::seconds(1));
+        assert!(short_break <= Duration::minutes(5));
+    }
+}
+
+#[test]
+fn forecasts_the_next_long_break() {
+    let mut settings = settings(25, 5, 15);
+    settings.profile_mut().sessions_per_long_break = 2;
+    let mut timer = Timer::pomodoro();
+    assert!(timer.long_break_forecast(&settings).is_none());
+    let close_to = |a: chrono::DateTime<chrono::Utc>, minutes| {
+        (a - chrono::Utc::now() - Duration::minutes(minutes))
+            .num_seconds()
+            .abs()
+            <= 1
+    };
+
+    timer.start(&mut settings);
+    let (start, end) = timer.long_break_forecast(&settings).unwrap();
+    // Work, short break, work.
+    assert!(close_to(start, 55));
+    assert_eq!(end - start, Duration::minutes(15));
+
+    // A skipped work session doesn't count, so there are two more to go.
+    timer.skip(&mut settings);
+    let (start, _) = timer.long_break_forecast(&settings).unwrap();
+    assert!(close_to(start, 60));
+}
+
+#[test]
+fn countdown_stops_when_done() {
+    let mut settings = settings(25, 5, 15);
+    let mut timer = Timer::countdown("Laundry".to_owned(), 40);
+    timer.start(&mut settings);
+    assert!(run_out(&mut timer, &mut settings));
+    assert!(!timer.is_running());
+    insta::assert_snapshot!(describe(&timer.take_events()), @"Laundry done");
+}
+
+#[test]
+fn engine_hands_events_to_channels_and_callbacks() {
+    let mut engine = Engine::new(settings(25, 5, 15));
+    let events = engine.subscribe();
+    let (tx, called) = std::sync::mpsc::channel();
+    engine.on_event(move |event| {
+        let _ = tx.send(event.clone());
+    });
+    engine.start();
+    let left = engine.timer.time_left().unwrap();
+    engine.timer.fast_forward(left);
+    assert!(engine.tick());
+    engine.stop();
+    let received: Vec<TimerEvent> = events.try_iter().collect();
+    assert_eq!(received, called.try_iter().collect::<Vec<_>>());
+    insta::assert_snapshot!(describe(&received), @r###"
+    started Work
+    ended Work, next Short break
+    started Short break
+    stopped Short break
+    "###);
+}